@@ -0,0 +1,67 @@
+extern crate colorbuf;
+extern crate grafizo;
+
+use grafizo::path::{Curve, Path};
+
+extern crate png;
+
+use png::HasParameters;
+
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+
+fn main() {
+    let background = colorbuf::Color {
+        r: 0.0f32,
+        g: 0.0f32,
+        b: 0.0f32,
+        a: 1.0f32,
+    };
+    let foreground = colorbuf::Color {
+        r: 1.0f32,
+        g: 1.0f32,
+        b: 1.0f32,
+        a: 1.0f32,
+    };
+    let mut canvas = grafizo::Canvas::new(800, 600, background);
+    canvas.set_draw_color(foreground);
+
+    let begin = grafizo::vector::Point2::new(100f32, 300f32);
+    let middle = grafizo::vector::Point2::new(400f32, 300f32);
+    let control = grafizo::vector::Point2::new(550f32, 100f32);
+    let end = grafizo::vector::Point2::new(700f32, 300f32);
+
+    let line: Box<dyn Curve> = Box::new(grafizo::path::Line::new(begin, middle));
+    let mut path = grafizo::path::OpenMultiPath::new(vec![line]);
+    path.push(Box::new(grafizo::path::QuadBezierCurve::new(
+        middle, control, end,
+    )));
+    path.stroke(&mut canvas, 10f32);
+
+    let mut buf = [0xFFu8; 800 * 600 * 4];
+
+    let mut stride = 0;
+    canvas
+        .to_bytebuffer(
+            &mut buf[..],
+            colorbuf::bitmap::ColorFormat::RGBA,
+            colorbuf::bitmap::BitDepth::Eight,
+            &mut stride,
+        )
+        .unwrap();
+
+    // Make the data into a PNG
+    let mut curr_path: PathBuf = std::env::current_dir().expect("Couldn't get current directory");
+    curr_path.push(r"multipath1.png");
+    let path = curr_path.as_path();
+    let file = File::create(path).unwrap();
+
+    let ref mut file_writer = BufWriter::new(file);
+
+    let mut encoder = png::Encoder::new(file_writer, 800, 600);
+    encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+    let mut png_writer = encoder.write_header().unwrap();
+
+    png_writer.write_image_data(&buf).unwrap();
+}
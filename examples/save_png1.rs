@@ -0,0 +1,37 @@
+extern crate colorbuf;
+extern crate grafizo;
+
+use grafizo::path::{Loop, Path};
+
+use std::path::PathBuf;
+
+fn main() {
+    let background = colorbuf::Color {
+        r: 0.0f32,
+        g: 0.0f32,
+        b: 0.0f32,
+        a: 1.0f32,
+    };
+    let foreground = colorbuf::Color {
+        r: 1.0f32,
+        g: 1.0f32,
+        b: 1.0f32,
+        a: 1.0f32,
+    };
+    let mut canvas = grafizo::Canvas::new(800, 600, background);
+    canvas.set_draw_color(foreground);
+
+    let begin = grafizo::vector::Point2::new(100f32, 200f32);
+    let end = grafizo::vector::Point2::new(500f32, 300f32);
+
+    let line = grafizo::path::Line::new(begin, end);
+    line.stroke(&mut canvas, 10f32);
+
+    let center = grafizo::vector::Point2::new(400f32, 400f32);
+    let circle = grafizo::path::Circle::new(center, 50f32);
+    circle.fill(&mut canvas);
+
+    let mut curr_path: PathBuf = std::env::current_dir().expect("Couldn't get current directory");
+    curr_path.push(r"save_png1.png");
+    canvas.save_png(curr_path).unwrap();
+}
@@ -0,0 +1,99 @@
+use std::ops;
+
+/// An angle, which can be made from and turned into both radians and degrees.
+///
+/// Angles aren't wrapped around to a single turn, so adding angles up keeps
+/// going past a full turn. Use `normalized` to bring an angle within one turn.
+#[derive(Debug, Copy, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Angle(f32);
+
+impl Angle {
+    pub fn from_radians(radians: f32) -> Angle {
+        Angle { 0: radians }
+    }
+
+    pub fn from_degrees(degrees: f32) -> Angle {
+        Angle {
+            0: degrees.to_radians(),
+        }
+    }
+
+    pub fn as_radians(&self) -> f32 {
+        self.0
+    }
+
+    pub fn as_degrees(&self) -> f32 {
+        self.0.to_degrees()
+    }
+
+    /// Gets the angle pointing in the same direction as this one, within `[0, 2 * pi)`.
+    pub fn normalized(&self) -> Angle {
+        Angle {
+            0: self.0.rem_euclid(2f32 * std::f32::consts::PI),
+        }
+    }
+}
+
+impl ops::Add<Angle> for Angle {
+    type Output = Angle;
+
+    fn add(self, rhs: Angle) -> Angle {
+        Angle { 0: self.0 + rhs.0 }
+    }
+}
+
+impl ops::Neg for Angle {
+    type Output = Angle;
+
+    fn neg(self) -> Angle {
+        Angle { 0: -self.0 }
+    }
+}
+
+impl ops::Sub<Angle> for Angle {
+    type Output = Angle;
+
+    fn sub(self, rhs: Angle) -> Angle {
+        self + (-rhs)
+    }
+}
+
+impl ops::Mul<f32> for Angle {
+    type Output = Angle;
+
+    fn mul(self, rhs: f32) -> Angle {
+        Angle { 0: self.0 * rhs }
+    }
+}
+
+impl ops::Div<f32> for Angle {
+    type Output = Angle;
+
+    fn div(self, rhs: f32) -> Angle {
+        Angle { 0: self.0 / rhs }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conversions_and_arithmetic() {
+        let half_turn = Angle::from_degrees(180f32).as_radians();
+        assert!((half_turn - std::f32::consts::PI).abs() < 1e-6);
+        let right = Angle::from_radians(std::f32::consts::FRAC_PI_2).as_degrees();
+        assert!((right - 90f32).abs() < 1e-4);
+
+        // Adding accumulates past a full turn until the angle gets normalized.
+        let sum = Angle::from_degrees(270f32) + Angle::from_degrees(180f32);
+        assert!((sum.as_degrees() - 450f32).abs() < 1e-3);
+        assert!((sum.normalized().as_degrees() - 90f32).abs() < 1e-3);
+        let negative = -Angle::from_degrees(90f32);
+        assert!((negative.normalized().as_degrees() - 270f32).abs() < 1e-3);
+
+        let scaled = (Angle::from_degrees(90f32) * 2f32 - Angle::from_degrees(30f32)) / 5f32;
+        assert!((scaled.as_degrees() - 30f32).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,293 @@
+//! Helpers for working with colors, including colors given in other color
+//! models than RGB.
+//!
+//! Hues are in degrees and wrap around at 360, while the other
+//! components are clamped to be within `[0, 1]`.
+
+/// Creates a color from its hue, saturation, lightness and alpha.
+pub fn color_from_hsl(h: f32, s: f32, l: f32, a: f32) -> colorbuf::Color {
+    let s = helper_clamp_unit(s);
+    let l = helper_clamp_unit(l);
+    let chroma = (1f32 - (2f32 * l - 1f32).abs()) * s;
+    helper_color_from_chroma(h, chroma, l - chroma / 2f32, a)
+}
+
+/// Creates a color from its hue, saturation, value and alpha.
+pub fn color_from_hsv(h: f32, s: f32, v: f32, a: f32) -> colorbuf::Color {
+    let s = helper_clamp_unit(s);
+    let v = helper_clamp_unit(v);
+    let chroma = v * s;
+    helper_color_from_chroma(h, chroma, v - chroma, a)
+}
+
+/// Gets the hue, saturation, lightness and alpha of a color, in that order.
+///
+/// Grays have no hue of their own, and they get a hue of 0.
+pub fn color_to_hsl(color: &colorbuf::Color) -> [f32; 4] {
+    let r = helper_clamp_unit(color.r);
+    let g = helper_clamp_unit(color.g);
+    let b = helper_clamp_unit(color.b);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let l = (max + min) / 2f32;
+    let s = if chroma > 0f32 {
+        chroma / (1f32 - (2f32 * l - 1f32).abs())
+    } else {
+        0f32
+    };
+    [helper_hue(r, g, b, max, chroma), s, l, color.a]
+}
+
+/// Gets the hue, saturation, value and alpha of a color, in that order.
+///
+/// Grays have no hue of their own, and they get a hue of 0.
+pub fn color_to_hsv(color: &colorbuf::Color) -> [f32; 4] {
+    let r = helper_clamp_unit(color.r);
+    let g = helper_clamp_unit(color.g);
+    let b = helper_clamp_unit(color.b);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let chroma = max - min;
+
+    let s = if max > 0f32 { chroma / max } else { 0f32 };
+    [helper_hue(r, g, b, max, chroma), s, max, color.a]
+}
+
+/// Creates an opaque color from 8-bit channels.
+///
+/// The channels are just divided by 255, so they stay in the same gamma-encoded
+/// space they're usually given in, which is also what the canvases expect.
+pub fn color_rgb8(r: u8, g: u8, b: u8) -> colorbuf::Color {
+    color_rgba8(r, g, b, 255)
+}
+
+/// Creates a color from 8-bit channels, which work like they do with `color_rgb8`.
+/// The alpha is linear, like alphas always are.
+pub fn color_rgba8(r: u8, g: u8, b: u8, a: u8) -> colorbuf::Color {
+    let channel = |c: u8| f32::from(c) / 255f32;
+    colorbuf::Color {
+        r: channel(r),
+        g: channel(g),
+        b: channel(b),
+        a: channel(a),
+    }
+}
+
+/// The reasons why a hex color couldn't be parsed.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum HexColorError {
+    /// The color doesn't start with a `#`.
+    MissingHash,
+    /// The color has some other amount of digits than 6 or 8.
+    InvalidLength,
+    /// The color has something else than hexadecimal digits after the `#`.
+    InvalidDigit,
+}
+
+/// Parses a color given as `#RRGGBB` or `#RRGGBBAA`, with the channels being
+/// 8-bit like with `color_rgba8`. The digits may be either upper or lower case.
+pub fn color_from_hex(hex: &str) -> Result<colorbuf::Color, HexColorError> {
+    let digits = hex.strip_prefix('#').ok_or(HexColorError::MissingHash)?;
+    if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(HexColorError::InvalidDigit);
+    }
+    if digits.len() != 6 && digits.len() != 8 {
+        return Err(HexColorError::InvalidLength);
+    }
+
+    // All of the digits are ASCII, so every byte is a character of its own.
+    let channel = |i: usize| u8::from_str_radix(&digits[2 * i..2 * i + 2], 16).unwrap();
+    let a = if digits.len() == 8 { channel(3) } else { 255 };
+    Ok(color_rgba8(channel(0), channel(1), channel(2), a))
+}
+
+/// Tells whether all four channels of the colors are within `epsilon` of each other,
+/// which is handy for comparing rendered colors against expected ones.
+///
+/// Channels which are NaN are never equal to anything.
+pub fn colors_approx_eq(a: &colorbuf::Color, b: &colorbuf::Color, epsilon: f32) -> bool {
+    let close = |x: f32, y: f32| !x.is_nan() && !y.is_nan() && (x - y).abs() <= epsilon;
+    close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b) && close(a.a, b.a)
+}
+
+/// Multiplies the color channels of a color by its alpha, which is how the pixels of
+/// canvases are stored.
+pub fn premultiply(color: &colorbuf::Color) -> colorbuf::Color {
+    colorbuf::Color {
+        r: color.r * color.a,
+        g: color.g * color.a,
+        b: color.b * color.a,
+        a: color.a,
+    }
+}
+
+/// The inverse of `premultiply`. Transparent colors have no color
+/// left to recover, so they come out as transparent black.
+pub fn unpremultiply(color: &colorbuf::Color) -> colorbuf::Color {
+    if color.a <= 0f32 {
+        return colorbuf::Color {
+            r: 0f32,
+            g: 0f32,
+            b: 0f32,
+            a: 0f32,
+        };
+    }
+
+    colorbuf::Color {
+        r: color.r / color.a,
+        g: color.g / color.a,
+        b: color.b / color.a,
+        a: color.a,
+    }
+}
+
+/// Builds the color with the given hue and chroma, with `m` added to every channel.
+fn helper_color_from_chroma(h: f32, chroma: f32, m: f32, a: f32) -> colorbuf::Color {
+    let h = if h.is_finite() {
+        h.rem_euclid(360f32)
+    } else {
+        0f32
+    };
+    let sector = h / 60f32;
+    let x = chroma * (1f32 - (sector % 2f32 - 1f32).abs());
+
+    let (r, g, b) = match sector as u32 {
+        0 => (chroma, x, 0f32),
+        1 => (x, chroma, 0f32),
+        2 => (0f32, chroma, x),
+        3 => (0f32, x, chroma),
+        4 => (x, 0f32, chroma),
+        _ => (chroma, 0f32, x),
+    };
+
+    colorbuf::Color {
+        r: r + m,
+        g: g + m,
+        b: b + m,
+        a: helper_clamp_unit(a),
+    }
+}
+
+fn helper_hue(r: f32, g: f32, b: f32, max: f32, chroma: f32) -> f32 {
+    if chroma <= 0f32 {
+        return 0f32;
+    }
+
+    let sector = if max == r {
+        ((g - b) / chroma).rem_euclid(6f32)
+    } else if max == g {
+        (b - r) / chroma + 2f32
+    } else {
+        (r - g) / chroma + 4f32
+    };
+    sector * 60f32
+}
+
+fn helper_clamp_unit(value: f32) -> f32 {
+    value.clamp(0f32, 1f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(color: &colorbuf::Color) -> [f32; 4] {
+        [color.r, color.g, color.b, color.a]
+    }
+
+    #[test]
+    fn hsl_and_hsv() {
+        let red = color_from_hsl(0f32, 1f32, 0.5, 1f32);
+        assert_eq!(channels(&red), [1f32, 0f32, 0f32, 1f32]);
+        // The hue wraps around, and the rest gets clamped.
+        let red = color_from_hsl(360f32, 1f32, 0.5, 2f32);
+        assert_eq!(channels(&red), [1f32, 0f32, 0f32, 1f32]);
+        let green = color_from_hsv(-240f32, 1f32, 1f32, 1f32);
+        assert_eq!(channels(&green), [0f32, 1f32, 0f32, 1f32]);
+        let white = color_from_hsv(200f32, 0f32, 1.5, 1f32);
+        assert_eq!(channels(&white), [1f32, 1f32, 1f32, 1f32]);
+
+        for i in 0..36 {
+            let hue = i as f32 * 10f32;
+            let [h, s, l, a] = color_to_hsl(&color_from_hsl(hue, 0.7, 0.4, 0.3));
+            assert!((h - hue).abs() < 1e-3, "{} vs {}", h, hue);
+            assert!((s - 0.7).abs() < 1e-4 && (l - 0.4).abs() < 1e-4 && a == 0.3);
+            let [h, s, v, _] = color_to_hsv(&color_from_hsv(hue, 0.7, 0.4, 0.3));
+            assert!((h - hue).abs() < 1e-3, "{} vs {}", h, hue);
+            assert!((s - 0.7).abs() < 1e-4 && (v - 0.4).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn approximate_equality() {
+        let gray = color_rgb8(128, 128, 128);
+        let close = colorbuf::Color {
+            r: gray.r + 1e-4,
+            g: gray.g - 1e-4,
+            ..gray
+        };
+        assert!(colors_approx_eq(&gray, &close, 1e-3));
+        assert!(!colors_approx_eq(&gray, &close, 1e-5));
+        assert!(colors_approx_eq(&gray, &gray, 0f32));
+
+        // NaN channels are never equal, not even to themselves.
+        let nan = colorbuf::Color {
+            r: f32::NAN,
+            ..gray
+        };
+        assert!(!colors_approx_eq(&nan, &nan, 1f32));
+        assert!(!colors_approx_eq(&gray, &nan, f32::INFINITY));
+    }
+
+    #[test]
+    fn hex_colors() {
+        let red = color_from_hex("#ff0000").unwrap();
+        assert_eq!(channels(&red), [1f32, 0f32, 0f32, 1f32]);
+        let green = color_from_hex("#00FF0080").unwrap();
+        assert_eq!(channels(&green), channels(&color_rgba8(0, 255, 0, 128)));
+        assert_eq!(channels(&color_rgb8(255, 255, 255)), [1f32; 4]);
+
+        assert_eq!(
+            color_from_hex("ff0000").err(),
+            Some(HexColorError::MissingHash)
+        );
+        assert_eq!(
+            color_from_hex("#ff00").err(),
+            Some(HexColorError::InvalidLength)
+        );
+        assert_eq!(
+            color_from_hex("#gg0000").err(),
+            Some(HexColorError::InvalidDigit)
+        );
+        assert_eq!(
+            color_from_hex("#ffé000").err(),
+            Some(HexColorError::InvalidDigit)
+        );
+    }
+
+    #[test]
+    fn premultiply_round_trip() {
+        for i in 0..=20 {
+            let alpha = i as f32 / 20f32;
+            let color = colorbuf::Color {
+                r: 0.9,
+                g: 0.3,
+                b: 0.05,
+                a: alpha,
+            };
+            let premultiplied = premultiply(&color);
+            assert!((premultiplied.r - 0.9 * alpha).abs() < 1e-6);
+            assert_eq!(premultiplied.a, alpha);
+
+            // Without any alpha the color is lost, but nothing gets divided by zero.
+            let back = unpremultiply(&premultiplied);
+            if alpha == 0f32 {
+                assert_eq!(channels(&back), [0f32; 4]);
+            } else {
+                assert!(colors_approx_eq(&back, &color, 1e-5));
+            }
+        }
+    }
+}
@@ -1,17 +1,34 @@
 extern crate colorbuf;
 
-use std::collections::HashMap;
+use std::convert::TryFrom;
 use std::result::Result;
 
 use colorbuf::ColorBuf;
 
+pub mod angle;
+pub mod color;
+pub mod mask;
+pub mod paint;
 pub mod path;
+pub mod scene;
+pub mod svg;
+pub mod tessellate;
+pub mod transform;
 pub mod vector;
 
+use self::mask::{AlphaBuf, MaskCanvas};
+use self::paint::Paint;
+use self::path::{Curve, FillRule, LineCap, LineJoin, Loop, Path, StrokeAlign};
+use self::transform::Transform2;
 use self::vector::{Point2, Vector2};
 
+/// The pixels of a canvas.
+///
+/// The colors are stored with their color channels premultiplied by their alpha,
+/// but they go in and come out of the `ColorBuf` interface as straight colors.
+#[derive(Clone)]
 pub struct CanvasColorBuf {
-    buf: HashMap<[u64; 2], colorbuf::Color>,
+    buf: Vec<colorbuf::Color>,
     width: u64,
     height: u64,
 }
@@ -22,8 +39,7 @@ impl colorbuf::ColorBuf for CanvasColorBuf {
             return Err(colorbuf::ColorBufError::InvalidCoordinate);
         }
 
-        let entry = [x, y];
-        Ok(self.buf.get(&entry).unwrap().clone())
+        Ok(color::unpremultiply(&self.buf[self.index(x, y)]))
     }
 
     fn set_pixel(
@@ -36,8 +52,8 @@ impl colorbuf::ColorBuf for CanvasColorBuf {
             return Err(colorbuf::ColorBufError::InvalidCoordinate);
         }
 
-        let entry = [x, y];
-        self.buf.insert(entry, color.clone());
+        let index = self.index(x, y);
+        self.buf[index] = color::premultiply(color);
         Ok(())
     }
 
@@ -52,123 +68,964 @@ impl colorbuf::ColorBuf for CanvasColorBuf {
 
 impl CanvasColorBuf {
     fn new(width: u64, height: u64, color: colorbuf::Color) -> CanvasColorBuf {
-        let mut ret = CanvasColorBuf {
-            buf: HashMap::new(),
+        CanvasColorBuf {
+            buf: vec![color::premultiply(&color); helper_pixel_count(width, height)],
             width: width,
             height: height,
-        };
+        }
+    }
+
+    /// Creates a buffer with the given size but without any pixels, for canvases
+    /// which never get to write to their pixels. Trying to use the pixels panics.
+    fn without_pixels(width: u64, height: u64) -> CanvasColorBuf {
+        CanvasColorBuf {
+            buf: Vec::new(),
+            width,
+            height,
+        }
+    }
 
-        for x in 0..width {
-            for y in 0..height {
-                let entry = [x, y];
-                ret.buf.insert(entry, color.clone());
+    /// Creates a buffer where every pixel is transparent black.
+    ///
+    /// Unlike `new`, this doesn't have to write every pixel, since it can ask for
+    /// memory that has already been zeroed, which is a lot faster for large buffers.
+    fn new_zeroed(width: u64, height: u64) -> CanvasColorBuf {
+        let len = helper_pixel_count(width, height);
+        let buf = if len == 0 {
+            Vec::new()
+        } else {
+            let layout =
+                std::alloc::Layout::array::<colorbuf::Color>(len).expect("canvas is too large");
+            // SAFETY: The layout isn't zero-sized, and is exactly what a Vec of `len`
+            // colors would allocate. A color only consists of `f32`s, for which every
+            // bit pattern is valid, so the zeroed memory is `len` initialized colors.
+            unsafe {
+                let ptr = std::alloc::alloc_zeroed(layout) as *mut colorbuf::Color;
+                if ptr.is_null() {
+                    std::alloc::handle_alloc_error(layout);
+                }
+                Vec::from_raw_parts(ptr, len, len)
             }
+        };
+
+        CanvasColorBuf { buf, width, height }
+    }
+
+    // The pixels are stored row by row.
+    fn index(&self, x: u64, y: u64) -> usize {
+        (y * self.width + x) as usize
+    }
+
+    /// Sets the pixels on row `y` from `x_start` up to but not including `x_end`.
+    /// The parts of the run outside of the buffer are left out.
+    fn set_span(&mut self, y: u64, x_start: u64, x_end: u64, color: &colorbuf::Color) {
+        let x_end = x_end.min(self.width);
+        if y >= self.height || x_start >= x_end {
+            return;
+        }
+
+        let (start, end) = (self.index(x_start, y), self.index(x_end, y));
+        let color = color::premultiply(color);
+        for pixel in self.buf[start..end].iter_mut() {
+            *pixel = color;
         }
+    }
+}
+
+/// The reasons why a canvas couldn't be created from a byte buffer, or written back into one.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BytebufferError {
+    /// The length of the buffer doesn't match the dimensions and the pixel format.
+    LengthMismatch,
+    /// Decoding the given color format and bit depth isn't supported.
+    UnsupportedFormat,
+}
+
+/// A pixel of a canvas which can be changed through `Canvas::pixels_mut`.
+///
+/// This works like a mutable reference to the straight color of the pixel, and
+/// the pixel gets the color it has been changed to once this is dropped.
+pub struct PixelMut<'a> {
+    pixel: &'a mut colorbuf::Color,
+    color: colorbuf::Color,
+}
+
+impl<'a> std::ops::Deref for PixelMut<'a> {
+    type Target = colorbuf::Color;
+
+    fn deref(&self) -> &colorbuf::Color {
+        &self.color
+    }
+}
+
+impl<'a> std::ops::DerefMut for PixelMut<'a> {
+    fn deref_mut(&mut self) -> &mut colorbuf::Color {
+        &mut self.color
+    }
+}
+
+impl<'a> Drop for PixelMut<'a> {
+    fn drop(&mut self) {
+        // The canvas stores its colors premultiplied, unlike what we hand out.
+        *self.pixel = color::premultiply(&self.color);
+    }
+}
+
+/// A canvas drawing into a buffer of 8-bit RGBA bytes owned by someone else, made
+/// with `Canvas::from_slice`.
+///
+/// This isn't zero-copy. The canvas keeps pixels of its own to draw onto, since it
+/// blends in premultiplied floating point and 8-bit pixels would lose precision with
+/// every blend, and all of them get converted and written into the buffer after each
+/// call to `draw`. That way the buffer always has what has been drawn without
+/// exporting the canvas separately. The canvas can be looked at through this at any
+/// time, but it can only be changed through `draw`.
+pub struct SliceCanvas<'a> {
+    canvas: Canvas,
+    bytes: &'a mut [u8],
+}
+
+impl<'a> std::ops::Deref for SliceCanvas<'a> {
+    type Target = Canvas;
+
+    fn deref(&self) -> &Canvas {
+        &self.canvas
+    }
+}
+
+impl<'a> SliceCanvas<'a> {
+    /// Draws onto the canvas with `draw`, and then writes the pixels into the buffer.
+    ///
+    /// If `draw` changed the size of the canvas, its pixels no longer fit into the
+    /// buffer, so the buffer is left as it was and this gives
+    /// `BytebufferError::LengthMismatch`.
+    pub fn draw<F: FnOnce(&mut Canvas)>(&mut self, draw: F) -> Result<(), BytebufferError> {
+        draw(&mut self.canvas);
 
-        ret
+        let backing = &self.canvas.backing;
+        if helper_pixel_count(backing.width, backing.height) * 4 != self.bytes.len() {
+            return Err(BytebufferError::LengthMismatch);
+        }
+        // The buffer gets the exact pixels, so they aren't dithered like when exporting.
+        for (color, pixel) in backing.buf.iter().zip(self.bytes.chunks_mut(4)) {
+            let color = color::unpremultiply(color);
+            let quantize = |channel: f32| (channel.clamp(0f32, 1f32) * 255f32).round() as u8;
+            pixel[0] = quantize(color.r);
+            pixel[1] = quantize(color.g);
+            pixel[2] = quantize(color.b);
+            pixel[3] = quantize(color.a);
+        }
+        Ok(())
     }
 }
 
+/// The ways of combining the colors of a source drawn over a destination.
+///
+/// The modes only decide the color where both of them are there, and
+/// elsewhere the source just goes over the destination.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// The source covers the destination.
+    SourceOver,
+    /// The channels get multiplied, which only ever darkens.
+    Multiply,
+    /// The inverses of the channels get multiplied, which only ever brightens.
+    Screen,
+    /// The channels get added together, up to the full intensity.
+    Add,
+    /// Multiplies the dark parts and screens the light parts of the destination.
+    Overlay,
+}
+
 pub struct Canvas {
     backing: CanvasColorBuf,
-    current_color: colorbuf::Color,
+    background_color: colorbuf::Color,
+    paint: Paint,
+    clip: Option<[u64; 4]>,
     antialias_enabled: bool,
+    conservative: bool,
+    aa_samples: u32,
+    gamma: f32,
+    global_alpha: f32,
+    dither: bool,
+    line_cap: LineCap,
+    line_join: LineJoin,
+    miter_limit: f32,
+    stroke_align: StrokeAlign,
+    dash: Option<Vec<f32>>,
+    flattening_tolerance: f32,
+    fill_rule: FillRule,
+    coverage_layer: Option<AlphaBuf>,
+    transform: Transform2,
+    supersample: u32,
 }
 
 impl Canvas {
+    /// Creates a canvas filled with `color`, which is also the background and the draw
+    /// color of the canvas.
+    ///
+    /// A canvas can have a width or a height of zero, in which case it has no pixels
+    /// and drawing onto it does nothing.
     pub fn new(width: u64, height: u64, color: colorbuf::Color) -> Canvas {
         Canvas {
             backing: CanvasColorBuf::new(width, height, color),
-            current_color: color,
+            background_color: color,
+            paint: Paint::Solid(color),
+            clip: None,
             antialias_enabled: true,
+            conservative: false,
+            aa_samples: 16,
+            gamma: 2.2f32,
+            global_alpha: 1f32,
+            dither: false,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 10f32,
+            stroke_align: StrokeAlign::Center,
+            dash: None,
+            flattening_tolerance: path::DEFAULT_TOLERANCE,
+            fill_rule: FillRule::EvenOdd,
+            coverage_layer: None,
+            transform: Transform2::identity(),
+            supersample: 1,
+        }
+    }
+
+    /// Creates a canvas without filling it with a color, which is faster for large
+    /// canvases that are going to be drawn over anyway.
+    ///
+    /// All pixels which haven't been drawn to are transparent black, which is also
+    /// the background and the draw color of the canvas.
+    pub fn new_uninitialized(width: u64, height: u64) -> Canvas {
+        let transparent = colorbuf::Color {
+            r: 0f32,
+            g: 0f32,
+            b: 0f32,
+            a: 0f32,
+        };
+        Canvas {
+            backing: CanvasColorBuf::new_zeroed(width, height),
+            ..Canvas::new(0, 0, transparent)
+        }
+    }
+
+    /// Creates a canvas filled with `color` which has `scale` times as many pixels in both
+    /// directions as its size, for antialiasing by drawing without antialiasing and then
+    /// shrinking the result back to its size with `resolve`. Antialiasing starts off
+    /// disabled on these canvases.
+    ///
+    /// Shapes, clips, paints and background fills all use the coordinates of the resolved
+    /// canvas, so drawing onto this canvas looks the same as drawing onto a normal one.
+    /// Everything working on the pixels directly, such as `get_pixel` and `blit`, works on
+    /// the pixels of the larger canvas though.
+    pub fn new_supersampled(width: u64, height: u64, scale: u32, color: colorbuf::Color) -> Canvas {
+        let scale = scale.max(1);
+        let factor = scale as f32;
+        Canvas {
+            backing: CanvasColorBuf::new(width * scale as u64, height * scale as u64, color),
+            antialias_enabled: false,
+            transform: Transform2::scale(factor, factor),
+            supersample: scale,
+            ..Canvas::new(0, 0, color)
+        }
+    }
+
+    /// Shrinks a canvas created with `new_supersampled` to its actual size, by averaging
+    /// each block of pixels into one pixel. The averaging is done in the same way that
+    /// `box_blur` does it. Other canvases just get copied.
+    ///
+    /// The resolved canvas keeps the background color and the gamma of this canvas.
+    pub fn resolve(&self) -> Canvas {
+        let scale = self.supersample as usize;
+        let big_width = self.backing.get_width() as usize;
+        let width = self.backing.get_width() / self.supersample as u64;
+        let height = self.backing.get_height() / self.supersample as u64;
+        let gamma = self.gamma;
+
+        let mut backing = CanvasColorBuf::new_zeroed(width, height);
+        let block_size = (scale * scale) as f32;
+        for (index, color) in backing.buf.iter_mut().enumerate() {
+            let (x, y) = (index % width as usize, index / width as usize);
+            let mut sum = [0f32; 4];
+            for sub_y in y * scale..(y + 1) * scale {
+                for sub_x in x * scale..(x + 1) * scale {
+                    let pixel = &self.backing.buf[sub_y * big_width + sub_x];
+                    let linear = helper_to_linear(pixel, gamma);
+                    for (total, channel) in sum.iter_mut().zip(linear.iter()) {
+                        *total += channel;
+                    }
+                }
+            }
+            for total in sum.iter_mut() {
+                *total /= block_size;
+            }
+            *color = helper_from_linear(sum, gamma);
+        }
+
+        Canvas {
+            backing,
+            gamma,
+            ..Canvas::new(0, 0, self.background_color)
         }
     }
 
     pub fn set_draw_color(&mut self, new_color: colorbuf::Color) {
-        self.current_color = new_color;
+        self.paint = Paint::Solid(new_color);
+    }
+
+    /// Sets the paint used for drawing. This replaces the current draw color.
+    pub fn set_paint(&mut self, paint: Paint) {
+        self.paint = paint;
+    }
+
+    /// Sets the color used by `fill_background` and `fill_rect_background`.
+    ///
+    /// This doesn't change any pixels already on the canvas.
+    pub fn set_background_color(&mut self, new_color: colorbuf::Color) {
+        self.background_color = new_color;
+    }
+
+    pub fn get_background_color(&self) -> colorbuf::Color {
+        self.background_color
+    }
+
+    /// Clears the whole canvas to the background color.
+    pub fn fill_background(&mut self) {
+        let (width, height) = (self.backing.get_width(), self.backing.get_height());
+        let background_color = self.background_color;
+        for y in 0..height {
+            self.backing.set_span(y, 0, width, &background_color);
+        }
+    }
+
+    /// Clears the axis-aligned rectangle with `p1` and `p2` as its opposite corners to
+    /// the background color. A pixel gets cleared if its origin is within the rectangle.
+    pub fn fill_rect_background(&mut self, p1: Point2, p2: Point2) {
+        let (p1, p2) = (self.to_backing(p1), self.to_backing(p2));
+        let [min_x, min_y, max_x, max_y] =
+            helper_pixel_rect(p1, p2, self.backing.get_width(), self.backing.get_height());
+
+        for y in min_y..max_y {
+            let background_color = self.background_color;
+            self.backing.set_span(y, min_x, max_x, &background_color);
+        }
+    }
+
+    /// Strokes a line from `from` to `to` with the current paint.
+    ///
+    /// This is the same as stroking a `path::Line`.
+    pub fn draw_line(&mut self, from: Point2, to: Point2, width: f32) {
+        path::Line::new(from, to).stroke(self, width);
+    }
+
+    /// Strokes the outline of a circle with the current paint.
+    ///
+    /// This is the same as stroking a `path::Circle`.
+    pub fn draw_circle(&mut self, center: Point2, radius: f32, width: f32) {
+        path::Circle::new(center, radius).stroke(self, width);
+    }
+
+    /// Draws the line segments the curve gets flattened into with the given tolerance, and
+    /// marks the points between them with small dots, all with the current paint.
+    ///
+    /// This is meant for seeing how many segments are needed for a curve, and where they
+    /// go. The segments are always drawn solid and one pixel wide.
+    pub fn draw_flattening(&mut self, curve: &dyn Curve, tolerance: f32) {
+        let points = curve.flatten(tolerance);
+        let dash = self.dash.take();
+        path::Polyline::new(points.clone()).stroke(self, 1f32);
+        self.dash = dash;
+
+        for point in points {
+            path::Circle::new(point, 1.5f32).fill(self);
+        }
+    }
+
+    /// Strokes the path like `Path::stroke` does, except that the coverages of all of
+    /// the pieces the stroke is made out of get added up before anything is drawn.
+    ///
+    /// This way the stroke gets blended only once on each pixel, so there are no
+    /// visible seams where its pieces meet or overlap, but it takes more memory.
+    pub fn stroke_seamless(&mut self, path: &dyn Path, width: f32) {
+        self.draw_seamless(|c| path.stroke(c, width));
+    }
+
+    /// Fills the shape like `Loop::fill` does, except that the coverages of all of the
+    /// pieces the fill is made out of get added up before anything is drawn, up to full
+    /// coverage.
+    ///
+    /// The shapes of this crate already fill each pixel only once, even where they overlap
+    /// themselves. This is for shapes which fill themselves out of several overlapping
+    /// pieces, which would otherwise get blended more than once where the pieces overlap.
+    pub fn fill_seamless(&mut self, shape: &dyn Loop) {
+        self.draw_seamless(|c| shape.fill(c));
+    }
+
+    /// Collects the coverages of whatever `draw` draws, and then blends the current paint
+    /// onto each pixel once, with the coverage the pixel ended up with.
+    fn draw_seamless<F: FnOnce(&mut Canvas)>(&mut self, draw: F) {
+        let canvas_width = self.backing.get_width();
+        let canvas_height = self.backing.get_height();
+        self.coverage_layer = Some(AlphaBuf::new(canvas_width, canvas_height));
+
+        draw(self);
+
+        let layer = self.coverage_layer.take().unwrap();
+        for y in 0..canvas_height {
+            for x in 0..canvas_width {
+                let coverage = layer.get_coverage(x, y).unwrap();
+                if coverage <= 0f32 {
+                    continue;
+                }
+                let color = self.draw_color_at(x as i32, y as i32);
+                self.blend_pixel(x, y, &color, coverage);
+            }
+        }
+    }
+
+    /// Multiplies the alpha of every pixel by how much the mask covers it. The pixels
+    /// outside of the mask count as not covered, so they become transparent.
+    pub fn apply_mask(&mut self, mask: &MaskCanvas) {
+        let width = self.backing.get_width();
+        for (index, color) in self.backing.buf.iter_mut().enumerate() {
+            let (x, y) = (index as u64 % width, index as u64 / width);
+            let coverage = mask.get_coverage(x, y).unwrap_or(0f32);
+            // The colors are premultiplied, so all of the channels get scaled.
+            color.r *= coverage;
+            color.g *= coverage;
+            color.b *= coverage;
+            color.a *= coverage;
+        }
+    }
+
+    /// Fills the outline of a glyph, made out of closed `contours` with their points
+    /// in the coordinates of the canvas, using the given fill rule. Fonts usually use
+    /// `FillRule::NonZero`, with the contours of holes going the other way around.
+    ///
+    /// This leaves loading fonts to other crates, which can give the outlines of their
+    /// glyphs flattened into points.
+    pub fn fill_glyph(&mut self, contours: &[Vec<Point2>], fill_rule: FillRule) {
+        let contours = contours
+            .iter()
+            .map(|contour| &contour[..])
+            .collect::<Vec<_>>();
+        self.rasterize_filled_contours(&contours[..], fill_rule);
+    }
+
+    /// Fills glyphs one after another along a line, like `fill_glyph` does. Each glyph is
+    /// given as its contours relative to its origin and its advance, which is how far the
+    /// next glyph goes after it. The first glyph goes at `pen`.
+    ///
+    /// Gives where the pen ended up after the last glyph, for carrying on from there.
+    pub fn fill_glyphs(
+        &mut self,
+        glyphs: &[(&[Vec<Point2>], f32)],
+        fill_rule: FillRule,
+        pen: Point2,
+    ) -> Point2 {
+        let mut pen = pen;
+        for &(contours, advance) in glyphs.iter() {
+            let offset = pen - Point2::new(0f32, 0f32);
+            let placed = contours
+                .iter()
+                .map(|contour| contour.iter().map(|&p| p + offset).collect())
+                .collect::<Vec<_>>();
+            self.fill_glyph(&placed[..], fill_rule);
+            pen = pen + Vector2::new(advance, 0f32);
+        }
+        pen
+    }
+
+    /// Computes how much of each pixel the filled shape would cover, without
+    /// drawing anything onto the canvas.
+    ///
+    /// The coverages are in `[0, 1]` and are listed row by row, so the coverage of
+    /// the pixel at `(x, y)` is at index `y * width + x`. The clip and the antialiasing
+    /// settings of the canvas apply just like they do when filling.
+    pub fn coverage_mask(&mut self, path: &dyn path::Loop) -> Vec<f32> {
+        // We fill the shape with opaque white onto a transparent buffer, at which
+        // point the alpha of each pixel is exactly its coverage.
+        let width = self.backing.get_width();
+        let height = self.backing.get_height();
+        let white = colorbuf::Color {
+            r: 1f32,
+            g: 1f32,
+            b: 1f32,
+            a: 1f32,
+        };
+        // Neither the global alpha nor collecting coverages for a seamless stroke
+        // may get in the way of that, so they are put aside until we're done.
+        let mask = CanvasColorBuf::new_zeroed(width, height);
+        let backing = std::mem::replace(&mut self.backing, mask);
+        let paint = std::mem::replace(&mut self.paint, Paint::Solid(white));
+        let global_alpha = std::mem::replace(&mut self.global_alpha, 1f32);
+        let coverage_layer = self.coverage_layer.take();
+
+        path.fill(self);
+
+        let mask = std::mem::replace(&mut self.backing, backing);
+        self.paint = paint;
+        self.global_alpha = global_alpha;
+        self.coverage_layer = coverage_layer;
+        mask.buf.iter().map(|color| color.a).collect()
+    }
+
+    /// Restricts all drawing to the axis-aligned rectangle with `min` and `max` as its
+    /// opposite corners. A pixel can be drawn to if its origin is within the rectangle.
+    pub fn set_clip_rect(&mut self, min: Point2, max: Point2) {
+        self.clip = Some(helper_pixel_rect(
+            self.to_backing(min),
+            self.to_backing(max),
+            self.backing.get_width(),
+            self.backing.get_height(),
+        ));
+    }
+
+    /// Removes the clip rectangle, so that the whole canvas can be drawn to again.
+    pub fn clear_clip(&mut self) {
+        self.clip = None;
+    }
+
+    fn is_clipped(&self, x: i32, y: i32) -> bool {
+        match self.clip {
+            None => false,
+            Some([min_x, min_y, max_x, max_y]) => {
+                let (x, y) = (x as u64, y as u64);
+                x < min_x || x >= max_x || y < min_y || y >= max_y
+            }
+        }
+    }
+
+    /// Limits a bounding box of pixels to the pixels of the canvas which can be drawn to,
+    /// with both the first and the last pixels included. There are no pixels left if the
+    /// box lies outside of the canvas or its clip, or if the canvas has no pixels at all.
+    ///
+    /// The rasterizers only go through the pixels left in the box, so they don't need to
+    /// check the clip for each pixel, and a small clip keeps large shapes fast to draw.
+    fn clamp_to_visible(
+        &self,
+        min_x: i32,
+        min_y: i32,
+        max_x: i32,
+        max_y: i32,
+    ) -> Option<(i32, i32, i32, i32)> {
+        // The last pixels are computed with signed arithmetic so that an empty canvas
+        // doesn't underflow.
+        let (first_x, first_y, end_x, end_y) = match self.clip {
+            Some([min_x, min_y, max_x, max_y]) => (min_x, min_y, max_x, max_y),
+            None => (0, 0, self.backing.get_width(), self.backing.get_height()),
+        };
+        let limit = |v: u64| (v as i64).min(i32::MAX as i64) as i32;
+        let last_x = limit(end_x).saturating_sub(1);
+        let last_y = limit(end_y).saturating_sub(1);
+
+        let (min_x, max_x) = (min_x.max(limit(first_x)), max_x.min(last_x));
+        let (min_y, max_y) = (min_y.max(limit(first_y)), max_y.min(last_y));
+        if min_x > max_x || min_y > max_y {
+            return None;
+        }
+        Some((min_x, min_y, max_x, max_y))
     }
 
     pub fn enable_antialias(&mut self, enable: bool) {
         self.antialias_enabled = enable;
     }
 
+    /// Enables or disables conservative rasterization, where every pixel a shape covers
+    /// even a part of gets fully drawn to, whether antialiasing is enabled or not. This
+    /// is meant for things like picking buffers, where no touched pixel may be missed.
+    ///
+    /// The pixels are checked with the same subpixels as with antialiasing, so shapes
+    /// touching a pixel less than the subpixels can see are still left out.
+    pub fn enable_conservative(&mut self, enable: bool) {
+        self.conservative = enable;
+    }
+
+    /// Tells whether the rasterizers need to work out how much of each pixel is covered.
+    fn computes_coverage(&self) -> bool {
+        self.antialias_enabled || self.conservative
+    }
+
+    /// Turns the coverage of a pixel into the coverage it gets drawn with.
+    fn effective_coverage(&self, coverage: f32) -> f32 {
+        if self.conservative && coverage > 0f32 {
+            1f32
+        } else {
+            coverage
+        }
+    }
+
+    /// Runs `draw` with antialiasing enabled or disabled, and then sets antialiasing
+    /// back to how it was. This is handy for mixing crisp and smooth shapes.
+    pub fn with_antialias<F: FnOnce(&mut Canvas)>(&mut self, enable: bool, draw: F) {
+        let previous = self.antialias_enabled;
+        self.antialias_enabled = enable;
+        draw(self);
+        self.antialias_enabled = previous;
+    }
+
+    /// Sets the amount of subpixels per side used for antialiasing.
+    ///
+    /// Antialiased pixels are sampled at `per_side * per_side` points, so the cost
+    /// of the antialiasing grows quadratically with this value. The default is 16,
+    /// and values below 1 are treated as 1.
+    ///
+    /// Whole circles and rings don't use the subpixels at all, since their coverage
+    /// comes from the distance to their edges instead, so this doesn't affect them.
+    /// Arcs and pies of circles still use the subpixels.
+    pub fn set_antialias_samples(&mut self, per_side: u32) {
+        self.aa_samples = per_side.max(1);
+    }
+
+    /// Sets the gamma used when blending colors together. The default is 2.2.
+    ///
+    /// Gamma values that aren't positive are ignored.
+    pub fn set_gamma(&mut self, gamma: f32) {
+        if gamma <= 0f32 || !gamma.is_finite() {
+            return;
+        }
+        self.gamma = gamma;
+    }
+
+    /// Sets how opaque everything drawn from now on is, between 0 for not showing at all
+    /// and 1 for as opaque as its paint, which is the default. Values outside of that
+    /// are clamped to it, and NaN is ignored.
+    ///
+    /// This scales how much of each pixel gets covered, so it works with every paint,
+    /// and it applies to `blit` and `composite` as well. Filling the background and
+    /// `flood_fill` replace the pixels outright, so they aren't affected.
+    pub fn set_global_alpha(&mut self, alpha: f32) {
+        if alpha.is_nan() {
+            return;
+        }
+        self.global_alpha = alpha.clamp(0f32, 1f32);
+    }
+
+    pub fn get_global_alpha(&self) -> f32 {
+        self.global_alpha
+    }
+
+    /// Enables or disables ordered dithering when exporting the canvas with `to_bytebuffer`
+    /// and `save_png`. It is disabled by default.
+    ///
+    /// The rounding to 8 bits then goes up or down by a pattern repeating every 4 by 4
+    /// pixels, so that smooth gradients don't show bands of the same value. Each pixel
+    /// still stays within a step of its true value, and the steps average out to it.
+    pub fn enable_dithering(&mut self, enable: bool) {
+        self.dither = enable;
+    }
+
+    /// Sets the cap style used at the ends of stroked lines and curves.
+    pub fn set_line_cap(&mut self, cap: LineCap) {
+        self.line_cap = cap;
+    }
+
+    /// Sets the join style used at the corners of stroked multi-part paths.
+    pub fn set_line_join(&mut self, join: LineJoin) {
+        self.line_join = join;
+    }
+
+    /// Sets the limit for the ratio between the length of a miter join
+    /// and half of the stroke width. Miters going past this limit get
+    /// drawn as bevels instead. The default is 10.
+    pub fn set_miter_limit(&mut self, limit: f32) {
+        self.miter_limit = limit;
+    }
+
+    /// Sets where the strokes of closed shapes go relative to their boundary.
+    /// The default is to center them on the boundary.
+    pub fn set_stroke_align(&mut self, align: StrokeAlign) {
+        self.stroke_align = align;
+    }
+
+    /// Sets the transform which everything drawn onto the canvas goes through,
+    /// including the widths of strokes. The default is the identity transform.
+    ///
+    /// The clip, the paints and the background fills are unaffected, and they
+    /// stay in the coordinates of the pixels.
+    pub fn set_transform(&mut self, transform: Transform2) {
+        let factor = self.supersample as f32;
+        self.transform = transform.then(&Transform2::scale(factor, factor));
+    }
+
+    pub fn get_transform(&self) -> Transform2 {
+        let factor = self.supersample as f32;
+        self.transform
+            .then(&Transform2::scale(1f32 / factor, 1f32 / factor))
+    }
+
+    /// Sets how far, in pixels, the straight pieces used for drawing curves
+    /// may stray from the actual curve. Smaller values give smoother curves
+    /// at the cost of more pieces. The default is 0.1.
+    ///
+    /// Tolerances that aren't positive are ignored.
+    pub fn set_flattening_tolerance(&mut self, tolerance: f32) {
+        if tolerance <= 0f32 || !tolerance.is_finite() {
+            return;
+        }
+        self.flattening_tolerance = tolerance;
+    }
+
+    /// Sets the rule deciding which parts of self-intersecting polygons and paths count
+    /// as their inside when they get filled. The default is `FillRule::EvenOdd`.
+    pub fn set_fill_rule(&mut self, rule: FillRule) {
+        self.fill_rule = rule;
+    }
+
+    /// Sets the dash pattern used for stroking, as the lengths of the alternating
+    /// drawn and skipped parts of the stroke. With `None`, strokes are solid.
+    ///
+    /// Patterns which have negative lengths or add up to zero also give solid strokes.
+    pub fn set_dash(&mut self, dash: Option<Vec<f32>>) {
+        self.dash = dash;
+    }
+
+    pub fn get_pixel(&self, x: u64, y: u64) -> Result<colorbuf::Color, colorbuf::ColorBufError> {
+        self.backing.get_pixel(x, y)
+    }
+
+    pub fn width(&self) -> u64 {
+        self.backing.get_width()
+    }
+
+    pub fn height(&self) -> u64 {
+        self.backing.get_height()
+    }
+
+    /// Goes through the colors of all of the pixels along with their coordinates,
+    /// row by row.
+    pub fn pixels(&self) -> impl Iterator<Item = (u64, u64, colorbuf::Color)> + '_ {
+        let width = self.backing.width;
+        self.backing
+            .buf
+            .iter()
+            .enumerate()
+            .map(move |(index, color)| {
+                let index = index as u64;
+                (index % width, index / width, color::unpremultiply(color))
+            })
+    }
+
+    /// Goes through all of the pixels along with their coordinates, row by row,
+    /// so that their colors can be changed. The clip doesn't apply to this.
+    pub fn pixels_mut(&mut self) -> impl Iterator<Item = (u64, u64, PixelMut<'_>)> + '_ {
+        let width = self.backing.width;
+        self.backing
+            .buf
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, pixel)| {
+                let index = index as u64;
+                let color = color::unpremultiply(pixel);
+                (index % width, index / width, PixelMut { pixel, color })
+            })
+    }
+
+    /// Tells whether the canvases have the same size, and whether all of their pixels
+    /// have colors within `epsilon` of each other in every channel.
+    ///
+    /// Channels which are NaN are never equal to anything.
+    pub fn pixels_equal(&self, other: &Canvas, epsilon: f32) -> bool {
+        if self.width() != other.width() || self.height() != other.height() {
+            return false;
+        }
+
+        (0..self.height()).all(|y| {
+            (0..self.width()).all(|x| {
+                let a = self.backing.get_pixel(x, y).unwrap();
+                let b = other.backing.get_pixel(x, y).unwrap();
+                color::colors_approx_eq(&a, &b, epsilon)
+            })
+        })
+    }
+
+    /// Draws `src` over this canvas with its top left corner at the origin,
+    /// combining the colors with the given blend mode.
+    ///
+    /// The parts of `src` which don't fit onto this canvas are left out.
+    pub fn composite(&mut self, src: &Canvas, mode: BlendMode) {
+        let width = self.backing.get_width().min(src.backing.get_width());
+        let height = self.backing.get_height().min(src.backing.get_height());
+        let gamma = self.gamma;
+
+        for y in 0..height {
+            for x in 0..width {
+                if self.is_clipped(x as i32, y as i32) {
+                    continue;
+                }
+
+                let mut src_color = src.backing.get_pixel(x, y).unwrap();
+                src_color.a *= self.global_alpha;
+                let cur_color = self.backing.get_pixel(x, y).unwrap();
+                let out_color = helper_blend(&cur_color, &src_color, mode, gamma);
+                self.backing.set_pixel(x, y, &out_color).unwrap();
+            }
+        }
+    }
+
+    /// Changes the size of the canvas. The pixels which are within both the old and
+    /// the new size stay where they are, the rest of the old pixels are cropped away,
+    /// and any newly added pixels are set to `fill`.
+    pub fn resize(&mut self, new_width: u64, new_height: u64, fill: colorbuf::Color) {
+        let mut resized = CanvasColorBuf::new(new_width, new_height, fill);
+
+        let kept_width = self.backing.width.min(new_width);
+        let kept_height = self.backing.height.min(new_height);
+        for y in 0..kept_height {
+            let old_row = self.backing.index(0, y);
+            let new_row = resized.index(0, y);
+            resized.buf[new_row..new_row + kept_width as usize]
+                .copy_from_slice(&self.backing.buf[old_row..old_row + kept_width as usize]);
+        }
+
+        self.backing = resized;
+    }
+
     fn rasterize_stroked_circle(&mut self, center: Point2, inner_radius: f32, outer_radius: f32) {
+        self.rasterize_stroked_arc(center, inner_radius, outer_radius, None);
+    }
+
+    /// Rasterizes the part of a ring which lies within the given `arc`, given as the
+    /// starting angle and the sweep of the arc. If there is no arc, the whole ring is drawn.
+    fn rasterize_stroked_arc(
+        &mut self,
+        center: Point2,
+        inner_radius: f32,
+        outer_radius: f32,
+        arc: Option<(f32, f32)>,
+    ) {
+        let (center, inner_radius, outer_radius, arc) = match self.transform.similarity() {
+            Some((scale, rotation)) => (
+                self.transform.apply(center),
+                inner_radius * scale,
+                outer_radius * scale,
+                arc.map(|(start, sweep)| (start + rotation, sweep)),
+            ),
+            None => {
+                // The transform squashes the ring into something else than a ring,
+                // so it must be drawn as a polygon instead.
+                let (start, sweep) = arc.unwrap_or((0f32, 2f32 * std::f32::consts::PI));
+                let outer = self.flatten_arc(center, outer_radius, start, sweep);
+                let mut inner = if inner_radius > 0f32 {
+                    self.flatten_arc(center, inner_radius, start, sweep)
+                } else {
+                    vec![center]
+                };
+                if arc.is_none() {
+                    self.rasterize_filled_contours(&[&outer[..], &inner[..]], FillRule::EvenOdd);
+                } else {
+                    inner.reverse();
+                    let ring = outer.into_iter().chain(inner).collect::<Vec<_>>();
+                    self.rasterize_filled_contours(&[&ring[..]], FillRule::NonZero);
+                }
+                return;
+            }
+        };
+
         // Calculate the bounding box of the circle,
         // and round it to be the closest pixels.
-        let min_x = ((center.get_x() - outer_radius - 1f32).floor() as i32).max(0);
-        let max_x = ((center.get_x() + outer_radius + 1f32).ceil() as i32)
-            .min((self.backing.get_width() - 1) as i32);
-        let min_y = ((center.get_y() - outer_radius - 1f32).floor() as i32).max(0);
-        let max_y = ((center.get_y() + outer_radius + 1f32).ceil() as i32)
-            .min((self.backing.get_height() - 1) as i32);
+        let (min_x, min_y, max_x, max_y) = match self.clamp_to_visible(
+            (center.get_x() - outer_radius - 1f32).floor() as i32,
+            (center.get_y() - outer_radius - 1f32).floor() as i32,
+            (center.get_x() + outer_radius + 1f32).ceil() as i32,
+            (center.get_y() + outer_radius + 1f32).ceil() as i32,
+        ) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let width = outer_radius - inner_radius;
+        if !self.computes_coverage()
+            && arc.is_none()
+            && inner_radius > 0f32
+            && (width - 1f32).abs() <= 0.25f32
+        {
+            // A ring about a pixel wide has about a pixel per step around it, so going
+            // through just those is much faster than going through the whole bounding box.
+            let radius = (inner_radius + outer_radius) / 2f32;
+            self.rasterize_thin_circle(center, radius, (min_x, min_y, max_x, max_y));
+            return;
+        }
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
                 let cur_point = Point2::new(x as f32, y as f32);
-                if !self.antialias_enabled {
+                if !self.computes_coverage() {
                     let dist_from_center = (cur_point - center).length();
-                    if dist_from_center < inner_radius || dist_from_center > outer_radius {
+                    if dist_from_center < inner_radius
+                        || dist_from_center > outer_radius
+                        || !helper_within_arc(cur_point, center, arc)
+                    {
                         continue;
                     }
-                    self.backing
-                        .set_pixel(x as u64, y as u64, &self.current_color)
-                        .unwrap();
+                    let color = self.draw_color_at(x, y);
+                    self.blend_pixel(x as u64, y as u64, &color, 1f32);
                     continue;
                 }
 
                 // Antialiasing
+                let x_fac = x as f32;
+                let y_fac = y as f32;
+                if arc.is_none() {
+                    if self.conservative {
+                        // The estimate below can miss pixels whose corners just reach
+                        // into the ring, so check the nearest and farthest points instead.
+                        let (min_dist, max_dist) =
+                            helper_pixel_distance_range(center, x_fac, y_fac);
+                        if min_dist < outer_radius && max_dist > inner_radius {
+                            let color = self.draw_color_at(x, y);
+                            self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                        }
+                        continue;
+                    }
+
+                    // The coverage of a whole ring comes straight from the distances
+                    // to its edges, without needing any subpixels.
+                    let coverage = helper_disk_coverage(center, outer_radius, x_fac, y_fac)
+                        - helper_disk_coverage(center, inner_radius, x_fac, y_fac);
+                    if coverage > 0f32 {
+                        let color = self.draw_color_at(x, y);
+                        self.blend_pixel(x as u64, y as u64, &color, coverage);
+                    }
+                    continue;
+                }
+
                 // We check whether our point is within acceptiable
                 // range from the center
                 let circle_helper = |x, y| {
                     let p = Point2::new(x, y);
                     let dist = (p - center).length();
-                    (dist >= inner_radius) && (dist <= outer_radius)
+                    // Each subpixel gets checked against both edges on its own, so a
+                    // pixel straddling the inner edge only gets the subpixels outside of it.
+                    let inside_outer = dist <= outer_radius;
+                    let inside_inner = dist < inner_radius;
+                    inside_outer && !inside_inner && helper_within_arc(p, center, arc)
                 };
-                // We have antialiasing, so let us check the corners first for some heuristic reasons.
-                let x_fac = x as f32;
-                let y_fac = y as f32;
-                let corner_offsets = [
-                    [0f32, 0f32],
-                    [3f32 / 4f32, 0f32],
-                    [3f32 / 4f32, 3f32 / 4f32],
-                    [0f32, 3f32 / 4f32],
-                ];
-                let corner_locs = corner_offsets
-                    .iter()
-                    .map(|[xoff, yoff]| [x_fac + xoff, y_fac + yoff])
-                    .collect::<Vec<_>>();
-                let corners_inside = corner_locs
-                    .iter()
-                    .map(|&[x, y]| circle_helper(x, y))
-                    .collect::<Vec<_>>();
-                let is_empty = !(corners_inside.iter().fold(false, |acc, &x| acc || x));
-                if is_empty {
-                    // No corners touch so we aren't close enough to the circle.
-                    // Now, there are some literal edge-cases with this detection method where
-                    // this heuristic fails, but for our usage this is accurate enough.
+                // We have antialiasing, so let us first check whether the whole pixel
+                // is on one side of the edges of the ring, which saves us the subpixels.
+                let (min_dist, max_dist) = helper_pixel_distance_range(center, x_fac, y_fac);
+                if max_dist < inner_radius || min_dist > outer_radius {
                     continue;
                 }
-                let is_full = corners_inside.iter().fold(true, |acc, &x| acc && x);
-                if is_full {
-                    // We are fully contained within the circle edge. While this has similar
-                    // shortcomings to the last one, again, this is good enough for us.
-                    self.backing
-                        .set_pixel(x as u64, y as u64, &self.current_color)
-                        .unwrap();
+                if min_dist >= inner_radius
+                    && max_dist <= outer_radius
+                    && helper_arc_contains_pixel(center, arc, x_fac, y_fac)
+                {
+                    let color = self.draw_color_at(x, y);
+                    self.blend_pixel(x as u64, y as u64, &color, 1f32);
                     continue;
                 }
                 // We are at a position where some of our subpixels are within the circle
                 // and some are without. I.e. we are at a pixel where we should apply
                 // anti-aliasing to.
 
-                // TODO: Make the amount of subpixels variable.
-                let subpixels_per_side = 16;
+                let subpixels_per_side = self.aa_samples as usize;
                 let mut subs_within_polygon =
                     vec![false; subpixels_per_side * subpixels_per_side];
                 for y_sub in 0..subpixels_per_side {
                     for x_sub in 0..subpixels_per_side {
-                        let x_off = (x_sub as f32) / (subpixels_per_side as f32);
-                        let y_off = (x_sub as f32) / (subpixels_per_side as f32);
+                        let x_off = helper_subpixel_offset(x_sub, subpixels_per_side);
+                        let y_off = helper_subpixel_offset(y_sub, subpixels_per_side);
 
                         let sub_x = x_fac + x_off;
                         let sub_y = y_fac + y_off;
@@ -182,107 +1039,232 @@ impl Canvas {
                     .fold(0, |acc, x| acc + if x { 1 } else { 0 });
                 let blend_factor = (aa_blend_proportion as f32)
                     / ((subpixels_per_side * subpixels_per_side) as f32);
-                let blent_color = colorbuf::Color {
-                    r: self.current_color.r,
-                    g: self.current_color.g,
-                    b: self.current_color.b,
-                    a: self.current_color.a * blend_factor,
-                };
+                if blend_factor <= 0f32 {
+                    continue;
+                }
+                let color = self.draw_color_at(x, y);
+                self.blend_pixel(x as u64, y as u64, &color, blend_factor);
+            }
+        }
+    }
 
-                // TODO: Make gamma changeable
-                let gamma = 2.2f32;
-
-                let cur_color = self.backing.get_pixel(x as u64, y as u64).unwrap();
-
-                let out_a = blent_color.a + cur_color.a * (1f32 - blent_color.a);
-                let out_r = (blent_color.r.powf(gamma) * blent_color.a
-                             + cur_color.r.powf(gamma) * (1f32 - blent_color.a))
-                    .powf(1f32 / gamma);
-                let out_g = (blent_color.g.powf(gamma) * blent_color.a
-                             + cur_color.g.powf(gamma) * (1f32 - blent_color.a))
-                    .powf(1f32 / gamma);
-                let out_b = (blent_color.b.powf(gamma) * blent_color.a
-                             + cur_color.b.powf(gamma) * (1f32 - blent_color.a))
-                    .powf(1f32 / gamma);
-
-                let out_color = colorbuf::Color {
-                    r: out_r,
-                    g: out_g,
-                    b: out_b,
-                    a: out_a,
-                };
+    /// Draws the pixels closest to the circle, one in each column along its top and bottom and
+    /// one in each row along its sides, like the midpoint circle algorithm does. The circle is
+    /// followed exactly instead of stepping from pixel to pixel, so that it can be anywhere.
+    ///
+    /// Only the pixels within `bounds`, given as the first and the last pixels, are drawn.
+    fn rasterize_thin_circle(&mut self, center: Point2, radius: f32, bounds: (i32, i32, i32, i32)) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let (center_x, center_y) = (center.get_x(), center.get_y());
+        // The sides meet the top and the bottom where the circle is at 45 degrees.
+        let reach = radius * std::f32::consts::FRAC_1_SQRT_2;
+        let offset = |d: f32| (radius * radius - d * d).max(0f32).sqrt();
 
-                self.backing
-                    .set_pixel(x as u64, y as u64, &out_color)
-                    .unwrap();
+        let mut pixels = Vec::new();
+        let first_x = ((center_x - reach).ceil() as i32).max(min_x);
+        let last_x = ((center_x + reach).floor() as i32).min(max_x);
+        for x in first_x..=last_x {
+            let dy = offset(x as f32 - center_x);
+            pixels.push((x, (center_y - dy).round() as i32));
+            pixels.push((x, (center_y + dy).round() as i32));
+        }
+        let first_y = ((center_y - reach).ceil() as i32).max(min_y);
+        let last_y = ((center_y + reach).floor() as i32).min(max_y);
+        for y in first_y..=last_y {
+            let dx = offset(y as f32 - center_y);
+            pixels.push(((center_x - dx).round() as i32, y));
+            pixels.push(((center_x + dx).round() as i32, y));
+        }
+
+        // Where the parts meet, the same pixel can come up twice, but it must be drawn once.
+        pixels.sort_unstable();
+        pixels.dedup();
+        for (x, y) in pixels {
+            if x < min_x || x > max_x || y < min_y || y > max_y {
+                continue;
             }
+            let color = self.draw_color_at(x, y);
+            self.blend_pixel(x as u64, y as u64, &color, 1f32);
         }
     }
 
-    fn rasterize_filled_circle(&mut self, center: Point2, radius: f32) {}
+    fn rasterize_filled_circle(&mut self, center: Point2, radius: f32) {
+        self.rasterize_filled_sector(center, radius, None);
+    }
 
-    fn rasterize_convex_filled_polygon(&mut self, points: &[Point2]) {
-        // We must calculate the bounding box of our polygon,
-        // and rounding them to the closest integers.
-        let xs = points.iter().map(|p| p.get_x()).collect::<Vec<_>>();
-        let ys = points.iter().map(|p| p.get_y()).collect::<Vec<_>>();
+    /// Rasterizes the sector of a disk bounded by the given `arc`, given as the starting
+    /// angle and the sweep of the arc. If there is no arc, the whole disk is drawn.
+    fn rasterize_filled_sector(&mut self, center: Point2, radius: f32, arc: Option<(f32, f32)>) {
+        let (center, radius, arc) = match self.transform.similarity() {
+            Some((scale, rotation)) => (
+                self.transform.apply(center),
+                radius * scale,
+                arc.map(|(start, sweep)| (start + rotation, sweep)),
+            ),
+            None => {
+                // Same as with rings, the disk gets squashed into an ellipse.
+                let (start, sweep) = arc.unwrap_or((0f32, 2f32 * std::f32::consts::PI));
+                let mut points = self.flatten_arc(center, radius, start, sweep);
+                if arc.is_some() {
+                    points.push(center);
+                }
+                self.rasterize_filled_contours(&[&points[..]], FillRule::NonZero);
+                return;
+            }
+        };
+
+        // Calculate the bounding box of the circle,
+        // and round it to be the closest pixels.
+        let (min_x, min_y, max_x, max_y) = match self.clamp_to_visible(
+            (center.get_x() - radius - 1f32).floor() as i32,
+            (center.get_y() - radius - 1f32).floor() as i32,
+            (center.get_x() + radius + 1f32).ceil() as i32,
+            (center.get_y() + radius + 1f32).ceil() as i32,
+        ) {
+            Some(bounds) => bounds,
+            None => return,
+        };
 
-        let min_x = (helper_get_min(xs.clone()).unwrap().floor() as i32 - 1).max(0);
-        let max_x = (helper_get_max(xs).unwrap().ceil() as i32 + 1)
-            .min((self.backing.get_width() - 1) as i32);
-        let min_y = (helper_get_min(ys.clone()).unwrap().floor() as i32 - 1).max(0);
-        let max_y = (helper_get_max(ys).unwrap().ceil() as i32 + 1)
-            .min((self.backing.get_height() - 1) as i32);
+        // The pixel which contains the center of the circle. This one must always get
+        // some coverage, even if the circle is so small that none of our samples hit it.
+        let center_x = center.get_x().floor() as i32;
+        let center_y = center.get_y().floor() as i32;
+
+        let circle_helper = |x, y| {
+            let p = Point2::new(x, y);
+            (p - center).length() <= radius && helper_within_arc(p, center, arc)
+        };
 
         for y in min_y..=max_y {
             for x in min_x..=max_x {
-                // We know that all of these are within the bounding box which limits the necessary
-                // checks
-                if self.antialias_enabled {
-                    let x_fac = x as f32;
-                    let y_fac = y as f32;
-                    let corner_offsets = [
-                        [0f32, 0f32],
-                        [3f32 / 4f32, 0f32],
-                        [3f32 / 4f32, 3f32 / 4f32],
-                        [0f32, 3f32 / 4f32],
-                    ];
-                    let corner_locs = corner_offsets
-                        .iter()
-                        .map(|[xoff, yoff]| [x_fac + xoff, y_fac + yoff])
-                        .collect::<Vec<_>>();
-                    let corners_inside = corner_locs
-                        .iter()
-                        .map(|&[x, y]| helper_even_odd_rule(x, y, &points[..]))
-                        .collect::<Vec<_>>();
-                    let is_empty = !(corners_inside.iter().fold(false, |acc, &x| acc || x));
-                    if is_empty {
-                        // No corners touch so we aren't close enough to the polygon.
-                        // Now, there are some literal edge-cases with this detection method where
-                        // this heuristic fails, but for our usage this is accurate enough.
+                let is_center_pixel = x == center_x && y == center_y;
+                if !self.computes_coverage() {
+                    if !circle_helper(x as f32, y as f32) && !is_center_pixel {
                         continue;
                     }
-                    let is_full = corners_inside.iter().fold(true, |acc, &x| acc && x);
-                    if is_full {
-                        // We are fully contained within the polygon. While this has similar
-                        // shortcomings to the last one, again, this is good enough for us.
-                        self.backing
-                            .set_pixel(x as u64, y as u64, &self.current_color)
-                            .unwrap();
+                    let color = self.draw_color_at(x, y);
+                    self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                    continue;
+                }
+
+                let x_fac = x as f32;
+                let y_fac = y as f32;
+                if arc.is_none() {
+                    if self.conservative {
+                        let (min_dist, _) = helper_pixel_distance_range(center, x_fac, y_fac);
+                        if min_dist < radius || is_center_pixel {
+                            let color = self.draw_color_at(x, y);
+                            self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                        }
                         continue;
                     }
-                    // We are at a position where some of our subpixels are within the polygon
-                    // and some are without. I.e. we are at a pixel where we should apply
-                    // anti-aliasing to.
 
-                    // TODO: Make the amount of subpixels variable.
-                    let subpixels_per_side = 16;
-                    let mut subs_within_polygon =
+                    // Same as with the stroked circle, a whole disk doesn't need subpixels.
+                    let coverage = helper_disk_coverage(center, radius, x_fac, y_fac);
+                    if coverage > 0f32 {
+                        let color = self.draw_color_at(x, y);
+                        self.blend_pixel(x as u64, y as u64, &color, coverage);
+                    }
+                    continue;
+                }
+                // Same as with the stroked circle, skip the subpixels if the whole
+                // pixel is on one side of the edge of the circle.
+                let (min_dist, max_dist) = helper_pixel_distance_range(center, x_fac, y_fac);
+                if min_dist > radius && !is_center_pixel {
+                    continue;
+                }
+                if max_dist <= radius && helper_arc_contains_pixel(center, arc, x_fac, y_fac) {
+                    let color = self.draw_color_at(x, y);
+                    self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                    continue;
+                }
+
+                let subpixels_per_side = self.aa_samples as usize;
+                let mut subs_within_circle = vec![false; subpixels_per_side * subpixels_per_side];
+                for y_sub in 0..subpixels_per_side {
+                    for x_sub in 0..subpixels_per_side {
+                        let x_off = helper_subpixel_offset(x_sub, subpixels_per_side);
+                        let y_off = helper_subpixel_offset(y_sub, subpixels_per_side);
+
+                        let sub_x = x_fac + x_off;
+                        let sub_y = y_fac + y_off;
+
+                        subs_within_circle[y_sub * subpixels_per_side + x_sub] =
+                            circle_helper(sub_x, sub_y);
+                    }
+                }
+                let aa_blend_proportion = subs_within_circle
+                    .into_iter()
+                    .fold(0, |acc, x| acc + if x { 1 } else { 0 });
+                let mut blend_factor = (aa_blend_proportion as f32)
+                    / ((subpixels_per_side * subpixels_per_side) as f32);
+                if is_center_pixel {
+                    // The subpixels might miss a tiny circle entirely, so use the
+                    // area of the circle as the lower bound for the coverage.
+                    let area = std::f32::consts::PI * radius * radius;
+                    blend_factor = blend_factor.max(area.min(1f32));
+                }
+                if blend_factor <= 0f32 {
+                    continue;
+                }
+                let color = self.draw_color_at(x, y);
+                self.blend_pixel(x as u64, y as u64, &color, blend_factor);
+            }
+        }
+    }
+
+    fn rasterize_convex_filled_polygon(&mut self, points: &[Point2]) {
+        let transformed = self.transform_contours(&[points]);
+        let points = match transformed {
+            Some(ref transformed) => &transformed[0][..],
+            None => points,
+        };
+
+        // We must calculate the bounding box of our polygon,
+        // and rounding them to the closest integers.
+        let (min, max) = match helper_bounds(points.iter()) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let (min_x, min_y, max_x, max_y) = match self.clamp_to_visible(
+            (min.get_x().floor() as i32).saturating_sub(1),
+            (min.get_y().floor() as i32).saturating_sub(1),
+            (max.get_x().ceil() as i32).saturating_add(1),
+            (max.get_y().ceil() as i32).saturating_add(1),
+        ) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                // We know that all of these are within the bounding box which limits the necessary
+                // checks
+                if self.computes_coverage() {
+                    let x_fac = x as f32;
+                    let y_fac = y as f32;
+                    if !helper_polygon_touches_pixel(&points[..], x_fac, y_fac) {
+                        // No edge goes through this pixel, so it is either completely
+                        // inside or completely outside of the polygon, and any point
+                        // of the pixel tells us which.
+                        if helper_even_odd_rule(x_fac + 0.5f32, y_fac + 0.5f32, &points[..]) {
+                            let color = self.draw_color_at(x, y);
+                            self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                        }
+                        continue;
+                    }
+                    // We are at a position where some of our subpixels are within the polygon
+                    // and some are without. I.e. we are at a pixel where we should apply
+                    // anti-aliasing to.
+
+                    let subpixels_per_side = self.aa_samples as usize;
+                    let mut subs_within_polygon =
                         vec![false; subpixels_per_side * subpixels_per_side];
                     for y_sub in 0..subpixels_per_side {
                         for x_sub in 0..subpixels_per_side {
-                            let x_off = (x_sub as f32) / (subpixels_per_side as f32);
-                            let y_off = (x_sub as f32) / (subpixels_per_side as f32);
+                            let x_off = helper_subpixel_offset(x_sub, subpixels_per_side);
+                            let y_off = helper_subpixel_offset(y_sub, subpixels_per_side);
 
                             let sub_x = x_fac + x_off;
                             let sub_y = y_fac + y_off;
@@ -296,49 +1278,260 @@ impl Canvas {
                         .fold(0, |acc, x| acc + if x { 1 } else { 0 });
                     let blend_factor = (aa_blend_proportion as f32)
                         / ((subpixels_per_side * subpixels_per_side) as f32);
-                    let blent_color = colorbuf::Color {
-                        r: self.current_color.r,
-                        g: self.current_color.g,
-                        b: self.current_color.b,
-                        a: self.current_color.a * blend_factor,
-                    };
-
-                    // TODO: Make gamma changeable
-                    let gamma = 2.2f32;
-
-                    let cur_color = self.backing.get_pixel(x as u64, y as u64).unwrap();
-
-                    let out_a = blent_color.a + cur_color.a * (1f32 - blent_color.a);
-                    let out_r = (blent_color.r.powf(gamma) * blent_color.a
-                        + cur_color.r.powf(gamma) * (1f32 - blent_color.a))
-                        .powf(1f32 / gamma);
-                    let out_g = (blent_color.g.powf(gamma) * blent_color.a
-                        + cur_color.g.powf(gamma) * (1f32 - blent_color.a))
-                        .powf(1f32 / gamma);
-                    let out_b = (blent_color.b.powf(gamma) * blent_color.a
-                        + cur_color.b.powf(gamma) * (1f32 - blent_color.a))
-                        .powf(1f32 / gamma);
-
-                    let out_color = colorbuf::Color {
-                        r: out_r,
-                        g: out_g,
-                        b: out_b,
-                        a: out_a,
-                    };
-
-                    self.backing
-                        .set_pixel(x as u64, y as u64, &out_color)
-                        .unwrap();
+                    if blend_factor <= 0f32 {
+                        continue;
+                    }
+                    let color = self.draw_color_at(x, y);
+                    self.blend_pixel(x as u64, y as u64, &color, blend_factor);
                 } else {
                     let inside = helper_even_odd_rule(x as f32, y as f32, &points[..]);
                     if inside {
-                        self.backing
-                            .set_pixel(x as u64, y as u64, &self.current_color)
-                            .unwrap();
+                        let color = self.draw_color_at(x, y);
+                        self.blend_pixel(x as u64, y as u64, &color, 1f32);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Puts the points of the contours through the transform of the canvas. Nothing
+    /// needs to be done for the identity transform, which gives nothing.
+    fn transform_contours(&self, contours: &[&[Point2]]) -> Option<Vec<Vec<Point2>>> {
+        if self.transform.is_identity() {
+            return None;
+        }
+
+        let transform = self.transform;
+        Some(
+            contours
+                .iter()
+                .map(|contour| contour.iter().map(|&p| transform.apply(p)).collect())
+                .collect(),
+        )
+    }
+
+    /// Gets points along the arc of a circle, starting at the angle `start` and going
+    /// through `sweep`, close enough to each other for the arc to look smooth after
+    /// going through the transform of the canvas.
+    fn flatten_arc(&self, center: Point2, radius: f32, start: f32, sweep: f32) -> Vec<Point2> {
+        let tolerance = self.flattening_tolerance;
+        let radius_on_canvas = radius * self.transform.max_stretch();
+        // A piece covering the angle `a` strays `radius * (1 - cos(a / 2))` from the arc.
+        let max_angle = if tolerance < radius_on_canvas {
+            2f32 * (1f32 - tolerance / radius_on_canvas).acos()
+        } else {
+            std::f32::consts::FRAC_PI_2
+        };
+        let line_segments = ((sweep / max_angle).ceil() as u64).max(4);
+
+        (0..=line_segments)
+            .map(|i| {
+                let angle = start + sweep * (i as f32) / (line_segments as f32);
+                let (sin, cos) = angle.sin_cos();
+                center + Vector2::new(cos, sin) * radius
+            })
+            .collect()
+    }
+
+    /// Rasterizes an arbitrary polygon with the fill rule of the canvas.
+    fn rasterize_filled_polygon(&mut self, points: &[Point2]) {
+        let rule = self.fill_rule;
+        self.rasterize_filled_contours(&[points], rule);
+    }
+
+    /// Rasterizes the shape outlined by all of the given closed contours together, by
+    /// filling the spans between the crossings of their edges on each scanline.
+    ///
+    /// With antialiasing, each pixel row is split into several scanlines, and the
+    /// horizontal coverage of the spans within each pixel is computed exactly.
+    fn rasterize_filled_contours(&mut self, contours: &[&[Point2]], rule: FillRule) {
+        let transformed = self.transform_contours(contours);
+        let transformed_refs;
+        let contours = match transformed {
+            Some(ref transformed) => {
+                transformed_refs = transformed.iter().map(|c| &c[..]).collect::<Vec<_>>();
+                &transformed_refs[..]
+            }
+            None => contours,
+        };
+
+        let points = contours
+            .iter()
+            .filter(|contour| contour.len() >= 3)
+            .flat_map(|contour| contour.iter());
+        let (min, max) = match helper_bounds(points) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let (min_x, min_y, max_x, max_y) = match self.clamp_to_visible(
+            min.get_x().floor() as i32,
+            min.get_y().floor() as i32,
+            max.get_x().ceil() as i32,
+            max.get_y().ceil() as i32,
+        ) {
+            Some(bounds) => bounds,
+            None => return,
+        };
+
+        let scanlines = if self.computes_coverage() {
+            Some(self.aa_samples)
+        } else {
+            None
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            if self.coverage_layer.is_none() {
+                self.fill_rows_in_parallel(contours, rule, scanlines, (min_x, min_y, max_x, max_y));
+                return;
+            }
+        }
+
+        self.fill_rows(contours, rule, scanlines, (min_x, min_y, max_x, max_y));
+    }
+
+    /// Does the drawing of `rasterize_filled_contours` within the given bounds, one row
+    /// after another.
+    fn fill_rows(
+        &mut self,
+        contours: &[&[Point2]],
+        rule: FillRule,
+        scanlines: Option<u32>,
+        bounds: (i32, i32, i32, i32),
+    ) {
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let mut row = RowCoverage::new((max_x - min_x + 1) as usize);
+
+        // Only a solid paint is the same color everywhere, and collecting
+        // coverages needs every pixel to go through the blending.
+        let opaque_color = match self.paint {
+            Paint::Solid(color)
+                if color.a >= 1f32
+                    && self.global_alpha >= 1f32
+                    && self.coverage_layer.is_none() =>
+            {
+                Some(color)
+            }
+            _ => None,
+        };
+
+        for y in min_y..=max_y {
+            row.measure(y, min_x, contours, rule, scanlines);
+            let coverage = &row.coverage;
+
+            let mut i = 0;
+            while i < coverage.len() {
+                let x = min_x + i as i32;
+                let cov = coverage[i];
+                i += 1;
+                if cov <= 0f32 {
+                    continue;
+                }
+
+                if let (Some(color), true) = (opaque_color, cov == 1f32) {
+                    // Covered pixels of an opaque color just get overwritten,
+                    // so we can set the whole run of them at once.
+                    while i < coverage.len() && coverage[i] == 1f32 {
+                        i += 1;
+                    }
+                    let x_end = (min_x + i as i32) as u64;
+                    self.backing.set_span(y as u64, x as u64, x_end, &color);
+                    continue;
+                }
+
+                let color = self.draw_color_at(x, y);
+                self.blend_pixel(x as u64, y as u64, &color, cov);
+            }
+        }
+    }
+
+    /// Does the drawing of `rasterize_filled_contours` within the given bounds with a
+    /// thread for each few rows. Each row only gets drawn to by a single thread, so the
+    /// pixels come out just like they do when drawing the rows one after another.
+    #[cfg(feature = "rayon")]
+    fn fill_rows_in_parallel(
+        &mut self,
+        contours: &[&[Point2]],
+        rule: FillRule,
+        scanlines: Option<u32>,
+        bounds: (i32, i32, i32, i32),
+    ) {
+        use rayon::prelude::*;
+
+        let (min_x, min_y, max_x, max_y) = bounds;
+        let width = self.backing.get_width() as usize;
+        let (paint, supersample) = (&self.paint, self.supersample);
+        let (gamma, conservative, global_alpha) =
+            (self.gamma, self.conservative, self.global_alpha);
+        let rows = &mut self.backing.buf[min_y as usize * width..(max_y as usize + 1) * width];
+
+        rows.par_chunks_mut(width).enumerate().for_each_init(
+            || RowCoverage::new((max_x - min_x + 1) as usize),
+            |row, (i, pixels)| {
+                let y = min_y + i as i32;
+                row.measure(y, min_x, contours, rule, scanlines);
+
+                for (i, &cov) in row.coverage.iter().enumerate() {
+                    if cov <= 0f32 {
+                        continue;
                     }
+                    let cov = if conservative { 1f32 } else { cov } * global_alpha;
+                    let x = min_x + i as i32;
+                    let color = helper_paint_color_at(paint, supersample, x, y);
+                    helper_blend_into(&mut pixels[x as usize], &color, cov, gamma);
+                }
+            },
+        );
+    }
+
+    /// Gets the color of the current paint at the center of the given pixel.
+    fn draw_color_at(&self, x: i32, y: i32) -> colorbuf::Color {
+        helper_paint_color_at(&self.paint, self.supersample, x, y)
+    }
+
+    /// Turns a point in the coordinates of the canvas into the coordinates
+    /// of its pixels, which differ only on supersampled canvases.
+    fn to_backing(&self, p: Point2) -> Point2 {
+        let factor = self.supersample as f32;
+        Point2::new(p.get_x() * factor, p.get_y() * factor)
+    }
+
+    /// Adds `coverage` to the coverage of the pixel at the given coordinates, if
+    /// coverages are being collected instead of drawn. Tells whether they are.
+    fn accumulate_coverage(&mut self, x: u64, y: u64, coverage: f32) -> bool {
+        match self.coverage_layer.as_mut() {
+            Some(layer) => {
+                if x < layer.get_width() && y < layer.get_height() {
+                    layer.add_coverage(x, y, coverage);
                 }
+                true
             }
+            None => false,
+        }
+    }
+
+    /// Blends `src` on top of the pixel at the given coordinates, where `coverage`
+    /// tells how much of the pixel is covered by whatever is being drawn.
+    ///
+    /// Like with all of the rasterizers, pixels which aren't on the canvas are skipped
+    /// instead of panicking, even though the bounding boxes should keep them out.
+    fn blend_pixel(&mut self, x: u64, y: u64, src: &colorbuf::Color, coverage: f32) {
+        let coverage = self.effective_coverage(coverage);
+        if self.accumulate_coverage(x, y, coverage) {
+            return;
         }
+
+        if x >= self.backing.get_width() || y >= self.backing.get_height() {
+            return;
+        }
+
+        // The global alpha applies only once the coverages have been collected, since
+        // they get capped at full coverage.
+        let index = self.backing.index(x, y);
+        let gamma = self.gamma;
+        let coverage = coverage * self.global_alpha;
+        helper_blend_into(&mut self.backing.buf[index], src, coverage, gamma);
     }
 
     fn rasterize_filled_rectangle(&mut self, p1: Point2, p2: Point2, p3: Point2, p4: Point2) {
@@ -346,52 +1539,2372 @@ impl Canvas {
         self.rasterize_convex_filled_polygon(&points[..]);
     }
 
+    /// Replaces the region of pixels around `seed` which have the same color as the pixel
+    /// at `seed` with the current paint. The colors of the pixels in the region can differ
+    /// from the color at `seed` by at most `tolerance` in each channel.
+    ///
+    /// The region only spreads horizontally and vertically, and not past the clip.
+    /// Like every other drawing, `seed` goes through the current transform.
+    pub fn flood_fill(&mut self, seed: Point2, tolerance: f32) {
+        let seed = self.transform.apply(seed);
+        let (seed_x, seed_y) = (seed.get_x().floor(), seed.get_y().floor());
+        let width = self.backing.get_width();
+        let height = self.backing.get_height();
+        if seed_x < 0f32 || seed_y < 0f32 || seed_x >= width as f32 || seed_y >= height as f32 {
+            return;
+        }
+        let (seed_x, seed_y) = (seed_x as u64, seed_y as u64);
+        if self.is_clipped(seed_x as i32, seed_y as i32) {
+            return;
+        }
+
+        let target = self.backing.get_pixel(seed_x, seed_y).unwrap();
+        if let Paint::Solid(color) = self.paint {
+            // Filling with the color which is already there changes nothing.
+            if color::colors_approx_eq(&color, &target, 0f32) {
+                return;
+            }
+        }
+
+        // The filled pixels might still match the target with other paints,
+        // so we keep track of which pixels have already been seen.
+        let mut visited = vec![false; helper_pixel_count(width, height)];
+        let matches = |c: &Canvas, visited: &[bool], x: u64, y: u64| {
+            !visited[(y * width + x) as usize]
+                && !c.is_clipped(x as i32, y as i32)
+                && color::colors_approx_eq(&c.backing.get_pixel(x, y).unwrap(), &target, tolerance)
+        };
+
+        let mut seeds = vec![(seed_x, seed_y)];
+        while let Some((x, y)) = seeds.pop() {
+            if !matches(self, &visited[..], x, y) {
+                continue;
+            }
+
+            // Fill the whole run of matching pixels on this row at once.
+            let mut left = x;
+            while left > 0 && matches(self, &visited[..], left - 1, y) {
+                left -= 1;
+            }
+            let mut right = x;
+            while right + 1 < width && matches(self, &visited[..], right + 1, y) {
+                right += 1;
+            }
+            for run_x in left..=right {
+                visited[(y * width + run_x) as usize] = true;
+                let color = self.draw_color_at(run_x as i32, y as i32);
+                self.backing.set_pixel(run_x, y, &color).unwrap();
+            }
+
+            // Each run of matching pixels next to this one gets a seed of its own.
+            let neighbours = [y.checked_sub(1), Some(y + 1).filter(|&y| y < height)];
+            for &next_y in neighbours.iter().flatten() {
+                let mut in_run = false;
+                for run_x in left..=right {
+                    let is_match = matches(self, &visited[..], run_x, next_y);
+                    if is_match && !in_run {
+                        seeds.push((run_x, next_y));
+                    }
+                    in_run = is_match;
+                }
+            }
+        }
+    }
+
+    /// Draws the pixels of `src` over this canvas, with the top left corner of `src`
+    /// at `dest`. The pixels get blended just like any other drawing, and the parts
+    /// falling outside of this canvas or its clip are left out.
+    ///
+    /// The pixels of the canvases are lined up by rounding `dest` to the nearest pixel.
+    pub fn blit(&mut self, src: &Canvas, dest: Point2) {
+        let offset_x = dest.get_x().round() as i64;
+        let offset_y = dest.get_y().round() as i64;
+        let width = self.backing.get_width() as i64;
+        let height = self.backing.get_height() as i64;
+
+        for src_y in 0..src.backing.get_height() {
+            let y = offset_y + src_y as i64;
+            if y < 0 || y >= height {
+                continue;
+            }
+
+            for src_x in 0..src.backing.get_width() {
+                let x = offset_x + src_x as i64;
+                if x < 0 || x >= width || self.is_clipped(x as i32, y as i32) {
+                    continue;
+                }
+
+                let color = src.backing.get_pixel(src_x, src_y).unwrap();
+                self.blend_pixel(x as u64, y as u64, &color, 1f32);
+            }
+        }
+    }
+
+    /// Copies the pixels within the axis-aligned rectangle with `min` and `max` as its
+    /// opposite corners into a new canvas of their own, like `fill_rect_background`
+    /// picks its pixels. The parts of the rectangle outside of this canvas are left out,
+    /// so the new canvas can be smaller than the rectangle, or even empty.
+    ///
+    /// Like with shapes, the rectangle is in the coordinates of the resolved canvas on
+    /// canvases made with `new_supersampled`, and it gets all of the pixels of the larger
+    /// canvas within the pixels it picks on the resolved one. The new canvas then stays
+    /// supersampled the same way, so that it resolves to the size of the rectangle.
+    ///
+    /// The new canvas keeps the background color, the gamma, the transform and whether
+    /// antialiasing is enabled from this canvas.
+    pub fn crop(&self, min: Point2, max: Point2) -> Canvas {
+        let scale = u64::from(self.supersample);
+        let (canvas_width, canvas_height) = (self.backing.get_width(), self.backing.get_height());
+        let resolved_rect =
+            helper_pixel_rect(min, max, canvas_width / scale, canvas_height / scale);
+        let [min_x, min_y, max_x, max_y] = [
+            resolved_rect[0] * scale,
+            resolved_rect[1] * scale,
+            resolved_rect[2] * scale,
+            resolved_rect[3] * scale,
+        ];
+        let (width, height) = (max_x - min_x, max_y - min_y);
+
+        let mut backing = CanvasColorBuf::new_zeroed(width, height);
+        if width > 0 {
+            for (y, row) in (min_y..max_y).zip(backing.buf.chunks_mut(width as usize)) {
+                let start = self.backing.index(min_x, y);
+                row.copy_from_slice(&self.backing.buf[start..start + width as usize]);
+            }
+        }
+
+        Canvas {
+            backing,
+            gamma: self.gamma,
+            transform: self.transform,
+            supersample: self.supersample,
+            antialias_enabled: self.antialias_enabled,
+            ..Canvas::new(0, 0, self.background_color)
+        }
+    }
+
+    /// Mirrors the pixels of the canvas from left to right.
+    pub fn flip_horizontal(&mut self) {
+        let width = self.backing.get_width() as usize;
+        if width == 0 {
+            return;
+        }
+        for row in self.backing.buf.chunks_mut(width) {
+            row.reverse();
+        }
+    }
+
+    /// Mirrors the pixels of the canvas from top to bottom.
+    pub fn flip_vertical(&mut self) {
+        let width = self.backing.get_width() as usize;
+        let height = self.backing.get_height() as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.backing.buf.split_at_mut((height - 1 - y) * width);
+            top[y * width..(y + 1) * width].swap_with_slice(&mut bottom[..width]);
+        }
+    }
+
+    /// Gets a copy of the canvas turned a quarter turn clockwise, as seen on the canvas.
+    /// The width of the new canvas is the height of this one, and the other way around.
+    ///
+    /// The new canvas keeps the background color and the gamma of this canvas.
+    pub fn rotate_90(&self) -> Canvas {
+        let (width, height) = (self.backing.get_width(), self.backing.get_height());
+
+        // The left column of this canvas becomes the top row of the new one.
+        let mut backing = CanvasColorBuf::new_zeroed(height, width);
+        for (index, color) in backing.buf.iter_mut().enumerate() {
+            let (x, y) = (index as u64 % height, index as u64 / height);
+            *color = self.backing.buf[self.backing.index(y, height - 1 - x)];
+        }
+
+        Canvas {
+            backing,
+            gamma: self.gamma,
+            ..Canvas::new(0, 0, self.background_color)
+        }
+    }
+
+    /// Blurs the whole canvas with a box blur, where every pixel becomes the average
+    /// of the pixels at most `radius` pixels away from it horizontally and vertically.
+    ///
+    /// The averaging is done with premultiplied alpha in the same gamma-corrected space
+    /// that the blending uses, so transparent pixels don't darken their surroundings.
+    /// Pixels past the edges of the canvas are treated as copies of the edge pixels.
+    pub fn box_blur(&mut self, radius: u32) {
+        if radius == 0 {
+            return;
+        }
+
+        let width = self.backing.get_width() as usize;
+        let height = self.backing.get_height() as usize;
+        let radius = radius as usize;
+        let gamma = self.gamma;
+
+        let mut linear = self
+            .backing
+            .buf
+            .iter()
+            .map(|color| helper_to_linear(color, gamma))
+            .collect::<Vec<_>>();
+        let mut blurred = linear.clone();
+
+        // The blur is separable, so first blur the rows and then the columns.
+        helper_box_blur_pass(&linear, &mut blurred, width, height, radius, |line, pos| {
+            line * width + pos
+        });
+        helper_box_blur_pass(&blurred, &mut linear, height, width, radius, |line, pos| {
+            pos * width + line
+        });
+
+        for (color, channels) in self.backing.buf.iter_mut().zip(linear) {
+            *color = helper_from_linear(channels, gamma);
+        }
+    }
+
+    /// Writes the pixels of the canvas into `bitmap` in the given format. The canvas
+    /// is left as it is, so it can be exported again after drawing more onto it.
     pub fn to_bytebuffer(
-        self,
+        &self,
         bitmap: &mut [u8],
         format: colorbuf::bitmap::ColorFormat,
         depth: colorbuf::bitmap::BitDepth,
         stride: &mut u64,
     ) -> std::result::Result<(), colorbuf::bitmap::BitmapError> {
-        colorbuf::bitmap::to_bitmap(self.backing, format, depth, stride, bitmap)
+        // Converting takes the buffer by value, so it gets a copy of ours.
+        let mut pixels = self.backing.clone();
+        if self.dither {
+            // Deeper formats have steps too small for banding to show.
+            if let colorbuf::bitmap::BitDepth::Eight = depth {
+                helper_dither(&mut pixels, 255f32);
+            }
+        }
+        colorbuf::bitmap::to_bitmap(pixels, format, depth, stride, bitmap)
     }
-}
 
-fn helper_even_odd_rule(x: f32, y: f32, points: &[Point2]) -> bool {
-    let mut inside = false;
-    let mut j = points.len() - 1;
-    for (i, _) in points.iter().enumerate() {
-        if ((points[i].get_y() > (y)) != (points[j].get_y() > (y)))
-            && ((x)
-                < (points[j].get_x() - points[i].get_x()) * ((y) - points[i].get_y())
-                    / (points[j].get_y() - points[i].get_y())
-                    + points[i].get_x())
+    /// Writes the canvas into a PNG file at `path`, as 8-bit RGBA.
+    #[cfg(feature = "png")]
+    pub fn save_png<P: AsRef<std::path::Path>>(&self, path: P) -> std::io::Result<()> {
+        use png::HasParameters;
+
+        let width = self.backing.get_width();
+        let height = self.backing.get_height();
+        let too_large = || std::io::Error::other("the canvas is too large for a PNG");
+        let png_width = u32::try_from(width).map_err(|_| too_large())?;
+        let png_height = u32::try_from(height).map_err(|_| too_large())?;
+        let length = helper_pixel_count(width, height)
+            .checked_mul(4)
+            .ok_or_else(too_large)?;
+        let mut buf = vec![0u8; length];
+        let mut stride = 0;
+        self.to_bytebuffer(
+            &mut buf[..],
+            colorbuf::bitmap::ColorFormat::RGBA,
+            colorbuf::bitmap::BitDepth::Eight,
+            &mut stride,
+        )
+        .map_err(|_| std::io::Error::other("couldn't convert the canvas into RGBA"))?;
+
+        let file = std::fs::File::create(path)?;
+        let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), png_width, png_height);
+        encoder.set(png::ColorType::RGBA).set(png::BitDepth::Eight);
+        let mut png_writer = encoder.write_header()?;
+        png_writer.write_image_data(&buf)?;
+        Ok(())
+    }
+
+    /// Creates a canvas out of an existing raster, with the rows of pixels packed
+    /// one after another without any padding.
+    ///
+    /// Currently only 8-bit RGBA is supported. The background and the draw color
+    /// of the resulting canvas are transparent black.
+    pub fn from_bytebuffer(
+        bytes: &[u8],
+        width: u64,
+        height: u64,
+        format: colorbuf::bitmap::ColorFormat,
+        depth: colorbuf::bitmap::BitDepth,
+    ) -> std::result::Result<Canvas, BytebufferError> {
+        let bytes_per_pixel = match (format, depth) {
+            (colorbuf::bitmap::ColorFormat::RGBA, colorbuf::bitmap::BitDepth::Eight) => 4,
+            _ => return Err(BytebufferError::UnsupportedFormat),
+        };
+        // Dimensions too large for their bytes to even be counted can't match either.
+        let length = width
+            .checked_mul(height)
+            .and_then(|pixels| pixels.checked_mul(bytes_per_pixel));
+        if length != Some(bytes.len() as u64) {
+            return Err(BytebufferError::LengthMismatch);
+        }
+
+        let mut ret = Canvas::new_uninitialized(width, height);
+        for (color, pixel) in ret
+            .backing
+            .buf
+            .iter_mut()
+            .zip(bytes.chunks(bytes_per_pixel as usize))
         {
-            inside = !inside;
+            *color = color::premultiply(&colorbuf::Color {
+                r: f32::from(pixel[0]) / 255f32,
+                g: f32::from(pixel[1]) / 255f32,
+                b: f32::from(pixel[2]) / 255f32,
+                a: f32::from(pixel[3]) / 255f32,
+            });
         }
-        j = i;
+
+        Ok(ret)
+    }
+
+    /// Creates a canvas which keeps `bytes` up to date with what it draws, starting
+    /// with the pixels which are already there. The bytes are laid out like with
+    /// `from_bytebuffer`, which also means that only 8-bit RGBA is supported, and the
+    /// whole canvas gets copied into them each time something is drawn with
+    /// `SliceCanvas::draw`.
+    pub fn from_slice(
+        bytes: &mut [u8],
+        width: u64,
+        height: u64,
+        format: colorbuf::bitmap::ColorFormat,
+        depth: colorbuf::bitmap::BitDepth,
+    ) -> std::result::Result<SliceCanvas<'_>, BytebufferError> {
+        let canvas = Canvas::from_bytebuffer(bytes, width, height, format, depth)?;
+        Ok(SliceCanvas { canvas, bytes })
     }
-    inside
 }
 
-fn helper_get_min<I, O>(i: I) -> Option<O>
-where
-    O: std::cmp::PartialOrd + std::clone::Clone,
-    I: IntoIterator<Item = O>,
-{
-    i.into_iter().fold(None, |min, x| match min {
-        None => Some(x),
-        Some(y) => Some(if x < y { x.clone() } else { y.clone() }),
-    })
+/// Gets the color of `paint` at the center of the given pixel of a canvas
+/// supersampled by `supersample`.
+fn helper_paint_color_at(paint: &Paint, supersample: u32, x: i32, y: i32) -> colorbuf::Color {
+    let factor = supersample as f32;
+    paint.color_at(Point2::new(
+        (x as f32 + 0.5f32) / factor,
+        (y as f32 + 0.5f32) / factor,
+    ))
 }
 
-fn helper_get_max<I, O>(i: I) -> Option<O>
-where
-    O: std::cmp::PartialOrd + std::clone::Clone,
-    I: IntoIterator<Item = O>,
-{
-    i.into_iter().fold(None, |min, x| match min {
-        None => Some(x),
-        Some(y) => Some(if x > y { x.clone() } else { y.clone() }),
-    })
+/// Blends `src` with the given coverage over the premultiplied color `pixel`,
+/// in the space given by `gamma`.
+fn helper_blend_into(
+    pixel: &mut colorbuf::Color,
+    src: &colorbuf::Color,
+    coverage: f32,
+    gamma: f32,
+) {
+    if src.a * coverage >= 1f32 {
+        // Nothing shows through an opaque color, so we don't need to blend.
+        *pixel = color::premultiply(src);
+        return;
+    }
+
+    let blent_color = colorbuf::Color {
+        r: src.r,
+        g: src.g,
+        b: src.b,
+        a: src.a * coverage,
+    };
+    let cur_color = color::unpremultiply(pixel);
+    *pixel = color::premultiply(&helper_blend_over(&cur_color, &blent_color, gamma));
+}
+
+/// Blends `src` over `dst` in the space given by `gamma`, where both colors
+/// and the result are straight colors.
+fn helper_blend_over(dst: &colorbuf::Color, src: &colorbuf::Color, gamma: f32) -> colorbuf::Color {
+    helper_blend(dst, src, BlendMode::SourceOver, gamma)
+}
+
+/// Blends `src` onto `dst` with the given mode in the space given by `gamma`,
+/// where both colors and the result are straight colors.
+///
+/// The color channels get weighted by how much each of the colors contributes
+/// to the resulting alpha, so the color of a transparent pixel doesn't leak into
+/// anything drawn over it. Where both colors are there, the mode decides the color.
+fn helper_blend(
+    dst: &colorbuf::Color,
+    src: &colorbuf::Color,
+    mode: BlendMode,
+    gamma: f32,
+) -> colorbuf::Color {
+    let out_a = src.a + dst.a * (1f32 - src.a);
+    if out_a <= 0f32 {
+        return colorbuf::Color {
+            r: 0f32,
+            g: 0f32,
+            b: 0f32,
+            a: 0f32,
+        };
+    }
+
+    let src_weight = src.a * (1f32 - dst.a);
+    let dst_weight = dst.a * (1f32 - src.a);
+    let both_weight = src.a * dst.a;
+    let blend = |src_channel: f32, dst_channel: f32| {
+        let (src_channel, dst_channel) = (src_channel.powf(gamma), dst_channel.powf(gamma));
+        let both = helper_blend_channel(dst_channel, src_channel, mode);
+        ((src_channel * src_weight + dst_channel * dst_weight + both * both_weight) / out_a)
+            .powf(1f32 / gamma)
+    };
+
+    colorbuf::Color {
+        r: blend(src.r, dst.r),
+        g: blend(src.g, dst.g),
+        b: blend(src.b, dst.b),
+        a: out_a,
+    }
+}
+
+/// Combines a channel of the source with the same channel of the destination.
+fn helper_blend_channel(dst: f32, src: f32, mode: BlendMode) -> f32 {
+    match mode {
+        BlendMode::SourceOver => src,
+        BlendMode::Multiply => src * dst,
+        BlendMode::Screen => src + dst - src * dst,
+        BlendMode::Add => (src + dst).min(1f32),
+        BlendMode::Overlay => {
+            if dst <= 0.5f32 {
+                2f32 * src * dst
+            } else {
+                1f32 - 2f32 * (1f32 - src) * (1f32 - dst)
+            }
+        }
+    }
+}
+
+/// Gets the amount of pixels in a buffer of the given size.
+///
+/// Panics if there are more pixels than can be addressed.
+fn helper_pixel_count(width: u64, height: u64) -> usize {
+    width
+        .checked_mul(height)
+        .and_then(|count| usize::try_from(count).ok())
+        .expect("canvas is too large")
+}
+
+/// The coverages of the pixels on a row of a filled shape, along with the buffers
+/// needed for working them out, so that they can be reused from row to row.
+struct RowCoverage {
+    coverage: Vec<f32>,
+    crossings: Vec<(f32, i32)>,
+    spans: Vec<(f32, f32)>,
+}
+
+impl RowCoverage {
+    fn new(len: usize) -> RowCoverage {
+        RowCoverage {
+            coverage: vec![0f32; len],
+            crossings: Vec::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Works out how much of each pixel on the row at `y`, starting from `min_x`, the contours
+    /// cover with the fill rule. With `scanlines`, the coverage is measured along that many
+    /// lines through the row, and without it a pixel is fully covered if its origin is inside.
+    fn measure(
+        &mut self,
+        y: i32,
+        min_x: i32,
+        contours: &[&[Point2]],
+        rule: FillRule,
+        scanlines: Option<u32>,
+    ) {
+        let coverage = &mut self.coverage;
+        let max_x = min_x + coverage.len() as i32 - 1;
+        for cov in coverage.iter_mut() {
+            *cov = 0f32;
+        }
+
+        let scanlines_per_row = scanlines.unwrap_or(1);
+        for scanline in 0..scanlines_per_row {
+            let scan_y = match scanlines {
+                Some(_) => (y as f32) + ((scanline as f32) + 0.5f32) / (scanlines_per_row as f32),
+                None => y as f32,
+            };
+            helper_scanline_crossings(scan_y, contours, &mut self.crossings);
+            helper_scanline_spans(&self.crossings[..], rule, &mut self.spans);
+
+            for &(span_start, span_end) in self.spans.iter() {
+                if scanlines.is_some() {
+                    let first = (span_start.floor() as i32).max(min_x);
+                    let last = (span_end.ceil() as i32 - 1).min(max_x);
+                    for x in first..=last {
+                        let overlap = span_end.min((x + 1) as f32) - span_start.max(x as f32);
+                        coverage[(x - min_x) as usize] +=
+                            overlap.max(0f32) / (scanlines_per_row as f32);
+                    }
+                } else {
+                    // Without antialiasing, a pixel is filled if its origin is inside.
+                    let first = (span_start.ceil() as i32).max(min_x);
+                    let last = (span_end.ceil() as i32 - 1).min(max_x);
+                    for x in first..=last {
+                        coverage[(x - min_x) as usize] = 1f32;
+                    }
+                }
+            }
+        }
+
+        // The coverages are sums of fractions, so allow for a bit of rounding error.
+        for cov in coverage.iter_mut() {
+            if *cov >= 1f32 - 1e-3 {
+                *cov = 1f32;
+            }
+        }
+    }
+}
+
+/// Collects the x-coordinates where the edges of the contours cross the horizontal
+/// line at `y` into `crossings`, sorted from left to right. Each crossing also has
+/// the direction of its edge, which is 1 for edges going down and -1 for edges going up.
+fn helper_scanline_crossings(y: f32, contours: &[&[Point2]], crossings: &mut Vec<(f32, i32)>) {
+    crossings.clear();
+
+    for points in contours.iter().filter(|contour| contour.len() >= 3) {
+        let mut j = points.len() - 1;
+        for (i, _) in points.iter().enumerate() {
+            let (p_i, p_j) = (points[i], points[j]);
+            if (p_i.get_y() > y) != (p_j.get_y() > y) {
+                let x = p_i.get_x()
+                    + (y - p_i.get_y()) * (p_j.get_x() - p_i.get_x()) / (p_j.get_y() - p_i.get_y());
+                let direction = if p_i.get_y() > p_j.get_y() { 1 } else { -1 };
+                crossings.push((x, direction));
+            }
+            j = i;
+        }
+    }
+
+    crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+}
+
+/// Collects the parts of a scanline which are inside the polygon according to
+/// the fill rule into `spans`, given the crossings of the scanline from
+/// `helper_scanline_crossings`.
+fn helper_scanline_spans(crossings: &[(f32, i32)], rule: FillRule, spans: &mut Vec<(f32, f32)>) {
+    spans.clear();
+
+    let mut winding = 0;
+    let mut span_start = 0f32;
+    for &(x, direction) in crossings.iter() {
+        let was_inside = helper_is_inside(winding, rule);
+        winding += direction;
+        let is_inside = helper_is_inside(winding, rule);
+
+        if !was_inside && is_inside {
+            span_start = x;
+        } else if was_inside && !is_inside {
+            spans.push((span_start, x));
+        }
+    }
+}
+
+/// Tells whether a point with the given winding number is inside a shape.
+fn helper_is_inside(winding: i32, rule: FillRule) -> bool {
+    match rule {
+        FillRule::EvenOdd => winding % 2 != 0,
+        FillRule::NonZero => winding != 0,
+    }
+}
+
+fn helper_even_odd_rule(x: f32, y: f32, points: &[Point2]) -> bool {
+    // Anything less than a triangle has no area to be inside of.
+    if points.len() < 3 {
+        return false;
+    }
+
+    let mut inside = false;
+    let mut j = points.len() - 1;
+    for (i, _) in points.iter().enumerate() {
+        // Horizontal edges can never be crossed by our horizontal ray,
+        // and they would make us divide by zero below.
+        if points[i].get_y() == points[j].get_y() {
+            j = i;
+            continue;
+        }
+
+        if ((points[i].get_y() > (y)) != (points[j].get_y() > (y)))
+            && ((x)
+                < (points[j].get_x() - points[i].get_x()) * ((y) - points[i].get_y())
+                    / (points[j].get_y() - points[i].get_y())
+                    + points[i].get_x())
+        {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
+}
+
+/// Tells whether any edge of the polygon goes through the pixel at `(x, y)`.
+fn helper_polygon_touches_pixel(points: &[Point2], x: f32, y: f32) -> bool {
+    if points.is_empty() {
+        return false;
+    }
+
+    let mut j = points.len() - 1;
+    for (i, &p_i) in points.iter().enumerate() {
+        if helper_segment_touches_pixel(points[j], p_i, x, y) {
+            return true;
+        }
+        j = i;
+    }
+    false
+}
+
+/// Tells whether the line segment from `a` to `b` goes through the pixel at `(x, y)`,
+/// by clipping the segment to the pixel and checking if anything is left.
+fn helper_segment_touches_pixel(a: Point2, b: Point2, x: f32, y: f32) -> bool {
+    let (dx, dy) = (b.get_x() - a.get_x(), b.get_y() - a.get_y());
+    // Each side of the pixel as the direction of the segment towards the outside of
+    // the side, and how far inside of the side the segment starts.
+    let sides = [
+        (-dx, a.get_x() - x),
+        (dx, x + 1f32 - a.get_x()),
+        (-dy, a.get_y() - y),
+        (dy, y + 1f32 - a.get_y()),
+    ];
+
+    let (mut t_enter, mut t_exit) = (0f32, 1f32);
+    for &(towards_outside, inside_by) in sides.iter() {
+        if towards_outside == 0f32 {
+            // The segment is parallel to the side, and so it's either always
+            // on the inside of it or never.
+            if inside_by < 0f32 {
+                return false;
+            }
+            continue;
+        }
+
+        let t = inside_by / towards_outside;
+        if towards_outside < 0f32 {
+            t_enter = t_enter.max(t);
+        } else {
+            t_exit = t_exit.min(t);
+        }
+        if t_enter > t_exit {
+            return false;
+        }
+    }
+    true
+}
+
+/// Gets the smallest and the largest distance from `center` to the points of the pixel
+/// at `(x, y)`.
+fn helper_pixel_distance_range(center: Point2, x: f32, y: f32) -> (f32, f32) {
+    let (cx, cy) = (center.get_x(), center.get_y());
+    let nearest = Point2::new(cx.max(x).min(x + 1f32), cy.max(y).min(y + 1f32));
+    let farthest = Point2::new(
+        if cx - x < x + 1f32 - cx { x + 1f32 } else { x },
+        if cy - y < y + 1f32 - cy { y + 1f32 } else { y },
+    );
+    (center.distance(nearest), center.distance(farthest))
+}
+
+/// Estimates how much of the pixel at `(x, y)` a disk covers from the signed distance
+/// between the center of the pixel and the edge of the disk. A pixel is a unit wide,
+/// so the coverage goes from full to none as the edge passes over the pixel center.
+fn helper_disk_coverage(center: Point2, radius: f32, x: f32, y: f32) -> f32 {
+    if radius <= 0f32 {
+        return 0f32;
+    }
+
+    let dist = center.distance(Point2::new(x + 0.5f32, y + 0.5f32)) - radius;
+    // A disk smaller than a pixel can't cover more than its own area.
+    let area = std::f32::consts::PI * radius * radius;
+    (0.5f32 - dist).clamp(0f32, 1f32).min(area)
+}
+
+/// Gets how far into the pixel the subpixel `index` of the `per_side` subpixels along one
+/// side of it is sampled. The samples are at the middles of the subpixels, so that they
+/// are spread out evenly instead of leaning towards the top left of the pixel.
+fn helper_subpixel_offset(index: usize, per_side: usize) -> f32 {
+    ((index as f32) + 0.5f32) / (per_side as f32)
+}
+
+/// Tells whether the whole pixel at `(x, y)` is within the wedge spanned by `arc`
+/// around `center`. This can only be decided from the corners of the pixel when the
+/// wedge is convex, so for wider arcs this just says no.
+fn helper_arc_contains_pixel(center: Point2, arc: Option<(f32, f32)>, x: f32, y: f32) -> bool {
+    match arc {
+        None => true,
+        Some((_, sweep)) if sweep > std::f32::consts::PI => false,
+        Some(_) => [(0f32, 0f32), (1f32, 0f32), (1f32, 1f32), (0f32, 1f32)]
+            .iter()
+            .all(|&(x_off, y_off)| {
+                helper_within_arc(Point2::new(x + x_off, y + y_off), center, arc)
+            }),
+    }
+}
+
+/// Checks whether the direction from `center` to `p` lies within the `arc`, which
+/// is given as the starting angle and the sweep of the arc, both in radians.
+fn helper_within_arc(p: Point2, center: Point2, arc: Option<(f32, f32)>) -> bool {
+    match arc {
+        None => true,
+        Some((start, sweep)) => {
+            let offset = p - center;
+            let angle = offset.get_y().atan2(offset.get_x());
+            (angle - start).rem_euclid(2f32 * std::f32::consts::PI) <= sweep
+        }
+    }
+}
+
+/// The order in which the pixels of each 4 by 4 block get rounded up, spread out
+/// so that any amount of them being rounded up looks as even as it can.
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+/// Moves the color channels of every pixel by less than half a step of the `levels`
+/// they get rounded to, following the Bayer matrix, so that rounding them then
+/// dithers the pixels.
+fn helper_dither(pixels: &mut CanvasColorBuf, levels: f32) {
+    let width = pixels.width as usize;
+    for (i, pixel) in pixels.buf.iter_mut().enumerate() {
+        let (x, y) = (i % width, i / width);
+        let threshold = (f32::from(BAYER_4X4[y % 4][x % 4]) + 0.5f32) / 16f32 - 0.5f32;
+        let offset = threshold / levels;
+
+        let straight = color::unpremultiply(pixel);
+        *pixel = color::premultiply(&colorbuf::Color {
+            r: (straight.r + offset).clamp(0f32, 1f32),
+            g: (straight.g + offset).clamp(0f32, 1f32),
+            b: (straight.b + offset).clamp(0f32, 1f32),
+            a: straight.a,
+        });
+    }
+}
+
+/// Turns a premultiplied color into premultiplied channels in the gamma-decoded
+/// space, where averaging colors gives the same results as blending them does.
+fn helper_to_linear(color: &colorbuf::Color, gamma: f32) -> [f32; 4] {
+    let linear = color::premultiply(&helper_gamma(&color::unpremultiply(color), gamma));
+    [linear.r, linear.g, linear.b, linear.a]
+}
+
+/// Turns channels from `helper_to_linear` back into a premultiplied color.
+fn helper_from_linear([r, g, b, a]: [f32; 4], gamma: f32) -> colorbuf::Color {
+    let linear = color::unpremultiply(&colorbuf::Color { r, g, b, a });
+    color::premultiply(&helper_gamma(&linear, 1f32 / gamma))
+}
+
+/// Raises the color channels of a straight color to the power of `gamma`.
+fn helper_gamma(color: &colorbuf::Color, gamma: f32) -> colorbuf::Color {
+    colorbuf::Color {
+        r: color.r.powf(gamma),
+        g: color.g.powf(gamma),
+        b: color.b.powf(gamma),
+        a: color.a,
+    }
+}
+
+/// Does one pass of a box blur over `lines` lines of `len` pixels each, where
+/// `index(line, pos)` tells where in the buffers the pixel at `pos` on `line` is.
+fn helper_box_blur_pass<F>(
+    src: &[[f32; 4]],
+    dst: &mut [[f32; 4]],
+    len: usize,
+    lines: usize,
+    radius: usize,
+    index: F,
+) where
+    F: Fn(usize, usize) -> usize,
+{
+    if len == 0 {
+        return;
+    }
+
+    let window = (2 * radius + 1) as f32;
+    // Positions past the ends of the line are clamped to the edge pixels.
+    let clamped = |pos: isize| pos.max(0).min(len as isize - 1) as usize;
+
+    for line in 0..lines {
+        let mut sum = [0f32; 4];
+        for pos in -(radius as isize)..=(radius as isize) {
+            let p = src[index(line, clamped(pos))];
+            for (s, c) in sum.iter_mut().zip(p.iter()) {
+                *s += c;
+            }
+        }
+
+        for pos in 0..len {
+            let out = &mut dst[index(line, pos)];
+            for (o, s) in out.iter_mut().zip(sum.iter()) {
+                *o = s / window;
+            }
+
+            // Slide the window forward by one pixel.
+            let entering = src[index(line, clamped((pos + radius + 1) as isize))];
+            let leaving = src[index(line, clamped(pos as isize - radius as isize))];
+            for i in 0..4 {
+                sum[i] += entering[i] - leaving[i];
+            }
+        }
+    }
+}
+
+/// Finds the pixels whose origins lie within the axis-aligned rectangle with `p1` and `p2`
+/// as its opposite corners, as `[min_x, min_y, max_x, max_y]` with exclusive maximums.
+/// The rectangle is clamped to be within a canvas of the given size.
+fn helper_pixel_rect(p1: Point2, p2: Point2, width: u64, height: u64) -> [u64; 4] {
+    let clamp_to = |v: f32, limit: u64| (v.ceil().max(0f32) as u64).min(limit);
+    [
+        clamp_to(p1.get_x().min(p2.get_x()), width),
+        clamp_to(p1.get_y().min(p2.get_y()), height),
+        clamp_to(p1.get_x().max(p2.get_x()), width),
+        clamp_to(p1.get_y().max(p2.get_y()), height),
+    ]
+}
+
+/// Gets the minimum and the maximum corners of the axis-aligned box containing
+/// all of the points, if there are any.
+fn helper_bounds<'a, I>(points: I) -> Option<(Point2, Point2)>
+where
+    I: IntoIterator<Item = &'a Point2>,
+{
+    points.into_iter().fold(None, |bounds, &p| match bounds {
+        None => Some((p, p)),
+        Some((min, max)) => Some((min.min(p), max.max(p))),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::{Circle, Line, Rectangle};
+
+    fn rgba(r: f32, g: f32, b: f32, a: f32) -> colorbuf::Color {
+        colorbuf::Color { r, g, b, a }
+    }
+
+    /// A canvas with a black background which draws in white.
+    fn black_canvas(width: u64, height: u64) -> Canvas {
+        let mut canvas = Canvas::new(width, height, rgba(0f32, 0f32, 0f32, 1f32));
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        canvas
+    }
+
+    fn red(canvas: &Canvas, x: u64, y: u64) -> f32 {
+        canvas.get_pixel(x, y).unwrap().r
+    }
+
+    #[test]
+    fn filled_circle() {
+        let mut canvas = black_canvas(20, 20);
+        Circle::new(Point2::new(10f32, 10f32), 5f32).fill(&mut canvas);
+        assert_eq!(red(&canvas, 10, 10), 1f32);
+        assert_eq!(red(&canvas, 0, 0), 0f32);
+        assert_eq!(red(&canvas, 19, 0), 0f32);
+        assert_eq!(red(&canvas, 0, 19), 0f32);
+        assert_eq!(red(&canvas, 19, 19), 0f32);
+
+        // Circles smaller than a pixel still cover some of it.
+        let mut canvas = black_canvas(20, 20);
+        Circle::new(Point2::new(10.5, 10.5), 0.2).fill(&mut canvas);
+        assert!(red(&canvas, 10, 10) > 0f32);
+    }
+
+    #[test]
+    fn diagonal_edge_coverage() {
+        // The edge runs along the diagonal of the pixels it crosses, so a grid of
+        // samples sees about half of each of them, while samples on the diagonal
+        // would see all or nothing.
+        let mut canvas = black_canvas(20, 20);
+        canvas.set_gamma(1f32);
+        let triangle = [
+            Point2::new(0f32, 0f32),
+            Point2::new(20f32, 0f32),
+            Point2::new(20f32, 20f32),
+        ];
+        canvas.rasterize_convex_filled_polygon(&triangle[..]);
+        for i in 2..18 {
+            let on_edge = red(&canvas, i, i);
+            assert!(on_edge > 0.4 && on_edge < 0.6, "{} at {}", on_edge, i);
+        }
+
+        for y in 2..18 {
+            for x in 2..18 {
+                // Mirroring across the edge gives the part which isn't covered.
+                let sum = red(&canvas, x, y) + red(&canvas, y, x);
+                assert!((sum - 1f32).abs() < 0.1, "{} at {}, {}", sum, x, y);
+                if x > 2 {
+                    assert!(red(&canvas, x, y) >= red(&canvas, x - 1, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reading_pixels() {
+        let mut canvas = black_canvas(4, 3);
+        assert_eq!((canvas.width(), canvas.height()), (4, 3));
+        canvas.draw_line(Point2::new(0f32, 1.5), Point2::new(4f32, 1.5), 1f32);
+        assert_eq!(red(&canvas, 0, 0), 0f32);
+        assert_eq!(red(&canvas, 3, 1), 1f32);
+
+        let out_of_bounds = [(4, 0), (0, 3), (4, 3), (u64::MAX, 0)];
+        for &(x, y) in out_of_bounds.iter() {
+            let pixel = canvas.get_pixel(x, y);
+            assert!(matches!(
+                pixel,
+                Err(colorbuf::ColorBufError::InvalidCoordinate)
+            ));
+        }
+    }
+
+    #[test]
+    fn antialias_samples_take_effect() {
+        let draw = |samples: u32| {
+            let mut canvas = black_canvas(20, 20);
+            canvas.set_antialias_samples(samples);
+            let triangle = [
+                Point2::new(2.3, 3.1),
+                Point2::new(17.6, 5.2),
+                Point2::new(8.4, 16.9),
+            ];
+            canvas.rasterize_convex_filled_polygon(&triangle[..]);
+            canvas
+        };
+        let (coarse, fine) = (draw(2), draw(32));
+        let differs = (0..20).any(|y| (0..20).any(|x| red(&coarse, x, y) != red(&fine, x, y)));
+        assert!(differs);
+
+        // No samples at all would cover nothing, so the count is kept at one or more.
+        let none = draw(0);
+        assert_eq!(red(&none, 10, 10), 1f32);
+    }
+
+    #[test]
+    fn blending_gamma() {
+        let blend = |gamma: f32| {
+            let mut canvas = black_canvas(4, 4);
+            canvas.enable_antialias(false);
+            canvas.set_gamma(gamma);
+            canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 0.5));
+            Rectangle::new(Point2::new(0f32, 0f32), Point2::new(4f32, 4f32)).fill(&mut canvas);
+            red(&canvas, 1, 1)
+        };
+        // Half of the way from 0 to 1 in the space raised to the power of gamma.
+        assert!((blend(1f32) - 0.5).abs() < 1e-4);
+        assert!((blend(2.2) - 0.5f32.powf(1f32 / 2.2)).abs() < 1e-4);
+
+        // Gamma values which can't be inverted are ignored.
+        let mut canvas = black_canvas(1, 1);
+        canvas.set_gamma(0f32);
+        canvas.set_gamma(-1f32);
+        assert_eq!(canvas.gamma, 2.2);
+    }
+
+    #[test]
+    fn large_canvas_fill() {
+        let (width, height) = (800, 600);
+        let mut canvas = black_canvas(width, height);
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(100f32, 50f32), Point2::new(700f32, 550f32)).fill(&mut canvas);
+        assert_eq!(red(&canvas, 100, 50), 1f32);
+        assert_eq!(red(&canvas, 699, 549), 1f32);
+        assert_eq!(red(&canvas, 99, 300), 0f32);
+        assert_eq!(red(&canvas, 400, 550), 0f32);
+        assert_eq!(red(&canvas, width - 1, height - 1), 0f32);
+
+        let mut bitmap = vec![0u8; (width * height * 4) as usize];
+        let mut stride = 0;
+        canvas
+            .to_bytebuffer(
+                &mut bitmap[..],
+                colorbuf::bitmap::ColorFormat::RGBA,
+                colorbuf::bitmap::BitDepth::Eight,
+                &mut stride,
+            )
+            .unwrap();
+        let byte = |x: u64, y: u64, channel: u64| bitmap[(y * stride + x * 4 + channel) as usize];
+        assert_eq!(stride, width * 4);
+        assert_eq!([byte(400, 300, 0), byte(400, 300, 3)], [255, 255]);
+        assert_eq!([byte(10, 10, 0), byte(10, 10, 3)], [0, 255]);
+    }
+
+    #[test]
+    fn concave_polygon() {
+        // An arrow pointing to the right, with a notch at its back.
+        let arrow = [
+            Point2::new(5f32, 5f32),
+            Point2::new(35f32, 20f32),
+            Point2::new(5f32, 35f32),
+            Point2::new(15f32, 20f32),
+        ];
+        for &antialias in [true, false].iter() {
+            let mut canvas = black_canvas(40, 40);
+            canvas.enable_antialias(antialias);
+            canvas.rasterize_filled_polygon(&arrow[..]);
+            assert_eq!(red(&canvas, 8, 20), 0f32);
+            assert_eq!(red(&canvas, 12, 20), 0f32);
+            assert_eq!(red(&canvas, 20, 20), 1f32);
+            assert_eq!(red(&canvas, 8, 8), 1f32);
+            assert_eq!(red(&canvas, 8, 31), 1f32);
+        }
+
+        // The pixels which the edge goes through are partially covered.
+        let mut canvas = black_canvas(40, 40);
+        let square = [
+            Point2::new(10f32, 10f32),
+            Point2::new(20.5, 10f32),
+            Point2::new(20.5, 20f32),
+            Point2::new(10f32, 20f32),
+        ];
+        canvas.rasterize_filled_polygon(&square[..]);
+        assert_eq!(red(&canvas, 19, 19), 1f32);
+        let edge = red(&canvas, 20, 15);
+        assert!(edge > 0.3 && edge < 0.9, "{}", edge);
+        assert_eq!(red(&canvas, 21, 15), 0f32);
+    }
+
+    #[test]
+    fn even_odd_rule_degenerate_input() {
+        assert!(!helper_even_odd_rule(0f32, 0f32, &[]));
+        let two = [Point2::new(0f32, 0f32), Point2::new(10f32, 10f32)];
+        assert!(!helper_even_odd_rule(5f32, 5f32, &two[..]));
+
+        // Both the top and the bottom edges are horizontal.
+        let square = [
+            Point2::new(0f32, 0f32),
+            Point2::new(10f32, 0f32),
+            Point2::new(10f32, 10f32),
+            Point2::new(0f32, 10f32),
+        ];
+        assert!(helper_even_odd_rule(5f32, 5f32, &square[..]));
+        assert!(helper_even_odd_rule(0.5, 9.5, &square[..]));
+        assert!(!helper_even_odd_rule(5f32, -1f32, &square[..]));
+        assert!(!helper_even_odd_rule(11f32, 5f32, &square[..]));
+        assert!(!helper_even_odd_rule(-5f32, 0f32, &square[..]));
+        assert!(!helper_even_odd_rule(15f32, 10f32, &square[..]));
+    }
+
+    #[test]
+    fn background_color() {
+        let mut canvas = Canvas::new(10, 10, rgba(0.2, 0.4, 0.6, 1f32));
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        assert_eq!(canvas.get_background_color().g, 0.4);
+
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(10f32, 10f32)).fill(&mut canvas);
+        canvas.fill_rect_background(Point2::new(2f32, 2f32), Point2::new(5f32, 5f32));
+        assert!((red(&canvas, 2, 2) - 0.2).abs() < 1e-6);
+        assert!((red(&canvas, 4, 4) - 0.2).abs() < 1e-6);
+        assert_eq!(red(&canvas, 5, 5), 1f32);
+        assert_eq!(red(&canvas, 1, 3), 1f32);
+
+        canvas.set_background_color(rgba(0f32, 0f32, 1f32, 1f32));
+        canvas.fill_background();
+        assert_eq!(canvas.get_pixel(7, 7).unwrap().b, 1f32);
+        assert_eq!(red(&canvas, 7, 7), 0f32);
+    }
+
+    #[test]
+    fn box_blur_spreads_evenly() {
+        let mut canvas = black_canvas(9, 9);
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(4f32, 4f32), Point2::new(5f32, 5f32)).fill(&mut canvas);
+        assert_eq!(red(&canvas, 4, 4), 1f32);
+        assert_eq!(red(&canvas, 5, 4), 0f32);
+
+        canvas.box_blur(1);
+        let left = red(&canvas, 3, 4);
+        assert!(left > 0f32);
+        for &(x, y) in [(5, 4), (4, 3), (4, 5)].iter() {
+            assert!((red(&canvas, x, y) - left).abs() < 1e-6);
+        }
+        let corner = red(&canvas, 3, 3);
+        assert!(corner > 0f32);
+        for &(x, y) in [(5, 3), (3, 5), (5, 5)].iter() {
+            assert!((red(&canvas, x, y) - corner).abs() < 1e-6);
+        }
+        assert_eq!(red(&canvas, 2, 4), 0f32);
+        assert_eq!(red(&canvas, 4, 6), 0f32);
+    }
+
+    #[test]
+    fn clip_rect() {
+        let mut canvas = black_canvas(40, 40);
+        canvas.set_clip_rect(Point2::new(10f32, 10f32), Point2::new(20f32, 20f32));
+        Circle::new(Point2::new(20f32, 20f32), 15f32).fill(&mut canvas);
+        for y in 0..40 {
+            for x in 0..40 {
+                let inside = (10..20).contains(&x) && (10..20).contains(&y);
+                assert_eq!(red(&canvas, x, y) == 1f32, inside, "at {}, {}", x, y);
+            }
+        }
+
+        // Clip rectangles reaching past the canvas are cut down to it.
+        canvas.set_clip_rect(Point2::new(-10f32, -10f32), Point2::new(100f32, 5f32));
+        Circle::new(Point2::new(20f32, 20f32), 30f32).fill(&mut canvas);
+        assert_eq!(red(&canvas, 39, 4), 1f32);
+        assert_eq!(red(&canvas, 30, 5), 0f32);
+
+        canvas.clear_clip();
+        Circle::new(Point2::new(20f32, 20f32), 15f32).fill(&mut canvas);
+        assert_eq!(red(&canvas, 30, 20), 1f32);
+    }
+
+    #[test]
+    fn uninitialized_canvas() {
+        let mut canvas = Canvas::new_uninitialized(300, 200);
+        assert_eq!((canvas.width(), canvas.height()), (300, 200));
+        let untouched = canvas.get_pixel(299, 199).unwrap();
+        assert_eq!(
+            [untouched.r, untouched.g, untouched.b, untouched.a],
+            [0f32; 4]
+        );
+        assert_eq!(canvas.get_background_color().a, 0f32);
+
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(1f32, 1f32)).fill(&mut canvas);
+        assert_eq!(canvas.get_pixel(0, 0).unwrap().a, 1f32);
+        assert_eq!(canvas.get_pixel(1, 0).unwrap().a, 0f32);
+    }
+
+    #[test]
+    fn bytebuffer_round_trip() {
+        use colorbuf::bitmap::{BitDepth, ColorFormat};
+
+        let mut canvas = black_canvas(10, 10);
+        canvas.set_draw_color(rgba(1f32, 0.5, 0.25, 1f32));
+        Circle::new(Point2::new(5f32, 5f32), 3f32).fill(&mut canvas);
+
+        let mut bytes = vec![0u8; 400];
+        let mut stride = 0;
+        let (format, depth) = (ColorFormat::RGBA, BitDepth::Eight);
+        canvas
+            .to_bytebuffer(&mut bytes[..], format, depth, &mut stride)
+            .unwrap();
+        let loaded =
+            Canvas::from_bytebuffer(&bytes[..], 10, 10, ColorFormat::RGBA, BitDepth::Eight)
+                .unwrap();
+        // The colors can't get further off than a step of the 8-bit channels.
+        for y in 0..10 {
+            for x in 0..10 {
+                let original = canvas.get_pixel(x, y).unwrap();
+                let reloaded = loaded.get_pixel(x, y).unwrap();
+                let epsilon = 1f32 / 255f32;
+                assert!(color::colors_approx_eq(&original, &reloaded, epsilon));
+            }
+        }
+
+        let short = Canvas::from_bytebuffer(&bytes[..], 10, 11, ColorFormat::RGBA, BitDepth::Eight);
+        assert_eq!(short.err(), Some(BytebufferError::LengthMismatch));
+        // Counting the bytes of this many pixels would wrap around to no bytes at all.
+        let huge = Canvas::from_bytebuffer(&[], 1 << 62, 1, ColorFormat::RGBA, BitDepth::Eight);
+        assert_eq!(huge.err(), Some(BytebufferError::LengthMismatch));
+        let deep = Canvas::from_bytebuffer(&bytes[..], 5, 5, ColorFormat::RGBA, BitDepth::Sixteen);
+        assert_eq!(deep.err(), Some(BytebufferError::UnsupportedFormat));
+    }
+
+    #[test]
+    fn translucent_fill_without_antialiasing() {
+        let mut canvas = Canvas::new(8, 8, rgba(0f32, 0f32, 1f32, 1f32));
+        canvas.enable_antialias(false);
+        canvas.set_gamma(1f32);
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 0.5));
+        Rectangle::new(Point2::new(2f32, 2f32), Point2::new(6f32, 6f32)).fill(&mut canvas);
+        let blent = canvas.get_pixel(3, 3).unwrap();
+        let expected = rgba(0.5, 0f32, 0.5, 1f32);
+        assert!(color::colors_approx_eq(&blent, &expected, 1e-5));
+        assert_eq!(canvas.get_pixel(1, 1).unwrap().b, 1f32);
+    }
+
+    #[test]
+    fn convenience_drawing() {
+        let (mut quick, mut explicit) = (black_canvas(30, 30), black_canvas(30, 30));
+        quick.draw_line(Point2::new(1f32, 2f32), Point2::new(25f32, 20f32), 3f32);
+        Line::new(Point2::new(1f32, 2f32), Point2::new(25f32, 20f32)).stroke(&mut explicit, 3f32);
+        quick.draw_circle(Point2::new(15f32, 15f32), 7f32, 2f32);
+        Circle::new(Point2::new(15f32, 15f32), 7f32).stroke(&mut explicit, 2f32);
+        assert!(quick.pixels_equal(&explicit, 0f32));
+    }
+
+    #[test]
+    fn coverage_mask() {
+        let mut canvas = Canvas::new(30, 30, rgba(0f32, 0f32, 1f32, 1f32));
+        let mask = canvas.coverage_mask(&Circle::new(Point2::new(15f32, 15f32), 8.3));
+        assert_eq!(mask.len(), 900);
+        assert_eq!(mask[15 * 30 + 15], 1f32);
+        assert_eq!(mask[0], 0f32);
+        assert!(mask
+            .iter()
+            .any(|&coverage| coverage > 0.05 && coverage < 0.95));
+        // Nothing got drawn.
+        assert!((0..30).all(|y| (0..30).all(|x| red(&canvas, x, y) == 0f32)));
+    }
+
+    #[test]
+    fn blit_at_offset() {
+        let mut canvas = black_canvas(20, 20);
+        let red_square = Canvas::new(4, 4, rgba(1f32, 0f32, 0f32, 1f32));
+        canvas.blit(&red_square, Point2::new(5.4, 7.6));
+        for y in 0..20 {
+            for x in 0..20 {
+                let inside = (5..9).contains(&x) && (8..12).contains(&y);
+                assert_eq!(red(&canvas, x, y), if inside { 1f32 } else { 0f32 });
+            }
+        }
+
+        // Whatever falls outside of the canvas is left out.
+        canvas.blit(&red_square, Point2::new(18f32, -2f32));
+        assert_eq!(red(&canvas, 19, 1), 1f32);
+        assert_eq!(red(&canvas, 19, 2), 0f32);
+        assert_eq!(red(&canvas, 17, 0), 0f32);
+    }
+
+    #[test]
+    fn overlapping_translucent_shapes() {
+        let mut canvas = Canvas::new_uninitialized(20, 20);
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 0.5));
+        Circle::new(Point2::new(8f32, 10f32), 6f32).fill(&mut canvas);
+        Circle::new(Point2::new(12f32, 10f32), 6f32).fill(&mut canvas);
+        // The transparent black underneath doesn't darken the white.
+        let overlap = canvas.get_pixel(10, 10).unwrap();
+        assert!((overlap.r - 1f32).abs() < 1e-5 && (overlap.a - 0.75).abs() < 1e-5);
+        let edge = canvas.get_pixel(14, 4).unwrap();
+        assert!(edge.a > 0f32 && edge.a < 0.5);
+        assert!((edge.r - 1f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn thin_lines_stay_continuous() {
+        let mut canvas = black_canvas(60, 60);
+        Line::new(Point2::new(2f32, 3f32), Point2::new(57f32, 41f32)).stroke(&mut canvas, 1f32);
+        for x in 3..56 {
+            let y = 3f32 + (x as f32 + 0.5 - 2f32) * 38f32 / 55f32;
+            assert!(red(&canvas, x, y as u64) > 0.2, "gap at {}", x);
+        }
+
+        // Thinner than the spacing of the old corner samples.
+        let mut canvas = black_canvas(60, 60);
+        Line::new(Point2::new(2f32, 10.3), Point2::new(57f32, 10.3)).stroke(&mut canvas, 0.3);
+        for x in 3..56 {
+            assert!(red(&canvas, x, 10) > 0.1, "gap at {}", x);
+        }
+    }
+
+    #[test]
+    fn resize_keeps_pixels() {
+        let mut canvas = Canvas::new(2, 2, rgba(1f32, 0f32, 0f32, 1f32));
+        canvas.set_draw_color(rgba(0f32, 1f32, 0f32, 1f32));
+        canvas.enable_antialias(false);
+        canvas.draw_line(Point2::new(1.5, 0f32), Point2::new(1.5, 2f32), 1f32);
+        let before: Vec<f32> = (0..4)
+            .map(|i| canvas.get_pixel(i % 2, i / 2).unwrap().g)
+            .collect();
+
+        canvas.resize(4, 4, rgba(0f32, 0f32, 1f32, 1f32));
+        assert_eq!((canvas.width(), canvas.height()), (4, 4));
+        for i in 0..4 {
+            assert_eq!(
+                canvas.get_pixel(i % 2, i / 2).unwrap().g,
+                before[i as usize]
+            );
+        }
+        for &(x, y) in [(2, 0), (3, 3), (0, 2), (1, 3)].iter() {
+            assert_eq!(canvas.get_pixel(x, y).unwrap().b, 1f32);
+        }
+
+        canvas.resize(1, 3, rgba(0f32, 0f32, 0f32, 1f32));
+        assert!(canvas.get_pixel(1, 0).is_err());
+        assert_eq!(canvas.get_pixel(0, 1).unwrap().g, before[2]);
+        assert_eq!(canvas.get_pixel(0, 2).unwrap().b, 1f32);
+    }
+
+    #[cfg(feature = "png")]
+    #[test]
+    fn save_png_writes_the_canvas() {
+        let mut canvas = black_canvas(12, 7);
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(1f32, 1f32)).fill(&mut canvas);
+        let file_name = format!("grafizo-save-png-{}.png", std::process::id());
+        let path = std::env::temp_dir().join(file_name);
+        canvas.save_png(&path).unwrap();
+
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let (info, mut reader) = decoder.read_info().unwrap();
+        assert_eq!((info.width, info.height), (12, 7));
+        let mut data = vec![0u8; info.buffer_size()];
+        reader.next_frame(&mut data[..]).unwrap();
+        assert_eq!(data[..8], [255, 255, 255, 255, 0, 0, 0, 255]);
+        std::fs::remove_file(&path).unwrap();
+
+        // Too many pixels to count their bytes, which is an error rather than a panic.
+        canvas.backing = CanvasColorBuf::without_pixels(1 << 31, 1 << 31);
+        assert!(canvas.save_png(&path).is_err());
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn export_twice() {
+        use colorbuf::bitmap::{BitDepth, ColorFormat};
+
+        let mut canvas = black_canvas(10, 10);
+        canvas.set_draw_color(rgba(1f32, 0.5, 0f32, 1f32));
+        canvas.draw_circle(Point2::new(5f32, 5f32), 3f32, 1f32);
+        let (mut first, mut second) = (vec![0u8; 400], vec![1u8; 400]);
+        let mut stride = 0;
+        canvas
+            .to_bytebuffer(
+                &mut first[..],
+                ColorFormat::RGBA,
+                BitDepth::Eight,
+                &mut stride,
+            )
+            .unwrap();
+        canvas
+            .to_bytebuffer(
+                &mut second[..],
+                ColorFormat::RGBA,
+                BitDepth::Eight,
+                &mut stride,
+            )
+            .unwrap();
+        assert_eq!(first, second);
+
+        // The canvas can still be drawn onto afterwards.
+        canvas.draw_line(Point2::new(0f32, 0f32), Point2::new(10f32, 10f32), 1f32);
+        assert!(red(&canvas, 0, 0) > 0f32);
+    }
+
+    #[test]
+    fn seamless_stroke() {
+        use crate::path::Polyline;
+
+        // The seams between the parts are in the middle of the pixels at 10 and 20.
+        let points = vec![
+            Point2::new(2f32, 10f32),
+            Point2::new(10.5, 10f32),
+            Point2::new(20.5, 10f32),
+            Point2::new(30f32, 10f32),
+        ];
+        let mut canvas = black_canvas(40, 20);
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 0.5));
+        canvas.stroke_seamless(&Polyline::new(points), 3f32);
+        for &y in [9, 10, 11].iter() {
+            let straight = red(&canvas, 15, y);
+            assert!(straight > 0f32);
+            assert!(
+                (red(&canvas, 10, y) - straight).abs() < 1e-5,
+                "seam at {}",
+                y
+            );
+            assert!(
+                (red(&canvas, 20, y) - straight).abs() < 1e-5,
+                "seam at {}",
+                y
+            );
+        }
+    }
+
+    #[test]
+    fn comparing_canvases() {
+        let mut canvas = black_canvas(20, 20);
+        let untouched = black_canvas(20, 20);
+        assert!(canvas.pixels_equal(&canvas, 0f32));
+        assert!(canvas.pixels_equal(&untouched, 0f32));
+        assert!(!canvas.pixels_equal(&black_canvas(20, 21), 1f32));
+
+        Circle::new(Point2::new(10f32, 10f32), 3f32).fill(&mut canvas);
+        assert!(!canvas.pixels_equal(&untouched, 1e-3));
+
+        let nan = Canvas::new(2, 2, rgba(f32::NAN, 0f32, 0f32, 1f32));
+        assert!(!nan.pixels_equal(&nan, 1f32));
+    }
+
+    #[test]
+    fn spans_match_single_pixels() {
+        let color = rgba(0.2f32, 0.6f32, 1f32, 0.5f32);
+        let mut spans = CanvasColorBuf::new(12, 4, rgba(0f32, 0f32, 0f32, 1f32));
+        let mut pixels = spans.clone();
+        for y in 0..4 {
+            spans.set_span(y, 3, 20, &color);
+            for x in 3..12 {
+                pixels.set_pixel(x, y, &color).unwrap();
+            }
+        }
+        spans.set_span(9, 0, 12, &color);
+        spans.set_span(1, 8, 8, &rgba(1f32, 0f32, 0f32, 1f32));
+        for y in 0..4 {
+            for x in 0..12 {
+                let (a, b) = (
+                    spans.get_pixel(x, y).unwrap(),
+                    pixels.get_pixel(x, y).unwrap(),
+                );
+                assert_eq!((a.r, a.g, a.b, a.a), (b.r, b.g, b.b, b.a));
+            }
+        }
+
+        // An opaque fill is written in runs, a nearly-opaque one is blended pixel by pixel.
+        let square = vec![
+            Point2::new(3f32, 3f32),
+            Point2::new(27f32, 3f32),
+            Point2::new(27f32, 17.5f32),
+            Point2::new(3f32, 17.5f32),
+        ];
+        let mut opaque = black_canvas(30, 20);
+        opaque.set_draw_color(rgba(0.2f32, 0.6f32, 1f32, 1f32));
+        opaque.rasterize_filled_polygon(&square);
+        let mut blended = black_canvas(30, 20);
+        blended.set_draw_color(rgba(0.2f32, 0.6f32, 1f32, 0.99999994f32));
+        blended.rasterize_filled_polygon(&square);
+        assert!(opaque.pixels_equal(&blended, 1e-4));
+        assert_eq!(opaque.get_pixel(10, 10).unwrap().b, 1f32);
+    }
+
+    #[test]
+    fn shapes_reach_last_row_and_column() {
+        for &antialias in &[false, true] {
+            let mut canvas = black_canvas(10, 10);
+            canvas.enable_antialias(antialias);
+            Rectangle::new(Point2::new(5f32, 5f32), Point2::new(10f32, 10f32)).fill(&mut canvas);
+            assert_eq!(red(&canvas, 9, 9), 1f32);
+            assert_eq!(red(&canvas, 9, 5), 1f32);
+            assert_eq!(red(&canvas, 5, 9), 1f32);
+
+            let mut canvas = black_canvas(10, 10);
+            canvas.enable_antialias(antialias);
+            Circle::new(Point2::new(9.5f32, 9.5f32), 3f32).fill(&mut canvas);
+            assert!(red(&canvas, 9, 9) > 0.99);
+        }
+    }
+
+    #[test]
+    fn zero_sized_canvases_are_empty() {
+        for &(width, height) in &[(0u64, 10u64), (10, 0), (0, 0)] {
+            for &antialias in &[false, true] {
+                let mut canvas = Canvas::new(width, height, rgba(0f32, 0f32, 0f32, 1f32));
+                canvas.enable_antialias(antialias);
+                assert_eq!(canvas.width(), width);
+                assert!(canvas.get_pixel(0, 0).is_err());
+
+                let circle = Circle::new(Point2::new(5f32, 5f32), 3f32);
+                circle.fill(&mut canvas);
+                circle.stroke(&mut canvas, 2f32);
+                Rectangle::new(Point2::new(1f32, 1f32), Point2::new(8f32, 8f32)).fill(&mut canvas);
+                Line::new(Point2::new(0f32, 0f32), Point2::new(9f32, 9f32))
+                    .stroke(&mut canvas, 2f32);
+                canvas.stroke_seamless(&circle, 2f32);
+                let _ = canvas.coverage_mask(&circle);
+                canvas.fill_background();
+                canvas.box_blur(2);
+
+                let mut other = Canvas::new(4, 4, rgba(1f32, 0f32, 0f32, 1f32));
+                canvas.blit(&other, Point2::new(0f32, 0f32));
+                other.blit(&canvas, Point2::new(1f32, 1f32));
+                let mut mask = MaskCanvas::new(width, height);
+                mask.fill(&circle);
+                canvas.apply_mask(&mask);
+                other.apply_mask(&mask);
+                assert!(canvas.pixels_equal(&canvas, 0f32));
+
+                canvas.resize(3, 3, rgba(0f32, 0f32, 0f32, 0f32));
+                assert!(canvas.get_pixel(2, 2).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn small_circles_are_smooth() {
+        let mut canvas = Canvas::new(20, 20, rgba(0f32, 0f32, 0f32, 0f32));
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        Circle::new(Point2::new(10f32, 10f32), 3f32).fill(&mut canvas);
+        let alpha = |canvas: &Canvas, x, y| canvas.get_pixel(x, y).unwrap().a;
+        let total: f32 = (0..400).map(|i| alpha(&canvas, i % 20, i / 20)).sum();
+        assert!((total - std::f32::consts::PI * 9f32).abs() < 0.5);
+
+        // The coverage falls off steadily towards the edge instead of in steps.
+        let row: Vec<f32> = (10..15).map(|x| alpha(&canvas, x, 10)).collect();
+        assert!(row.windows(2).all(|pair| pair[0] >= pair[1]));
+        assert!(row[2] > 0f32 && row[2] < 1f32);
+
+        let mut canvas = Canvas::new(20, 20, rgba(0f32, 0f32, 0f32, 0f32));
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        Circle::new(Point2::new(10f32, 10f32), 5f32).stroke(&mut canvas, 2f32);
+        let total: f32 = (0..400).map(|i| alpha(&canvas, i % 20, i / 20)).sum();
+        assert!((total - std::f32::consts::PI * (36f32 - 16f32)).abs() < 1f32);
+        assert_eq!(alpha(&canvas, 10, 10), 0f32);
+    }
+
+    #[test]
+    fn composite_blend_modes() {
+        let close = |a: f32, b: f32| (a - b).abs() < 1e-4;
+        let base = rgba(0.2f32, 0.5f32, 0.9f32, 1f32);
+        let mut dst = Canvas::new(3, 3, base);
+        dst.composite(
+            &Canvas::new(3, 3, rgba(1f32, 1f32, 1f32, 1f32)),
+            BlendMode::Multiply,
+        );
+        let pixel = dst.get_pixel(1, 1).unwrap();
+        assert!(close(pixel.r, 0.2) && close(pixel.g, 0.5) && close(pixel.b, 0.9));
+        assert!(close(pixel.a, 1f32));
+
+        let gray = rgba(0.8f32, 0.8f32, 0.8f32, 1f32);
+        let mut dst = Canvas::new(3, 3, gray);
+        dst.composite(&Canvas::new(3, 3, gray), BlendMode::Add);
+        assert!(close(red(&dst, 0, 0), 1f32));
+
+        // Only the pixels under the smaller source change.
+        let mut dst = Canvas::new(3, 3, rgba(0.4f32, 0.4f32, 0.4f32, 1f32));
+        dst.composite(
+            &Canvas::new(2, 2, rgba(0.3f32, 0.3f32, 0.3f32, 1f32)),
+            BlendMode::Screen,
+        );
+        assert!(red(&dst, 0, 0) > 0.4);
+        assert!(close(red(&dst, 2, 2), 0.4));
+
+        let mut dst = Canvas::new(3, 3, base);
+        let src = Canvas::new(3, 3, rgba(0.1f32, 0.7f32, 0.3f32, 1f32));
+        dst.composite(&src, BlendMode::SourceOver);
+        let pixel = dst.get_pixel(0, 0).unwrap();
+        assert!(close(pixel.r, 0.1) && close(pixel.g, 0.7));
+
+        // Over a transparent destination, every mode leaves the source as it is.
+        let mut dst = Canvas::new(3, 3, rgba(0.4f32, 0.4f32, 0.4f32, 0f32));
+        let src = Canvas::new(3, 3, rgba(0.1f32, 0.7f32, 0.3f32, 0.5f32));
+        dst.composite(&src, BlendMode::Multiply);
+        let pixel = dst.get_pixel(0, 0).unwrap();
+        assert!(close(pixel.r, 0.1) && close(pixel.a, 0.5));
+    }
+
+    #[test]
+    fn antialiasing_per_draw() {
+        let mut canvas = black_canvas(40, 20);
+        canvas.with_antialias(false, |canvas| {
+            Circle::new(Point2::new(10f32, 10f32), 6.3f32).fill(canvas)
+        });
+        Circle::new(Point2::new(30f32, 10f32), 6.3f32).fill(&mut canvas);
+        assert!(canvas.antialias_enabled);
+
+        let has_partial_pixels = |columns: std::ops::Range<u64>| {
+            (0..20).any(|y| {
+                columns.clone().any(|x| {
+                    let red = red(&canvas, x, y);
+                    red > 0.01 && red < 0.99
+                })
+            })
+        };
+        assert!(!has_partial_pixels(0..20));
+        assert!(has_partial_pixels(20..40));
+    }
+
+    #[test]
+    fn thin_ring_inner_edge() {
+        let center = Point2::new(30.3f32, 30.5f32);
+        let arc = crate::path::Arc::new(
+            center,
+            19f32,
+            angle::Angle::from_radians(3f32),
+            angle::Angle::from_radians(3.3f32),
+        );
+        let rings: [&dyn Path; 2] = [&Circle::new(center, 19f32), &arc];
+        for ring in rings.iter() {
+            let mut canvas = Canvas::new(60, 60, rgba(0f32, 0f32, 0f32, 0f32));
+            canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+            ring.stroke(&mut canvas, 2f32);
+
+            // The outer edge is at 11.3 and the inner one at 12.3.
+            let row: Vec<f32> = (8..15)
+                .map(|x| canvas.get_pixel(x, 30).unwrap().a)
+                .collect();
+            assert!(row[3] > 0.9);
+            assert!(row[4] > 0.1 && row[4] < 0.5);
+            assert_eq!(row[5], 0f32);
+        }
+    }
+
+    #[test]
+    fn drawing_goes_through_transform() {
+        let draw = |transform: Option<Transform2>| {
+            let mut canvas = black_canvas(40, 40);
+            if let Some(transform) = transform {
+                canvas.set_transform(transform);
+            }
+            Line::new(Point2::new(2f32, 5f32), Point2::new(15f32, 9f32)).stroke(&mut canvas, 2f32);
+            Circle::new(Point2::new(8f32, 20f32), 4f32).stroke(&mut canvas, 1.5f32);
+            canvas
+        };
+        let plain = draw(None);
+        let moved = draw(Some(Transform2::translation(7f32, 3f32)));
+        for y in 0..37 {
+            for x in 0..33 {
+                let (a, b) = (
+                    plain.get_pixel(x, y).unwrap(),
+                    moved.get_pixel(x + 7, y + 3).unwrap(),
+                );
+                assert!(color::colors_approx_eq(&a, &b, 1e-3));
+            }
+        }
+
+        // A non-uniform scale turns circles into ellipses.
+        let mut canvas = black_canvas(60, 60);
+        canvas.set_transform(Transform2::scale(3f32, 1f32));
+        Circle::new(Point2::new(10f32, 30f32), 8f32).fill(&mut canvas);
+        assert_eq!(red(&canvas, 52, 30), 1f32);
+        assert_eq!(red(&canvas, 30, 23), 1f32);
+        assert_eq!(red(&canvas, 30, 40), 0f32);
+        let mut ring = black_canvas(60, 60);
+        ring.set_transform(Transform2::scale(3f32, 1f32));
+        Circle::new(Point2::new(10f32, 30f32), 8f32).stroke(&mut ring, 2f32);
+        assert_eq!(red(&ring, 30, 30), 0f32);
+        assert_eq!(red(&ring, 30, 22), 1f32);
+        assert!(red(&ring, 54, 30) > 0.9);
+
+        // A uniform one keeps them circles.
+        let mut scaled = black_canvas(40, 40);
+        scaled.set_transform(Transform2::scale(2f32, 2f32));
+        Circle::new(Point2::new(10f32, 10f32), 5f32).fill(&mut scaled);
+        let mut large = black_canvas(40, 40);
+        Circle::new(Point2::new(20f32, 20f32), 10f32).fill(&mut large);
+        assert!(scaled.pixels_equal(&large, 1e-4));
+    }
+
+    #[test]
+    fn flood_fill_region() {
+        let mut canvas = black_canvas(30, 30);
+        canvas.enable_antialias(false);
+        Rectangle::new(Point2::new(5f32, 5f32), Point2::new(20f32, 20f32))
+            .stroke(&mut canvas, 2f32);
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        canvas.flood_fill(Point2::new(12f32, 12f32), 0.01);
+        assert_eq!(red(&canvas, 12, 12), 1f32);
+        assert_eq!(canvas.get_pixel(7, 7).unwrap().g, 0f32);
+        assert_eq!(red(&canvas, 7, 7), 1f32);
+
+        // The border and everything outside of it are left alone.
+        assert_eq!(canvas.get_pixel(5, 12).unwrap().g, 1f32);
+        assert_eq!(red(&canvas, 2, 2), 0f32);
+        assert_eq!(red(&canvas, 25, 12), 0f32);
+
+        // Filling with the color which is already there doesn't change anything.
+        canvas.flood_fill(Point2::new(12f32, 12f32), 0.01);
+        assert_eq!(red(&canvas, 12, 12), 1f32);
+        canvas.flood_fill(Point2::new(-1f32, 1f32), 1f32);
+        assert_eq!(red(&canvas, 2, 2), 0f32);
+
+        // A paint which changes over the region still terminates.
+        let mut gradient = paint::RadialGradient::new(Point2::new(12f32, 12f32), 10f32);
+        gradient
+            .add_stop(0f32, rgba(1f32, 0f32, 0f32, 1f32))
+            .add_stop(1f32, rgba(0f32, 0f32, 0f32, 1f32));
+        canvas.set_paint(Paint::RadialGradient(gradient));
+        canvas.flood_fill(Point2::new(1f32, 1f32), 1f32);
+    }
+
+    #[test]
+    fn inverting_through_pixel_iteration() {
+        let mut canvas = Canvas::new(10, 8, rgba(0.2f32, 0.4f32, 0.6f32, 1f32));
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 0.5f32));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(5f32, 4f32)).fill(&mut canvas);
+        let before: Vec<_> = canvas.pixels().collect();
+        assert_eq!(before.len(), 80);
+        assert_eq!((before[13].0, before[13].1), (3, 1));
+
+        for (_, _, mut pixel) in canvas.pixels_mut() {
+            pixel.r = 1f32 - pixel.r;
+            pixel.g = 1f32 - pixel.g;
+            pixel.b = 1f32 - pixel.b;
+        }
+        for (x, y, old) in before {
+            let inverted = rgba(1f32 - old.r, 1f32 - old.g, 1f32 - old.b, old.a);
+            assert!(color::colors_approx_eq(
+                &canvas.get_pixel(x, y).unwrap(),
+                &inverted,
+                1e-5
+            ));
+        }
+
+        // Changing the alpha keeps the color channels as they were written.
+        let mut canvas = Canvas::new(2, 2, rgba(0f32, 0f32, 0f32, 0f32));
+        for (x, _, mut pixel) in canvas.pixels_mut() {
+            if x == 1 {
+                pixel.a = 0.5;
+                pixel.r = 1f32;
+            }
+        }
+        let expected = rgba(1f32, 0f32, 0f32, 0.5f32);
+        assert!(color::colors_approx_eq(
+            &canvas.get_pixel(1, 0).unwrap(),
+            &expected,
+            1e-6
+        ));
+    }
+
+    #[test]
+    fn flattening_overlay() {
+        let curve = path::CubicBezierCurve::new(
+            Point2::new(5f32, 90f32),
+            Point2::new(20f32, 0f32),
+            Point2::new(80f32, 0f32),
+            Point2::new(95f32, 90f32),
+        );
+        let drawn_pixels = |tolerance: f32| {
+            let mut canvas = black_canvas(100, 100);
+            // The segments are drawn solid even with a dash pattern.
+            canvas.set_dash(Some(vec![2f32, 2f32]));
+            canvas.draw_flattening(&curve, tolerance);
+            canvas.pixels().filter(|pixel| pixel.2.r > 0.5).count()
+        };
+        let (fine, coarse) = (drawn_pixels(0.05), drawn_pixels(2f32));
+        assert!(fine > coarse);
+        assert!(curve.flatten(0.05).len() > curve.flatten(2f32).len());
+    }
+
+    #[test]
+    fn supersampled_resolve() {
+        let mut canvas = Canvas::new_supersampled(20, 20, 4, rgba(0f32, 0f32, 0f32, 1f32));
+        assert_eq!((canvas.width(), canvas.height()), (80, 80));
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        // The top edge is a shallow diagonal across the row 10.
+        let edge = [
+            Point2::new(0f32, 20f32),
+            Point2::new(0f32, 8f32),
+            Point2::new(20f32, 12f32),
+            Point2::new(20f32, 20f32),
+        ];
+        path::Polygon::new(edge.to_vec()).fill(&mut canvas);
+        let resolved = canvas.resolve();
+        assert_eq!((resolved.width(), resolved.height()), (20, 20));
+        assert_eq!(resolved.transform, Transform2::identity());
+        let row: Vec<f32> = (0..20).map(|x| red(&resolved, x, 10)).collect();
+        assert!(row.iter().filter(|&&red| red > 0.05 && red < 0.95).count() >= 4);
+        assert!(row.windows(2).all(|pair| pair[1] <= pair[0] + 1e-4));
+        assert!(row[0] > 0.95 && row[19] < 0.05);
+
+        // The blocks are averaged in linear space.
+        let mut half = Canvas::new_supersampled(1, 1, 2, rgba(0f32, 0f32, 0f32, 1f32));
+        half.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(0.5f32, 1f32)).fill(&mut half);
+        let value = red(&half.resolve(), 0, 0);
+        assert!((value - 0.5f32.powf(1f32 / 2.2)).abs() < 1e-3);
+
+        // The clip is given in the resolved coordinates.
+        let mut clipped = Canvas::new_supersampled(4, 4, 2, rgba(0f32, 0f32, 0f32, 1f32));
+        clipped.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        clipped.set_clip_rect(Point2::new(0f32, 0f32), Point2::new(2f32, 4f32));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(4f32, 4f32)).fill(&mut clipped);
+        let clipped = clipped.resolve();
+        assert!(red(&clipped, 1, 1) > 0.99 && red(&clipped, 2, 1) < 0.01);
+    }
+
+    #[test]
+    fn flood_fill_seed_goes_through_transform() {
+        let mut canvas = Canvas::new_supersampled(20, 20, 2, rgba(0f32, 0f32, 0f32, 1f32));
+        canvas.enable_antialias(false);
+        canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 1f32));
+        Line::new(Point2::new(10f32, 0f32), Point2::new(10f32, 20f32)).stroke(&mut canvas, 2f32);
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        canvas.flood_fill(Point2::new(15f32, 5f32), 0.01);
+        let resolved = canvas.resolve();
+        assert_eq!(red(&resolved, 15, 5), 1f32);
+        assert_eq!(resolved.get_pixel(15, 5).unwrap().g, 0f32);
+        assert_eq!(red(&resolved, 3, 5), 0f32);
+
+        let mut canvas = black_canvas(20, 20);
+        canvas.enable_antialias(false);
+        Line::new(Point2::new(10f32, 0f32), Point2::new(10f32, 20f32)).stroke(&mut canvas, 2f32);
+        canvas.set_transform(Transform2::translation(10f32, 0f32));
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        canvas.flood_fill(Point2::new(5f32, 5f32), 0.01);
+        assert_eq!(red(&canvas, 15, 5), 1f32);
+        assert_eq!(canvas.get_pixel(15, 5).unwrap().g, 0f32);
+        assert_eq!(red(&canvas, 5, 5), 0f32);
+    }
+
+    #[test]
+    fn drawing_off_the_canvas() {
+        let points = [
+            Point2::new(-5f32, -5f32),
+            Point2::new(15.9f32, 15.9f32),
+            Point2::new(16f32, 16f32),
+            Point2::new(20f32, -3f32),
+            Point2::new(-1e9f32, 8f32),
+            Point2::new(1e9f32, 1e9f32),
+            Point2::new(f32::NAN, 3f32),
+            Point2::new(f32::INFINITY, 1f32),
+        ];
+        for &antialias in &[true, false] {
+            let mut canvas = Canvas::new(16, 16, rgba(0f32, 0f32, 0f32, 1f32));
+            canvas.enable_antialias(antialias);
+            canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 0.5f32));
+            for &a in points.iter() {
+                for &b in points.iter() {
+                    Circle::new(a, 10f32).fill(&mut canvas);
+                    Circle::new(a, 10f32).stroke(&mut canvas, 3f32);
+                    Rectangle::new(a, b).fill(&mut canvas);
+                    Line::new(a, b).stroke(&mut canvas, 2f32);
+                    path::Polygon::new(vec![a, b, Point2::new(8f32, 8f32)]).fill(&mut canvas);
+                    path::Ellipse::new(a, 5f32, 20f32).fill(&mut canvas);
+                }
+            }
+
+            let mut mask = MaskCanvas::new(16, 16);
+            mask.fill(&Circle::new(Point2::new(15.5f32, 15.5f32), 4f32));
+            mask.stroke(
+                &Line::new(Point2::new(-3f32, 15.9f32), Point2::new(30f32, 15.9f32)),
+                3f32,
+            );
+        }
+    }
+
+    #[test]
+    fn conservative_thin_diagonal() {
+        for &antialias in &[false, true] {
+            let mut canvas = black_canvas(40, 40);
+            canvas.enable_antialias(antialias);
+            canvas.enable_conservative(true);
+            let (a, b) = (Point2::new(2f32, 3.3f32), Point2::new(35f32, 20.7f32));
+            Line::new(a, b).stroke(&mut canvas, 0.2);
+            for i in 0..1000 {
+                let covered = a.lerp(b, i as f32 / 1000f32);
+                assert!(red(&canvas, covered.get_x() as u64, covered.get_y() as u64) > 0.999);
+            }
+            // Every pixel is either untouched or fully drawn to.
+            assert!(canvas
+                .pixels()
+                .all(|(_, _, pixel)| pixel.r < 1e-6 || pixel.r > 0.999));
+            assert!(canvas.pixels().filter(|pixel| pixel.2.r > 0.5).count() < 80);
+        }
+    }
+
+    #[test]
+    fn conservative_circle_edges() {
+        let mut canvas = black_canvas(30, 30);
+        canvas.enable_conservative(true);
+        Circle::new(Point2::new(15f32, 15f32), 7.3f32).fill(&mut canvas);
+        for y in 0..30 {
+            for x in 0..30 {
+                // The distance from the center to the nearest point of the pixel.
+                let nearest = Point2::new(
+                    15f32.max(x as f32).min(x as f32 + 1f32),
+                    15f32.max(y as f32).min(y as f32 + 1f32),
+                );
+                let distance = nearest.distance(Point2::new(15f32, 15f32));
+                if distance < 7.29 {
+                    assert!(red(&canvas, x, y) > 0.99);
+                } else if distance > 7.31 {
+                    assert!(red(&canvas, x, y) < 0.01);
+                }
+            }
+        }
+
+        // Only the corner of this pixel reaches into the ring.
+        let mut canvas = black_canvas(30, 30);
+        canvas.enable_conservative(true);
+        Circle::new(Point2::new(15f32, 15f32), 7.3f32).stroke(&mut canvas, 1f32);
+        assert!(red(&canvas, 20, 20) > 0.99);
+        assert!(red(&canvas, 15, 15) < 0.01);
+    }
+
+    #[test]
+    fn clip_limits_the_scanned_pixels() {
+        let mut canvas = Canvas::new_uninitialized(2000, 2000);
+        // The box of a huge circle only gets scanned where the clip is.
+        let circle_box = canvas.clamp_to_visible(100, 100, 1900, 1900);
+        assert_eq!(circle_box, Some((100, 100, 1900, 1900)));
+        canvas.set_clip_rect(Point2::new(1000f32, 95f32), Point2::new(1008f32, 103f32));
+        let clipped_box = canvas.clamp_to_visible(100, 100, 1900, 1900);
+        assert_eq!(clipped_box, Some((1000, 100, 1007, 102)));
+        assert_eq!(canvas.clamp_to_visible(0, 0, 999, 2000), None);
+
+        // The pixels inside the clip come out the same as without one.
+        let draw = |clip: bool| {
+            let mut canvas = Canvas::new(200, 200, rgba(0f32, 0f32, 0f32, 1f32));
+            canvas.set_draw_color(rgba(1f32, 1f32, 1f32, 0.5f32));
+            if clip {
+                canvas.set_clip_rect(Point2::new(100f32, 8f32), Point2::new(108f32, 16f32));
+            }
+            Circle::new(Point2::new(100f32, 100f32), 90f32).fill(&mut canvas);
+            Circle::new(Point2::new(100f32, 100f32), 90f32).stroke(&mut canvas, 3f32);
+            Rectangle::new(Point2::new(5f32, 5f32), Point2::new(195f32, 195f32)).fill(&mut canvas);
+            canvas
+        };
+        let (full, clipped) = (draw(false), draw(true));
+        for y in 0..30 {
+            for x in 90..120 {
+                let inside = (100..108).contains(&x) && (8..16).contains(&y);
+                let expected = if inside {
+                    full.get_pixel(x, y).unwrap()
+                } else {
+                    rgba(0f32, 0f32, 0f32, 1f32)
+                };
+                assert!(color::colors_approx_eq(
+                    &clipped.get_pixel(x, y).unwrap(),
+                    &expected,
+                    0f32
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn thin_circle_matches_scanned_ring() {
+        let full_turn = Some((0f32, 2f32 * std::f32::consts::PI));
+        let circles = [
+            (50f32, 50f32, 30f32),
+            (47.3, 52.8, 41.6),
+            (20.5, 20.5, 3f32),
+            (0f32, 0f32, 70f32),
+        ];
+        for &(x, y, radius) in circles.iter() {
+            let center = Point2::new(x, y);
+            let mut fast = black_canvas(100, 100);
+            fast.enable_antialias(false);
+            fast.set_draw_color(rgba(1f32, 1f32, 1f32, 0.5f32));
+            Circle::new(center, radius).stroke(&mut fast, 1f32);
+            // Arcs, even ones going all the way around, are drawn by scanning their box.
+            let mut scanned = black_canvas(100, 100);
+            scanned.enable_antialias(false);
+            scanned.rasterize_stroked_arc(center, radius - 0.5, radius + 0.5, full_turn);
+
+            let lit = |canvas: &Canvas, x: i64, y: i64| {
+                (0..100).contains(&x)
+                    && (0..100).contains(&y)
+                    && red(canvas, x as u64, y as u64) > 0f32
+            };
+            let (mut fast_count, mut scanned_count) = (0, 0);
+            for (x, y, pixel) in fast.pixels() {
+                if pixel.r > 0f32 {
+                    // Each pixel is blended only once.
+                    assert!((pixel.r - 0.5f32.powf(1f32 / 2.2)).abs() < 0.02);
+                    assert!(lit(&scanned, x as i64, y as i64));
+                    fast_count += 1;
+                }
+                if lit(&scanned, x as i64, y as i64) {
+                    scanned_count += 1;
+                    let (x, y) = (x as i64, y as i64);
+                    assert!((-1..=1).any(|dy| (-1..=1).any(|dx| lit(&fast, x + dx, y + dy))));
+                }
+            }
+            assert!(fast_count <= scanned_count && fast_count * 10 >= scanned_count * 7);
+        }
+    }
+
+    #[test]
+    fn blend_pixel_coverage() {
+        let background = rgba(0.2f32, 0.4f32, 0.6f32, 0.7f32);
+        let mut canvas = Canvas::new(2, 1, background);
+        canvas.blend_pixel(0, 0, &rgba(1f32, 0f32, 0f32, 1f32), 1f32);
+        canvas.blend_pixel(1, 0, &rgba(1f32, 0f32, 0f32, 1f32), 0f32);
+        let (covered, uncovered) = (
+            canvas.get_pixel(0, 0).unwrap(),
+            canvas.get_pixel(1, 0).unwrap(),
+        );
+        assert!(color::colors_approx_eq(
+            &covered,
+            &rgba(1f32, 0f32, 0f32, 1f32),
+            1e-6
+        ));
+        assert!(color::colors_approx_eq(&uncovered, &background, 1e-6));
+
+        // Pixels off the canvas are skipped.
+        canvas.blend_pixel(2, 0, &rgba(1f32, 0f32, 0f32, 1f32), 1f32);
+
+        // The shapes blend their edges through the same helper.
+        let mut canvas = Canvas::new(10, 10, background);
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        path::Ellipse::new(Point2::new(5f32, 5f32), 2.5f32, 2f32).fill(&mut canvas);
+        let edge = red(&canvas, 7, 5);
+        assert!(edge > 0.2 && edge < 1f32);
+    }
+
+    #[test]
+    fn draw_into_slice() {
+        use colorbuf::bitmap::{BitDepth, ColorFormat};
+
+        let mut bytes = [0u8, 0, 255, 255].repeat(8 * 4);
+        {
+            let mut canvas =
+                Canvas::from_slice(&mut bytes, 8, 4, ColorFormat::RGBA, BitDepth::Eight).unwrap();
+            let blue = rgba(0f32, 0f32, 1f32, 1f32);
+            assert!(color::colors_approx_eq(
+                &canvas.get_pixel(0, 0).unwrap(),
+                &blue,
+                1e-6
+            ));
+            canvas
+                .draw(|canvas| {
+                    canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+                    Rectangle::new(Point2::new(2f32, 1f32), Point2::new(5f32, 3f32)).fill(canvas);
+                })
+                .unwrap();
+
+            // A canvas of another size doesn't fit into the buffer any more.
+            let resized = canvas.draw(|canvas| canvas.resize(2, 2, blue));
+            assert_eq!(resized, Err(BytebufferError::LengthMismatch));
+        }
+        for y in 0..4 {
+            for x in 0..8 {
+                let index = (y * 8 + x) * 4;
+                let inside = (2..5).contains(&x) && (1..3).contains(&y);
+                let expected = if inside {
+                    [255, 0, 0, 255]
+                } else {
+                    [0, 0, 255, 255]
+                };
+                assert_eq!(bytes[index..index + 4], expected);
+            }
+        }
+
+        let mut short = [0u8; 3];
+        assert!(Canvas::from_slice(&mut short, 8, 4, ColorFormat::RGBA, BitDepth::Eight).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn parallel_fill_matches_serial_fill() {
+        // A large star with spikes of different lengths, so that it's far from convex.
+        let star: Vec<Point2> = (0..200)
+            .map(|i| {
+                let angle = i as f32 / 200f32 * 2f32 * std::f32::consts::PI;
+                let radius = if i % 2 == 0 {
+                    380f32
+                } else {
+                    150f32 + (i % 7) as f32 * 20f32
+                };
+                Point2::new(400f32 + radius * angle.cos(), 400f32 + radius * angle.sin())
+            })
+            .collect();
+        let bounds = (10, 5, 789, 779);
+        for &scanlines in &[Some(4), None] {
+            for &alpha in &[1f32, 0.6] {
+                let mut canvases = [black_canvas(800, 800), black_canvas(800, 800)];
+                for canvas in canvases.iter_mut() {
+                    canvas.set_draw_color(rgba(0.9f32, 0.5f32, 0.1f32, alpha));
+                }
+                canvases[0].fill_rows(&[&star[..]], FillRule::NonZero, scanlines, bounds);
+                canvases[1].fill_rows_in_parallel(
+                    &[&star[..]],
+                    FillRule::NonZero,
+                    scanlines,
+                    bounds,
+                );
+
+                let bits = |canvas: &Canvas| {
+                    let channels = canvas
+                        .backing
+                        .buf
+                        .iter()
+                        .flat_map(|c| vec![c.r, c.g, c.b, c.a]);
+                    channels.map(f32::to_bits).collect::<Vec<_>>()
+                };
+                assert!(bits(&canvases[0]) == bits(&canvases[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn glyphs_at_pen_positions() {
+        // A square with a square hole, like an "o" in a very blocky font.
+        let glyph = vec![
+            vec![
+                Point2::new(0f32, 0f32),
+                Point2::new(6f32, 0f32),
+                Point2::new(6f32, 6f32),
+                Point2::new(0f32, 6f32),
+            ],
+            vec![
+                Point2::new(2f32, 2f32),
+                Point2::new(2f32, 4f32),
+                Point2::new(4f32, 4f32),
+                Point2::new(4f32, 2f32),
+            ],
+        ];
+        let mut canvas = black_canvas(30, 12);
+        let glyphs = [(&glyph[..], 8f32), (&glyph[..], 8f32)];
+        let pen = canvas.fill_glyphs(&glyphs, FillRule::NonZero, Point2::new(3f32, 3f32));
+        assert_eq!(pen, Point2::new(19f32, 3f32));
+        for &x in &[3, 11] {
+            assert!(red(&canvas, x, 3) > 0.99);
+            assert!(red(&canvas, x + 5, 8) > 0.99);
+            assert!(red(&canvas, x + 2, 5) < 0.01);
+        }
+        assert!(red(&canvas, 10, 5) < 0.01);
+        assert!(red(&canvas, 20, 5) < 0.01);
+
+        let mut canvas = black_canvas(10, 10);
+        canvas.fill_glyph(&glyph, FillRule::NonZero);
+        assert!(red(&canvas, 0, 0) > 0.99 && red(&canvas, 2, 2) < 0.01);
+    }
+
+    #[test]
+    fn dithering_reduces_banding() {
+        use colorbuf::bitmap::{BitDepth, ColorFormat};
+
+        // A dark ramp, which only goes through a few 8-bit values.
+        let (width, height) = (256usize, 8usize);
+        let ramp = |x: usize| (x as f32 + 0.5) / width as f32 * 0.25;
+        let mut canvas = black_canvas(width as u64, height as u64);
+        for (x, _, mut pixel) in canvas.pixels_mut() {
+            *pixel = rgba(ramp(x as usize), ramp(x as usize), ramp(x as usize), 1f32);
+        }
+        let export = |canvas: &Canvas| {
+            let mut bytes = vec![0u8; width * height * 4];
+            let mut stride = 0;
+            let (format, depth) = (ColorFormat::RGBA, BitDepth::Eight);
+            canvas
+                .to_bytebuffer(&mut bytes, format, depth, &mut stride)
+                .unwrap();
+            bytes
+        };
+        let transitions = |bytes: &[u8]| {
+            let reds: Vec<u8> = bytes.iter().step_by(4).cloned().collect();
+            reds.chunks(width)
+                .map(|row| row.windows(2).filter(|pair| pair[0] != pair[1]).count())
+                .sum::<usize>()
+        };
+
+        let plain = export(&canvas);
+        canvas.enable_dithering(true);
+        let dithered = export(&canvas);
+        assert!(transitions(&dithered) > transitions(&plain) + 200);
+
+        // Each value is off by at most one step, and on average not at all.
+        let mut total_error = 0f32;
+        for (i, &value) in dithered.iter().step_by(4).enumerate() {
+            let error = f32::from(value) - ramp(i % width) * 255f32;
+            assert!(error.abs() <= 1f32);
+            total_error += error;
+        }
+        assert!((total_error / (width * height) as f32).abs() < 0.1);
+    }
+
+    #[test]
+    fn antialiasing_is_symmetric() {
+        // The largest difference between a shape and its mirror image, mirrored back.
+        let asymmetry = |draw: &dyn Fn(&mut Canvas, bool)| {
+            let (mut plain, mut mirrored) = (black_canvas(40, 40), black_canvas(40, 40));
+            draw(&mut plain, false);
+            draw(&mut mirrored, true);
+            (0..1600)
+                .map(|i| (red(&plain, i % 40, i / 40) - red(&mirrored, 39 - i % 40, i / 40)).abs())
+                .fold(0f32, f32::max)
+        };
+        let p =
+            |x: f32, y: f32, mirrored: bool| Point2::new(if mirrored { 40f32 - x } else { x }, y);
+        let disk = asymmetry(&|canvas, m| Circle::new(p(12.3, 17.6, m), 6.2).fill(canvas));
+        let ring = asymmetry(&|canvas, m| Circle::new(p(12.3, 17.6, m), 6.2).stroke(canvas, 2.3));
+        let line =
+            asymmetry(&|canvas, m| Line::new(p(4.2, 5.1, m), p(30.7, 33.3, m)).stroke(canvas, 3.3));
+        assert!(disk < 1e-3 && ring < 1e-3 && line < 1e-3);
+    }
+
+    #[test]
+    fn crop_a_region() {
+        let mut canvas = Canvas::new(40, 30, rgba(0f32, 0f32, 0f32, 1f32));
+        canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        Rectangle::new(Point2::new(15f32, 10f32), Point2::new(25f32, 20f32)).fill(&mut canvas);
+        let cropped = canvas.crop(Point2::new(10f32, 5f32), Point2::new(30f32, 25f32));
+        assert_eq!((cropped.width(), cropped.height()), (20, 20));
+        for (x, y, pixel) in cropped.pixels() {
+            let inside = (5..15).contains(&x) && (5..15).contains(&y);
+            assert_eq!(pixel.r > 0.99, inside);
+            let original = canvas.get_pixel(x + 10, y + 5).unwrap();
+            assert!(color::colors_approx_eq(&pixel, &original, 1e-6));
+        }
+
+        // The region gets clamped to the canvas.
+        let corner = canvas.crop(Point2::new(35f32, -5f32), Point2::new(50f32, 3f32));
+        assert_eq!((corner.width(), corner.height()), (5, 3));
+        let outside = canvas.crop(Point2::new(50f32, 50f32), Point2::new(60f32, 60f32));
+        assert_eq!((outside.width(), outside.height()), (0, 0));
+
+        // Supersampled canvases get cropped in the coordinates shapes are drawn in.
+        let mut supersampled = Canvas::new_supersampled(20, 20, 2, rgba(0f32, 0f32, 0f32, 1f32));
+        supersampled.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        Rectangle::new(Point2::new(5f32, 5f32), Point2::new(10f32, 10f32)).fill(&mut supersampled);
+        let mut cropped = supersampled.crop(Point2::new(4f32, 4f32), Point2::new(12.5f32, 12f32));
+        assert_eq!((cropped.width(), cropped.height()), (18, 16));
+        let resolved = cropped.resolve();
+        assert_eq!((resolved.width(), resolved.height()), (9, 8));
+        assert!(red(&resolved, 0, 0) < 0.01 && red(&resolved, 1, 1) > 0.99);
+        assert!(red(&resolved, 6, 6) < 0.01);
+
+        // Drawing onto the cropped canvas still goes through the transform.
+        cropped.set_draw_color(rgba(0f32, 1f32, 0f32, 1f32));
+        Rectangle::new(Point2::new(6f32, 6f32), Point2::new(8f32, 8f32)).fill(&mut cropped);
+        let resolved = cropped.resolve();
+        assert!(resolved.get_pixel(6, 6).unwrap().g > 0.99);
+        assert!(resolved.get_pixel(5, 5).unwrap().g < 0.01);
+    }
+
+    #[test]
+    fn flips_and_rotation() {
+        // Two red pixels near the top left, and a green one in the bottom left corner.
+        let asymmetric = || {
+            let mut canvas = Canvas::new(10, 6, rgba(0f32, 0f32, 0f32, 1f32));
+            canvas.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+            Rectangle::new(Point2::new(1f32, 1f32), Point2::new(3f32, 2f32)).fill(&mut canvas);
+            canvas.set_draw_color(rgba(0f32, 1f32, 0f32, 1f32));
+            Rectangle::new(Point2::new(0f32, 5f32), Point2::new(1f32, 6f32)).fill(&mut canvas);
+            canvas
+        };
+        let lit = |canvas: &Canvas| {
+            let mut lit: Vec<(u64, u64, bool)> = canvas
+                .pixels()
+                .filter(|(_, _, pixel)| pixel.r > 0.5 || pixel.g > 0.5)
+                .map(|(x, y, pixel)| (x, y, pixel.r > 0.5))
+                .collect();
+            lit.sort_unstable();
+            lit
+        };
+        assert_eq!(
+            lit(&asymmetric()),
+            vec![(0, 5, false), (1, 1, true), (2, 1, true)]
+        );
+
+        let mut flipped = asymmetric();
+        flipped.flip_horizontal();
+        assert_eq!(
+            lit(&flipped),
+            vec![(7, 1, true), (8, 1, true), (9, 5, false)]
+        );
+        let mut flipped = asymmetric();
+        flipped.flip_vertical();
+        assert_eq!(
+            lit(&flipped),
+            vec![(0, 0, false), (1, 4, true), (2, 4, true)]
+        );
+
+        let rotated = asymmetric().rotate_90();
+        assert_eq!((rotated.width(), rotated.height()), (6, 10));
+        assert_eq!(
+            lit(&rotated),
+            vec![(0, 0, false), (4, 1, true), (4, 2, true)]
+        );
+        let full_turn = rotated.rotate_90().rotate_90().rotate_90();
+        assert_eq!(lit(&full_turn), lit(&asymmetric()));
+
+        // The middle row of an odd height stays where it is.
+        let mut odd = Canvas::new(3, 3, rgba(0f32, 0f32, 0f32, 1f32));
+        odd.set_draw_color(rgba(1f32, 0f32, 0f32, 1f32));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(1f32, 2f32)).fill(&mut odd);
+        odd.flip_vertical();
+        assert_eq!(lit(&odd), vec![(0, 1, true), (0, 2, true)]);
+
+        let mut empty = Canvas::new(0, 0, rgba(0f32, 0f32, 0f32, 1f32));
+        empty.flip_horizontal();
+        empty.flip_vertical();
+        assert_eq!(empty.rotate_90().width(), 0);
+    }
+
+    #[test]
+    fn overlapping_fills_blend_once() {
+        // The middle of a pentagram goes around twice with the nonzero rule.
+        let star: Vec<Point2> = (0..5)
+            .map(|i| {
+                let angle =
+                    -std::f32::consts::FRAC_PI_2 + i as f32 * 4f32 * std::f32::consts::PI / 5f32;
+                Point2::new(30f32 + 25f32 * angle.cos(), 30f32 + 25f32 * angle.sin())
+            })
+            .collect();
+        let mut canvas = Canvas::new(60, 60, rgba(1f32, 1f32, 1f32, 1f32));
+        canvas.set_fill_rule(FillRule::NonZero);
+        canvas.set_draw_color(rgba(0f32, 0f32, 0f32, 0.5f32));
+        path::Polygon::new(star).fill(&mut canvas);
+        assert!((red(&canvas, 30, 30) - red(&canvas, 30, 8)).abs() < 1e-4);
+
+        // A shape filled out of two overlapping squares.
+        struct TwoSquares(Rectangle, Rectangle);
+        impl Path for TwoSquares {
+            fn stroke(&self, canvas: &mut Canvas, width: f32) {
+                self.0.stroke(canvas, width);
+                self.1.stroke(canvas, width);
+            }
+
+            fn offset(&self, distance: f32) -> path::OpenMultiPath {
+                let mut offset = self.0.offset(distance);
+                offset.append(self.1.offset(distance));
+                offset
+            }
+
+            fn to_fill_outline(&self, canvas: &Canvas, width: f32) -> path::Polygon {
+                let mut contours = self.0.to_fill_outline(canvas, width).outline(0f32);
+                contours.extend(self.1.to_fill_outline(canvas, width).outline(0f32));
+                path::Polygon::from_contours(contours)
+            }
+
+            fn bounding_box(&self) -> (Point2, Point2) {
+                let (first, second) = (self.0.bounding_box(), self.1.bounding_box());
+                (first.0.min(second.0), first.1.max(second.1))
+            }
+        }
+        impl Loop for TwoSquares {
+            fn fill(&self, canvas: &mut Canvas) {
+                self.0.fill(canvas);
+                self.1.fill(canvas);
+            }
+
+            fn outline(&self, tolerance: f32) -> Vec<Vec<Point2>> {
+                let mut outline = self.0.outline(tolerance);
+                outline.extend(self.1.outline(tolerance));
+                outline
+            }
+        }
+        let squares = TwoSquares(
+            Rectangle::new(Point2::new(5f32, 5f32), Point2::new(30f32, 30f32)),
+            Rectangle::new(Point2::new(20f32, 20f32), Point2::new(50f32, 50f32)),
+        );
+        let mut separately = Canvas::new(60, 60, rgba(1f32, 1f32, 1f32, 1f32));
+        separately.set_draw_color(rgba(0f32, 0f32, 0f32, 0.5f32));
+        squares.fill(&mut separately);
+        assert!(red(&separately, 25, 25) < red(&separately, 10, 10) - 0.05);
+        let mut seamless = Canvas::new(60, 60, rgba(1f32, 1f32, 1f32, 1f32));
+        seamless.set_draw_color(rgba(0f32, 0f32, 0f32, 0.5f32));
+        seamless.fill_seamless(&squares);
+        assert!((red(&seamless, 25, 25) - red(&seamless, 10, 10)).abs() < 1e-4);
+        assert!((red(&seamless, 10, 10) - red(&separately, 10, 10)).abs() < 1e-4);
+    }
+
+    #[test]
+    fn global_alpha() {
+        let square = Rectangle::new(Point2::new(2f32, 2f32), Point2::new(18f32, 18f32));
+        let mut faded = Canvas::new(20, 20, rgba(1f32, 1f32, 1f32, 1f32));
+        faded.set_gamma(1f32);
+        faded.set_draw_color(rgba(0f32, 0f32, 1f32, 1f32));
+        faded.set_global_alpha(0.5);
+        square.fill(&mut faded);
+        let pixel = faded.get_pixel(10, 10).unwrap();
+        assert!(color::colors_approx_eq(
+            &pixel,
+            &rgba(0.5f32, 0.5f32, 1f32, 1f32),
+            1e-4
+        ));
+        assert_eq!(red(&faded, 0, 0), 1f32);
+
+        // It's the same as drawing with half of the alpha.
+        let mut translucent = Canvas::new(20, 20, rgba(1f32, 1f32, 1f32, 1f32));
+        translucent.set_gamma(1f32);
+        translucent.set_draw_color(rgba(0f32, 0f32, 1f32, 0.5f32));
+        square.fill(&mut translucent);
+        assert!(faded.pixels_equal(&translucent, 1e-5));
+
+        faded.set_global_alpha(3f32);
+        assert_eq!(faded.get_global_alpha(), 1f32);
+        faded.set_global_alpha(-1f32);
+        assert_eq!(faded.get_global_alpha(), 0f32);
+        let before: Vec<f32> = faded.pixels().map(|(_, _, pixel)| pixel.r).collect();
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(20f32, 20f32)).stroke(&mut faded, 4f32);
+        assert_eq!(
+            before,
+            faded
+                .pixels()
+                .map(|(_, _, pixel)| pixel.r)
+                .collect::<Vec<_>>()
+        );
+
+        // A seamless stroke gets faded once, after its pieces have been put together.
+        let mut seamless = Canvas::new(20, 20, rgba(1f32, 1f32, 1f32, 1f32));
+        seamless.set_gamma(1f32);
+        seamless.set_draw_color(rgba(0f32, 0f32, 0f32, 1f32));
+        seamless.set_global_alpha(0.5);
+        seamless.stroke_seamless(
+            &Line::new(Point2::new(0f32, 10f32), Point2::new(20f32, 10f32)),
+            4f32,
+        );
+        assert!((red(&seamless, 10, 10) - 0.5).abs() < 1e-4);
+
+        // Coverage masks tell how much a shape covers, which the global alpha doesn't change.
+        let mask = seamless.coverage_mask(&square);
+        assert!((mask[3 * 20 + 3] - 1f32).abs() < 1e-6);
+        assert_eq!(seamless.get_global_alpha(), 0.5);
+    }
 }
@@ -0,0 +1,162 @@
+use crate::path::{Loop, Path};
+use crate::{helper_pixel_count, Canvas, CanvasColorBuf};
+
+/// A buffer holding a single coverage in `[0, 1]` for each pixel.
+pub struct AlphaBuf {
+    buf: Vec<f32>,
+    width: u64,
+    height: u64,
+}
+
+impl AlphaBuf {
+    /// Creates a buffer where nothing is covered.
+    pub fn new(width: u64, height: u64) -> AlphaBuf {
+        AlphaBuf {
+            buf: vec![0f32; helper_pixel_count(width, height)],
+            width,
+            height,
+        }
+    }
+
+    pub fn get_width(&self) -> u64 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn get_coverage(&self, x: u64, y: u64) -> Result<f32, colorbuf::ColorBufError> {
+        if x >= self.width || y >= self.height {
+            return Err(colorbuf::ColorBufError::InvalidCoordinate);
+        }
+
+        Ok(self.buf[self.index(x, y)])
+    }
+
+    /// Sets the coverage of a pixel, clamped to be within `[0, 1]`.
+    pub fn set_coverage(
+        &mut self,
+        x: u64,
+        y: u64,
+        coverage: f32,
+    ) -> Result<(), colorbuf::ColorBufError> {
+        if x >= self.width || y >= self.height {
+            return Err(colorbuf::ColorBufError::InvalidCoordinate);
+        }
+
+        let index = self.index(x, y);
+        self.buf[index] = coverage.clamp(0f32, 1f32);
+        Ok(())
+    }
+
+    /// Adds to the coverage of a pixel, up to the point of it being fully covered.
+    pub(crate) fn add_coverage(&mut self, x: u64, y: u64, coverage: f32) {
+        let index = self.index(x, y);
+        self.buf[index] = (self.buf[index] + coverage).min(1f32);
+    }
+
+    // The pixels are stored row by row, just like with canvases.
+    fn index(&self, x: u64, y: u64) -> usize {
+        (y * self.width + x) as usize
+    }
+}
+
+/// A canvas which only keeps track of how much of each pixel the shapes drawn
+/// onto it cover, without any colors. Masks can then be applied to color
+/// canvases with `Canvas::apply_mask`.
+///
+/// The shapes get combined like opaque shapes drawn onto a transparent canvas
+/// would, so where two shapes both cover half a pixel, three quarters of it
+/// are covered in the end.
+pub struct MaskCanvas {
+    canvas: Canvas,
+    mask: AlphaBuf,
+}
+
+impl MaskCanvas {
+    /// Creates a mask where nothing is covered.
+    pub fn new(width: u64, height: u64) -> MaskCanvas {
+        let transparent = colorbuf::Color {
+            r: 0f32,
+            g: 0f32,
+            b: 0f32,
+            a: 0f32,
+        };
+        // The rasterizers give their coverages to the coverage layer we set up
+        // when drawing, so the canvas never needs any pixels of its own.
+        let canvas = Canvas {
+            backing: CanvasColorBuf::without_pixels(width, height),
+            ..Canvas::new(0, 0, transparent)
+        };
+
+        MaskCanvas {
+            canvas,
+            mask: AlphaBuf::new(width, height),
+        }
+    }
+
+    pub fn width(&self) -> u64 {
+        self.mask.get_width()
+    }
+
+    pub fn height(&self) -> u64 {
+        self.mask.get_height()
+    }
+
+    pub fn get_coverage(&self, x: u64, y: u64) -> Result<f32, colorbuf::ColorBufError> {
+        self.mask.get_coverage(x, y)
+    }
+
+    /// Gets the coverages of the whole mask.
+    pub fn alpha(&self) -> &AlphaBuf {
+        &self.mask
+    }
+
+    pub fn enable_antialias(&mut self, enable: bool) {
+        self.canvas.enable_antialias(enable);
+    }
+
+    pub fn fill(&mut self, path: &dyn Loop) {
+        self.draw(|c| path.fill(c));
+    }
+
+    pub fn stroke(&mut self, path: &dyn Path, width: f32) {
+        self.draw(|c| path.stroke(c, width));
+    }
+
+    fn draw<F: FnOnce(&mut Canvas)>(&mut self, draw: F) {
+        let (width, height) = (self.width(), self.height());
+        self.canvas.coverage_layer = Some(AlphaBuf::new(width, height));
+
+        draw(&mut self.canvas);
+
+        let layer = self.canvas.coverage_layer.take().unwrap();
+        for (covered, &coverage) in self.mask.buf.iter_mut().zip(layer.buf.iter()) {
+            *covered += coverage * (1f32 - *covered);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color_rgb8;
+    use crate::path::Circle;
+    use crate::vector::Point2;
+
+    #[test]
+    fn filled_circle_mask() {
+        let mut mask = MaskCanvas::new(20, 20);
+        mask.fill(&Circle::new(Point2::new(10f32, 10f32), 5f32));
+        assert!((mask.get_coverage(10, 10).unwrap() - 1f32).abs() < 1e-5);
+        assert_eq!(mask.get_coverage(0, 0).unwrap(), 0f32);
+
+        // Pixels outside of the mask aren't covered by it.
+        let mut canvas = Canvas::new(30, 30, color_rgb8(255, 0, 0));
+        canvas.apply_mask(&mask);
+        assert!((canvas.get_pixel(10, 10).unwrap().a - 1f32).abs() < 1e-5);
+        assert_eq!(canvas.get_pixel(0, 0).unwrap().a, 0f32);
+        assert_eq!(canvas.get_pixel(25, 25).unwrap().a, 0f32);
+    }
+}
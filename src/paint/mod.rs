@@ -0,0 +1,230 @@
+use crate::vector::Point2;
+use crate::Canvas;
+
+/// What the shapes drawn on a canvas get filled with.
+#[derive(Clone)]
+pub enum Paint {
+    /// A single color everywhere.
+    Solid(colorbuf::Color),
+    RadialGradient(RadialGradient),
+    Pattern(Pattern),
+}
+
+impl Paint {
+    /// Gets the color of this paint at the given point.
+    pub fn color_at(&self, p: Point2) -> colorbuf::Color {
+        match self {
+            Paint::Solid(color) => *color,
+            Paint::RadialGradient(gradient) => gradient.color_at(p),
+            Paint::Pattern(pattern) => pattern.color_at(p),
+        }
+    }
+}
+
+/// A gradient whose color depends on the distance from its center.
+///
+/// The offsets of the color stops go from 0 at the center to 1 at the radius,
+/// and everything past the radius gets the color of the last stop.
+#[derive(Clone)]
+pub struct RadialGradient {
+    center: Point2,
+    radius: f32,
+    stops: Vec<(f32, colorbuf::Color)>,
+}
+
+impl RadialGradient {
+    pub fn new(center: Point2, radius: f32) -> RadialGradient {
+        RadialGradient {
+            center,
+            radius,
+            stops: Vec::new(),
+        }
+    }
+
+    /// Adds a color stop at the given offset, which gets clamped to be within `[0, 1]`.
+    pub fn add_stop(&mut self, offset: f32, color: colorbuf::Color) -> &mut RadialGradient {
+        let offset = offset.clamp(0f32, 1f32);
+        // Keep the stops sorted, with later stops at the same offset going after the earlier ones.
+        let index = self
+            .stops
+            .iter()
+            .position(|&(o, _)| o > offset)
+            .unwrap_or(self.stops.len());
+        self.stops.insert(index, (offset, color));
+        self
+    }
+
+    pub fn color_at(&self, p: Point2) -> colorbuf::Color {
+        if self.radius <= 0f32 {
+            return helper_color_at_offset(&self.stops[..], 0f32);
+        }
+
+        let offset = ((p - self.center).length() / self.radius).min(1f32);
+        helper_color_at_offset(&self.stops[..], offset)
+    }
+}
+
+/// A tile of pixels repeated over the whole canvas, with its top left corner
+/// at the origin.
+#[derive(Clone)]
+pub struct Pattern {
+    tile: Vec<colorbuf::Color>,
+    width: u64,
+    height: u64,
+}
+
+impl Pattern {
+    /// Creates a checkerboard of square cells, which are `cell_size` pixels wide. The
+    /// cell at the origin gets the first color.
+    pub fn checkerboard(
+        first: colorbuf::Color,
+        second: colorbuf::Color,
+        cell_size: u64,
+    ) -> Pattern {
+        let cell_size = cell_size.max(1);
+        let side = 2 * cell_size;
+        let tile = (0..side * side)
+            .map(|i| {
+                let (x, y) = (i % side, i / side);
+                if (x / cell_size + y / cell_size) % 2 == 1 {
+                    second
+                } else {
+                    first
+                }
+            })
+            .collect();
+
+        Pattern {
+            tile,
+            width: side,
+            height: side,
+        }
+    }
+
+    /// Creates a pattern which tiles a copy of the pixels of `canvas`.
+    pub fn from_canvas(canvas: &Canvas) -> Pattern {
+        let (width, height) = (canvas.width(), canvas.height());
+        let tile = (0..width * height)
+            .map(|i| canvas.get_pixel(i % width, i / width).unwrap())
+            .collect();
+
+        Pattern {
+            tile,
+            width,
+            height,
+        }
+    }
+
+    /// Gets the color of the pixel of the tile which `p` lands on.
+    ///
+    /// An empty tile is transparent black everywhere.
+    pub fn color_at(&self, p: Point2) -> colorbuf::Color {
+        if self.width == 0 || self.height == 0 {
+            return colorbuf::Color {
+                r: 0f32,
+                g: 0f32,
+                b: 0f32,
+                a: 0f32,
+            };
+        }
+
+        let x = (p.get_x().floor() as i64).rem_euclid(self.width as i64) as u64;
+        let y = (p.get_y().floor() as i64).rem_euclid(self.height as i64) as u64;
+        self.tile[(y * self.width + x) as usize]
+    }
+}
+
+/// Finds the color for the given offset in a sorted list of color stops,
+/// interpolating linearly between the stops around it.
+///
+/// Without any stops everything is transparent black.
+fn helper_color_at_offset(stops: &[(f32, colorbuf::Color)], offset: f32) -> colorbuf::Color {
+    let (first, last) = match (stops.first(), stops.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            return colorbuf::Color {
+                r: 0f32,
+                g: 0f32,
+                b: 0f32,
+                a: 0f32,
+            };
+        }
+    };
+
+    if offset <= first.0 {
+        return first.1;
+    }
+
+    for pair in stops.windows(2) {
+        let (start, end) = (&pair[0], &pair[1]);
+        if offset > end.0 {
+            continue;
+        }
+
+        let t = (offset - start.0) / (end.0 - start.0);
+        return colorbuf::Color {
+            r: start.1.r + (end.1.r - start.1.r) * t,
+            g: start.1.g + (end.1.g - start.1.g) * t,
+            b: start.1.b + (end.1.b - start.1.b) * t,
+            a: start.1.a + (end.1.a - start.1.a) * t,
+        };
+    }
+
+    last.1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color_rgb8;
+    use crate::path::{Circle, Loop, Rectangle};
+
+    #[test]
+    fn radial_gradient_fill() {
+        let mut gradient = RadialGradient::new(Point2::new(20f32, 20f32), 10f32);
+        gradient
+            .add_stop(0f32, color_rgb8(255, 255, 255))
+            .add_stop(1f32, color_rgb8(0, 0, 0));
+        let halfway = gradient.color_at(Point2::new(25f32, 20f32));
+        assert!((halfway.r - 0.5).abs() < 1e-6);
+
+        let mut canvas = Canvas::new(40, 40, color_rgb8(0, 0, 0));
+        canvas.set_paint(Paint::RadialGradient(gradient));
+        Circle::new(Point2::new(20f32, 20f32), 10f32).fill(&mut canvas);
+        assert!(canvas.get_pixel(20, 20).unwrap().r > 0.9);
+        assert!(canvas.get_pixel(28, 20).unwrap().r < 0.2);
+
+        // Without a radius, everything gets the first stop.
+        let mut point = RadialGradient::new(Point2::new(20f32, 20f32), 0f32);
+        point
+            .add_stop(0f32, color_rgb8(255, 0, 0))
+            .add_stop(1f32, color_rgb8(0, 0, 255));
+        assert_eq!(point.color_at(Point2::new(30f32, 30f32)).r, 1f32);
+    }
+
+    #[test]
+    fn checkerboard_pattern_fill() {
+        let (white, black) = (color_rgb8(255, 255, 255), color_rgb8(0, 0, 0));
+        let mut canvas = Canvas::new(20, 20, color_rgb8(0, 0, 255));
+        canvas.set_paint(Paint::Pattern(Pattern::checkerboard(white, black, 4)));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(16f32, 16f32)).fill(&mut canvas);
+        for y in 0..16 {
+            for x in 0..16 {
+                let expected = if (x / 4 + y / 4) % 2 == 0 { 1f32 } else { 0f32 };
+                let pixel = canvas.get_pixel(x, y).unwrap();
+                assert_eq!((pixel.r, pixel.b), (expected, expected));
+            }
+        }
+        assert_eq!(canvas.get_pixel(17, 17).unwrap().b, 1f32);
+
+        // A canvas can be tiled as well.
+        let mut tile = Canvas::new(2, 1, black);
+        tile.set_draw_color(white);
+        tile.enable_antialias(false);
+        Rectangle::new(Point2::new(1f32, 0f32), Point2::new(2f32, 1f32)).fill(&mut tile);
+        canvas.set_paint(Paint::Pattern(Pattern::from_canvas(&tile)));
+        Rectangle::new(Point2::new(0f32, 0f32), Point2::new(20f32, 20f32)).fill(&mut canvas);
+        assert_eq!(canvas.get_pixel(0, 5).unwrap().r, 0f32);
+        assert_eq!(canvas.get_pixel(1, 5).unwrap().r, 1f32);
+    }
+}
@@ -1,198 +1,3886 @@
 use crate::Canvas;
 use std::collections::VecDeque;
 
+use crate::angle::Angle;
 use crate::vector::{Point2, Vector2};
 
+/// The tolerance canvases flatten curves with by default, which is also used
+/// for flattening where there's no canvas to take the tolerance from.
+pub const DEFAULT_TOLERANCE: f32 = 0.1f32;
+
 pub trait Path {
     fn stroke(&self, c: &mut Canvas, width: f32);
+
+    /// Gets the smallest axis-aligned box containing the whole path, as its
+    /// minimum and maximum corners.
+    ///
+    /// A path without any points gives a box whose minimum is past its maximum.
+    fn bounding_box(&self) -> (Point2, Point2);
+
+    /// Gets the path going alongside this one at the given distance from it, made out
+    /// of lines. Curves get flattened within `DEFAULT_TOLERANCE` first, and the corners
+    /// of the offset path are mitered.
+    ///
+    /// Positive distances go to the left of open paths as seen on the canvas, and
+    /// negative ones to the right. Closed shapes instead grow by positive distances
+    /// and shrink by negative ones, whichever way around they go, and the holes in
+    /// them shrink as they grow. Each closed outline gives a loop of lines of its own,
+    /// one after the other in the path.
+    fn offset(&self, distance: f32) -> OpenMultiPath;
+
+    /// Gets the outline of what stroking the path on the canvas with the given width
+    /// covers, as a polygon which can be filled instead, or exported elsewhere. The
+    /// outline has the caps, the joins and the miter limit of the canvas, and curves get
+    /// flattened with its flattening tolerance. The outline is centered on the path
+    /// whatever the stroke alignment is, and it doesn't have the dash pattern.
+    ///
+    /// An open path gives one contour going around it, and a closed one gives a contour
+    /// on each side of it, going around in opposite directions. Where the path crosses
+    /// over itself or bends tighter than the stroke is wide, the outline overlaps itself,
+    /// and only gets filled all the way with `FillRule::NonZero`.
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon;
+
+    /// Gets a box containing a stroke of the path with the given width, which is the
+    /// bounding box grown by half of the width on every side. Miter joins and square
+    /// caps can stick out further than that.
+    fn stroke_bounding_box(&self, width: f32) -> (Point2, Point2) {
+        let (min, max) = self.bounding_box();
+        let half_width = Vector2::new(width / 2f32, width / 2f32);
+        (min - half_width, max + half_width)
+    }
+
+    /// Strokes the path with everything in the given style, instead of
+    /// with the stroke settings of the canvas.
+    ///
+    /// The settings of the canvas are left as they were before.
+    fn stroke_with(&self, c: &mut Canvas, style: &StrokeStyle) {
+        let previous = (c.line_cap, c.line_join, c.miter_limit, c.stroke_align);
+        let previous_dash = std::mem::replace(&mut c.dash, style.dash.clone());
+        c.line_cap = style.cap;
+        c.line_join = style.join;
+        c.miter_limit = style.miter_limit;
+        c.stroke_align = style.align;
+
+        self.stroke(c, style.width);
+
+        let (cap, join, miter_limit, align) = previous;
+        c.line_cap = cap;
+        c.line_join = join;
+        c.miter_limit = miter_limit;
+        c.stroke_align = align;
+        c.dash = previous_dash;
+    }
+}
+
+/// Everything that affects how a path gets stroked.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StrokeStyle {
+    pub width: f32,
+    pub cap: LineCap,
+    pub join: LineJoin,
+    /// See `Canvas::set_miter_limit`.
+    pub miter_limit: f32,
+    pub align: StrokeAlign,
+    /// The lengths of the alternating drawn and skipped parts of a dashed stroke.
+    pub dash: Option<Vec<f32>>,
+}
+
+impl Default for StrokeStyle {
+    fn default() -> StrokeStyle {
+        StrokeStyle {
+            width: 1f32,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10f32,
+            align: StrokeAlign::Center,
+            dash: None,
+        }
+    }
+}
+
+/// The shape used for the ends of stroked lines and curves.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineCap {
+    /// The stroke ends exactly at the endpoint.
+    Butt,
+    /// The stroke ends in a half-disk centered at the endpoint.
+    Round,
+    /// The stroke is extended past the endpoint by half of its width.
+    Square,
+}
+
+/// The shape used to fill the corners between the parts of stroked paths.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineJoin {
+    /// The outer edges are extended until they meet. If the resulting
+    /// corner would be longer than the miter limit, a bevel is used instead.
+    Miter,
+    /// The corner is rounded off with a disk.
+    Round,
+    /// The corner is cut off by a straight edge.
+    Bevel,
+}
+
+/// Where the stroke of a closed shape goes relative to the boundary of the shape.
+///
+/// Circles, ellipses and rectangles, rounded or not, follow the alignment.
+/// Everything else, including all open paths, always gets centered strokes.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StrokeAlign {
+    /// The stroke is split evenly between both sides of the boundary.
+    Center,
+    /// The stroke lies completely inside of the shape.
+    Inside,
+    /// The stroke lies completely outside of the shape.
+    Outside,
+}
+
+impl StrokeAlign {
+    /// Gets how far a stroke of the given width reaches inside and outside
+    /// of the boundary, in that order.
+    fn extents(self, width: f32) -> (f32, f32) {
+        match self {
+            StrokeAlign::Center => (width / 2f32, width / 2f32),
+            StrokeAlign::Inside => (width, 0f32),
+            StrokeAlign::Outside => (0f32, width),
+        }
+    }
+}
+
+/// The rule deciding which points are inside of a filled shape,
+/// which matters when the outline of the shape crosses itself.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FillRule {
+    /// A point is inside if a ray from it crosses the outline an odd number of times.
+    EvenOdd,
+    /// A point is inside if the outline winds around it, that is if the parts of the
+    /// outline crossing a ray from it don't all cancel each other out by going in
+    /// opposite directions.
+    NonZero,
 }
 
 pub trait Loop: Path {
     fn fill(&self, c: &mut Canvas);
+
+    /// Gets the outline of this shape as closed polygons, with curves flattened
+    /// so that they stray at most `tolerance` from the actual outline.
+    fn outline(&self, tolerance: f32) -> Vec<Vec<Point2>>;
+
+    /// Tells whether the point is inside of this shape, by the even-odd rule.
+    ///
+    /// The outline gets flattened with the same tolerance canvases use by default.
+    fn contains(&self, p: Point2) -> bool {
+        let contours = self.outline(DEFAULT_TOLERANCE);
+        let crossed = contours
+            .iter()
+            .filter(|contour| crate::helper_even_odd_rule(p.get_x(), p.get_y(), &contour[..]))
+            .count();
+        crossed % 2 == 1
+    }
 }
 
 pub struct OpenMultiPath {
-    parts: Vec<Box<Curve>>,
+    parts: Vec<Box<dyn Curve>>,
 }
 
-pub struct ClosedMultiPath {
-    parts: Vec<Box<Curve>>,
+impl OpenMultiPath {
+    pub fn new(parts: Vec<Box<dyn Curve>>) -> OpenMultiPath {
+        OpenMultiPath { parts }
+    }
+
+    pub fn push(&mut self, part: Box<dyn Curve>) -> &mut OpenMultiPath {
+        self.parts.push(part);
+        self
+    }
+
+    /// Moves the parts of `other` onto the end of this path.
+    pub fn append(&mut self, other: OpenMultiPath) -> &mut OpenMultiPath {
+        self.parts.extend(other.parts);
+        self
+    }
+
+    /// Gets the path going alongside this one at the given distance, like
+    /// `Curve::offset_within` does for a single curve. All of the parts get offset
+    /// together, with the corners between them mitered.
+    pub fn offset_within(&self, distance: f32, tolerance: f32) -> OpenMultiPath {
+        let points = flatten_parts(&self.parts[..], tolerance);
+        lines_between(&offset_polyline(&points[..], false, distance)[..], false)
+    }
 }
 
-pub struct Circle {
-    center: Point2,
-    radius: f32,
+impl Path for OpenMultiPath {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        stroke_parts(&self.parts[..], false, c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        self.offset_within(distance, DEFAULT_TOLERANCE)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        let points = flatten_parts(&self.parts[..], c.flattening_tolerance);
+        stroke_outline(c, &[(points, false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        self.parts
+            .iter()
+            .map(|part| part.bounding_box())
+            .fold(empty_bounding_box(), union_bounding_boxes)
+    }
 }
 
-impl Circle {
-    pub fn new(center: Point2, radius: f32) -> Circle {
-        Circle { center, radius, }
+pub struct ClosedMultiPath {
+    parts: Vec<Box<dyn Curve>>,
+}
+
+impl ClosedMultiPath {
+    pub fn new(parts: Vec<Box<dyn Curve>>) -> ClosedMultiPath {
+        ClosedMultiPath { parts }
+    }
+
+    pub fn push(&mut self, part: Box<dyn Curve>) -> &mut ClosedMultiPath {
+        self.parts.push(part);
+        self
+    }
+
+    /// Gets the outline of this shape grown by the given distance, or shrunk by it
+    /// if the distance is negative, whichever way around the parts go.
+    ///
+    /// See `Curve::offset_within` for how the offset outline gets made. Only the loops
+    /// which don't go over the seam at the start of the path get removed, and offsetting
+    /// inwards by more than the shape is thick still leaves some outline.
+    pub fn offset_within(&self, distance: f32, tolerance: f32) -> ClosedMultiPath {
+        let points = flatten_parts(&self.parts[..], tolerance);
+        ClosedMultiPath {
+            parts: offset_contours(&[points], distance).parts,
+        }
     }
 }
 
-impl Path for Circle {
+impl Path for ClosedMultiPath {
     fn stroke(&self, c: &mut Canvas, width: f32) {
-        let inner_radius = (self.radius) - (width / 2f32);
-        let outer_radius = (self.radius) + (width / 2f32);
+        stroke_parts(&self.parts[..], true, c, width);
+    }
 
-        c.rasterize_stroked_circle(self.center, inner_radius, outer_radius);
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        OpenMultiPath::new(self.offset_within(distance, DEFAULT_TOLERANCE).parts)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        let points = flatten_parts(&self.parts[..], c.flattening_tolerance);
+        stroke_outline(c, &[(points, true)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        self.parts
+            .iter()
+            .map(|part| part.bounding_box())
+            .fold(empty_bounding_box(), union_bounding_boxes)
     }
 }
 
-impl Loop for Circle {
+impl Loop for ClosedMultiPath {
+    /// Fills the area enclosed by the parts of this path. Where a part doesn't start
+    /// where the previous one ended, or the last part doesn't end where the first one
+    /// started, the gap is bridged with a straight line.
     fn fill(&self, c: &mut Canvas) {
-        c.rasterize_stroked_circle(self.center, 0f32, self.radius);
+        let points = flatten_parts(&self.parts[..], c.flattening_tolerance);
+        if points.len() < 3 {
+            return;
+        }
+
+        c.rasterize_filled_polygon(&points[..]);
+    }
+
+    fn outline(&self, tolerance: f32) -> Vec<Vec<Point2>> {
+        vec![flatten_parts(&self.parts[..], tolerance)]
     }
 }
 
-/// This trait represents a curve defined based on a parametric function.
+/// Builds multi-part paths one segment at a time, with each segment
+/// starting where the previous one ended.
+pub struct PathBuilder {
+    parts: Vec<Box<dyn Curve>>,
+    start: Point2,
+    current: Point2,
+}
+
+impl PathBuilder {
+    /// Creates a builder with no segments, starting at the origin.
+    pub fn new() -> PathBuilder {
+        let origin = Point2::new(0f32, 0f32);
+        PathBuilder {
+            parts: Vec::new(),
+            start: origin,
+            current: origin,
+        }
+    }
+
+    /// Moves to `point` without adding a segment. This is also where
+    /// `close` will go back to.
+    pub fn move_to(mut self, point: Point2) -> PathBuilder {
+        self.start = point;
+        self.current = point;
+        self
+    }
+
+    pub fn line_to(mut self, end: Point2) -> PathBuilder {
+        self.parts.push(Box::new(Line::new(self.current, end)));
+        self.current = end;
+        self
+    }
+
+    pub fn quad_to(mut self, control: Point2, end: Point2) -> PathBuilder {
+        let curve = QuadBezierCurve::new(self.current, control, end);
+        self.parts.push(Box::new(curve));
+        self.current = end;
+        self
+    }
+
+    pub fn cubic_to(mut self, c1: Point2, c2: Point2, end: Point2) -> PathBuilder {
+        let curve = CubicBezierCurve::new(self.current, c1, c2, end);
+        self.parts.push(Box::new(curve));
+        self.current = end;
+        self
+    }
+
+    /// Adds a line back to the point given to the last `move_to`,
+    /// unless we're already there.
+    pub fn close(self) -> PathBuilder {
+        if self.current == self.start {
+            return self;
+        }
+        let start = self.start;
+        self.line_to(start)
+    }
+
+    pub fn build(self) -> OpenMultiPath {
+        OpenMultiPath::new(self.parts)
+    }
+
+    pub fn build_closed(self) -> ClosedMultiPath {
+        ClosedMultiPath::new(self.parts)
+    }
+}
+
+impl Default for PathBuilder {
+    fn default() -> PathBuilder {
+        PathBuilder::new()
+    }
+}
+
+/// An open path made out of straight lines between the given points.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polyline {
+    points: Vec<Point2>,
+}
+
+impl Polyline {
+    pub fn new(points: Vec<Point2>) -> Polyline {
+        Polyline { points }
+    }
+}
+
+impl Path for Polyline {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        stroke_lines(&self.points[..], false, c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        let points = offset_polyline(&self.points[..], false, distance);
+        lines_between(&points[..], false)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.points.clone(), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        bounding_box_of(self.points.iter().cloned())
+    }
+}
+
+/// A closed shape with straight edges between the given points,
+/// which may also have holes in it.
 ///
-/// A curve in grafizo is defined as a parametric function which gets lineraly
-/// interpreted on between two or more points.
-pub trait Curve: Path {
-    /// This function tells us how long the curve
-    /// thinks it is at a maximum.
-    ///
-    /// This can be used for deciding how many steps are used
-    /// to approximate the function.
+/// The polygon doesn't need to be convex, and it is filled with the fill rule of the canvas.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Polygon {
+    points: Vec<Point2>,
+    holes: Vec<Vec<Point2>>,
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Point2>) -> Polygon {
+        Polygon {
+            points,
+            holes: Vec::new(),
+        }
+    }
+
+    /// Creates a polygon with the outline `outer`, with each of `holes`
+    /// being the outline of a hole cut out of it.
     ///
-    /// Note: The actual length of the curve may not match this.
-    fn approximate_length(&self) -> f32;
-    fn get_point(&self, t: f32) -> Point2;
-    fn derivative(&self, t: f32) -> [f32; 2];
+    /// The holes get turned to go around in the opposite direction of the outer
+    /// outline, so that they stay empty with the nonzero fill rule as well.
+    pub fn with_holes(outer: Vec<Point2>, holes: Vec<Vec<Point2>>) -> Polygon {
+        let mut polygon = Polygon {
+            points: outer,
+            holes,
+        };
+        polygon.orient_holes();
+        polygon
+    }
+
+    /// Makes a polygon out of the contours as they are, with the first one as the
+    /// outline and the rest as holes. Unlike with `with_holes`, the holes are left
+    /// going around the way they do.
+    pub(crate) fn from_contours(mut contours: Vec<Vec<Point2>>) -> Polygon {
+        contours.retain(|contour| !contour.is_empty());
+        if contours.is_empty() {
+            return Polygon::new(Vec::new());
+        }
+        let points = contours.remove(0);
+        Polygon {
+            points,
+            holes: contours,
+        }
+    }
+
+    /// Tells whether the points go around the polygon clockwise, as seen on the
+    /// canvas where the y-axis points downwards.
+    pub fn is_clockwise(&self) -> bool {
+        helper_signed_area(&self.points[..]) > 0f32
+    }
+
+    /// Reverses the order of the points if needed, so that they go around
+    /// the polygon counter-clockwise. The holes then go around clockwise.
+    pub fn to_counter_clockwise(&mut self) {
+        if self.is_clockwise() {
+            self.points.reverse();
+            self.orient_holes();
+        }
+    }
+
+    fn orient_holes(&mut self) {
+        let clockwise = self.is_clockwise();
+        for hole in self.holes.iter_mut() {
+            if (helper_signed_area(&hole[..]) > 0f32) == clockwise {
+                hole.reverse();
+            }
+        }
+    }
 }
 
-pub struct Line {
-    p0: Point2,
-    p1: Point2,
+impl Path for Polygon {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        stroke_lines(&self.points[..], true, c, width);
+        for hole in self.holes.iter() {
+            stroke_lines(&hole[..], true, c, width);
+        }
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        // The holes are inside of the outer boundary, so they can't make the box any larger.
+        bounding_box_of(self.points.iter().cloned())
+    }
 }
 
-impl Line {
-    pub fn new(p0: Point2, p1: Point2) -> Line {
-        Line { p0, p1 }
+impl Loop for Polygon {
+    fn fill(&self, c: &mut Canvas) {
+        let contours = std::iter::once(&self.points[..])
+            .chain(self.holes.iter().map(|hole| &hole[..]))
+            .collect::<Vec<_>>();
+        let rule = c.fill_rule;
+        c.rasterize_filled_contours(&contours[..], rule);
+    }
+
+    fn outline(&self, _: f32) -> Vec<Vec<Point2>> {
+        std::iter::once(self.points.clone())
+            .chain(self.holes.iter().cloned())
+            .collect()
     }
 }
 
-impl Path for Line {
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Circle {
+    center: Point2,
+    radius: f32,
+}
+
+impl Circle {
+    pub fn new(center: Point2, radius: f32) -> Circle {
+        Circle { center, radius, }
+    }
+}
+
+impl Path for Circle {
     fn stroke(&self, c: &mut Canvas, width: f32) {
-        let half_width = width / 2f32;
+        let (inside, outside) = c.stroke_align.extents(width);
+        if c.dash.is_some() {
+            // The dash pattern is walked along the middle of the stroke, starting from angle 0.
+            let radius = self.radius + (outside - inside) / 2f32;
+            let mut points = c.flatten_arc(self.center, radius, 0f32, 2f32 * std::f32::consts::PI);
+            let last = points.len() - 1;
+            points[last] = points[0];
+            if stroke_dashed(&points[..], true, c, width) {
+                return;
+            }
+        }
 
-        let vec = self.p1 - self.p0;
-        let norm = Vector2::new(vec.get_y(), -vec.get_x());
-        let norm = norm.unit() * half_width;
+        let inner_radius = (self.radius) - inside;
+        let outer_radius = (self.radius) + outside;
 
-        // Now to create the rectangle that is our actual "thick line".
-        let p1 = self.p0 - norm;
-        let p2 = self.p1 - norm;
-        let p3 = self.p1 + norm;
-        let p4 = self.p0 + norm;
+        c.rasterize_stroked_circle(self.center, inner_radius, outer_radius);
+    }
 
-        c.rasterize_filled_rectangle(p1, p2, p3, p4);
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
     }
-}
 
-impl Curve for Line {
-    fn approximate_length(&self) -> f32 {
-        (square(self.p1.get_x() - self.p0.get_x()) + square(self.p1.get_y() - self.p0.get_y()))
-            .sqrt()
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
     }
 
-    fn get_point(&self, t: f32) -> Point2 {
-        let x = (1f32 - t) * self.p0.get_x() + t * self.p1.get_x();
-        let y = (1f32 - t) * self.p0.get_y() + t * self.p1.get_y();
+    fn bounding_box(&self) -> (Point2, Point2) {
+        let extent = Vector2::new(self.radius, self.radius);
+        (self.center - extent, self.center + extent)
+    }
+}
 
-        Point2::new(x, y)
+impl Loop for Circle {
+    fn fill(&self, c: &mut Canvas) {
+        c.rasterize_filled_circle(self.center, self.radius);
     }
 
-    fn derivative(&self, _: f32) -> [f32; 2] {
-        let dx = -self.p0.get_x() + self.p1.get_x();
-        let dy = -self.p0.get_y() + self.p1.get_y();
+    fn outline(&self, tolerance: f32) -> Vec<Vec<Point2>> {
+        if self.radius <= 0f32 {
+            return Vec::new();
+        }
 
-        [dx, dy]
+        let circumference = 2f32 * std::f32::consts::PI * self.radius;
+        let line_segments = flattened_segment_count(circumference, self.radius, tolerance);
+        let points = (0..line_segments)
+            .map(|i| {
+                let angle = 2f32 * std::f32::consts::PI * (i as f32) / (line_segments as f32);
+                let (sin, cos) = angle.sin_cos();
+                self.center + Vector2::new(cos, sin) * self.radius
+            })
+            .collect();
+        vec![points]
+    }
+
+    fn contains(&self, p: Point2) -> bool {
+        self.center.distance(p) <= self.radius
     }
 }
 
-pub struct QuadBezierCurve {
-    p0: [f32; 2],
-    p1: [f32; 2],
-    p2: [f32; 2],
+/// A rectangle, which is axis-aligned unless it gets rotated.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rectangle {
+    corners: [Point2; 4],
 }
 
-impl QuadBezierCurve {
-    pub fn new(begin: Point2, control: Point2, end: Point2) -> QuadBezierCurve {
-        QuadBezierCurve {
-            p0: [begin.get_x(), begin.get_y()],
-            p1: [control.get_x(), control.get_y()],
-            p2: [end.get_x(), end.get_y()],
+impl Rectangle {
+    /// Creates an axis-aligned rectangle with `min` and `max` as its opposite corners.
+    pub fn new(min: Point2, max: Point2) -> Rectangle {
+        Rectangle {
+            corners: [
+                min,
+                Point2::new(max.get_x(), min.get_y()),
+                max,
+                Point2::new(min.get_x(), max.get_y()),
+            ],
         }
     }
+
+    /// Rotates the rectangle around `center` by the given angle.
+    pub fn rotated(self, center: Point2, angle: Angle) -> Rectangle {
+        let mut corners = self.corners;
+        for corner in corners.iter_mut() {
+            *corner = center + (*corner - center).rotate(angle.as_radians());
+        }
+        Rectangle { corners }
+    }
+
+    /// Gets the corners of this rectangle with its sides moved outwards by `offset`.
+    ///
+    /// Gives nothing if the offset rectangle is empty.
+    fn offset_corners(&self, offset: f32) -> Option<[Point2; 4]> {
+        let [first, second, _, last] = self.corners;
+        let (along, across) = (second - first, last - first);
+        let half_width = along.length() / 2f32 + offset;
+        let half_height = across.length() / 2f32 + offset;
+        if half_width <= 0f32 || half_height <= 0f32 {
+            return None;
+        }
+
+        let center = first + (along + across) * 0.5f32;
+        let along = along.unit() * half_width;
+        let across = across.unit() * half_height;
+        Some([
+            center - along - across,
+            center + along - across,
+            center + along + across,
+            center - along + across,
+        ])
+    }
 }
 
-impl Path for QuadBezierCurve {
+impl Path for Rectangle {
     fn stroke(&self, c: &mut Canvas, width: f32) {
-        let half_width = width / 2f32;
+        if c.stroke_align == StrokeAlign::Center {
+            stroke_lines(&self.corners[..], true, c, width);
+            return;
+        }
 
-        // We want to use a line-based approximation of
-        // our Bezier curve.
-        //
-        // So for that to work we need to know how many line
-        // segments we want to have. We are going to use a hyperbola
-        // so we get a somewhat linear approximation for the amount
-        // of segments needed while having the count be high for
-        // low numbers. The particular hyperbola we'll be using is
-        // `sqrt(x * x + 100), for x >= 0` (never actually going to be 0).
-        let line_segment_hyperbola =
-            |length: f32| (((length * length) + 100f32).sqrt() + 1f32) as u64;
-        let approx_len = self.approximate_length();
-        let line_segments = line_segment_hyperbola(approx_len);
-
-        let mut left_edge: Vec<Point2> = Vec::new();
-        let mut right_edge: VecDeque<Point2> = VecDeque::new();
-
-        for i in 0..=line_segments {
-            let t = (i as f32) / (line_segments as f32);
-            let curr_point = self.get_point(t);
-            let [dx, dy] = self.derivative(t);
-            let norm = Vector2::new(dy, -dx).unit() * half_width;
-
-            let left = curr_point - norm;
-            let right = curr_point + norm;
-
-            left_edge.push(left);
-            right_edge.push_front(right);
-        }
-
-        let point = left_edge.into_iter().chain(right_edge).collect::<Vec<_>>();
-        c.rasterize_convex_filled_polygon(&point[..]);
+        // A stroke to one side is the ring between the offset boundaries, like
+        // with rounded rectangles. The outer corners of the ring stay sharp.
+        let (inside, outside) = c.stroke_align.extents(width);
+        let mut outer = match self.offset_corners(outside) {
+            Some(corners) => corners.to_vec(),
+            None => return,
+        };
+        if let Some(corners) = self.offset_corners(-inside) {
+            let mut inner = corners.to_vec();
+            outer.push(outer[0]);
+            inner.push(inner[0]);
+            inner.reverse();
+            outer.extend(inner);
+        }
+        c.rasterize_convex_filled_polygon(&outer[..]);
     }
-}
 
-impl Curve for QuadBezierCurve {
-    fn approximate_length(&self) -> f32 {
-        (square(self.p1[0] - self.p0[0]) + square(self.p1[1] - self.p0[1])).sqrt()
-            + (square(self.p2[0] - self.p1[0]) + square(self.p2[1] - self.p1[1])).sqrt()
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
     }
 
-    fn get_point(&self, t: f32) -> Point2 {
-        let x = square(1f32 - t) * self.p0[0]
-            + 2f32 * t * (1f32 - t) * self.p1[0]
-            + square(t) * self.p2[0];
-        let y = square(1f32 - t) * self.p0[1]
-            + 2f32 * t * (1f32 - t) * self.p1[1]
-            + square(t) * self.p2[1];
-        Point2::new(x, y)
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
     }
 
-    fn derivative(&self, t: f32) -> [f32; 2] {
-        let dx = (-2f32 + 2f32 * t) * self.p0[0]
-            + (2f32 - 4f32 * t) * self.p1[0]
-            + 2f32 * t * self.p2[0];
-        let dy = (-2f32 + 2f32 * t) * self.p0[1]
-            + (2f32 - 4f32 * t) * self.p1[1]
-            + 2f32 * t * self.p2[1];
-        [dx, dy]
+    fn bounding_box(&self) -> (Point2, Point2) {
+        bounding_box_of(self.corners.iter().cloned())
     }
 }
-fn square(x: f32) -> f32 {
-    x * x
+
+impl Loop for Rectangle {
+    fn fill(&self, c: &mut Canvas) {
+        c.rasterize_filled_polygon(&self.corners[..]);
+    }
+
+    fn outline(&self, _: f32) -> Vec<Vec<Point2>> {
+        vec![self.corners.to_vec()]
+    }
+}
+
+/// An axis-aligned rectangle whose corners are rounded off with quarter circles.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RoundedRect {
+    min: Point2,
+    max: Point2,
+    corner_radius: f32,
+}
+
+impl RoundedRect {
+    /// Creates a rounded rectangle with `min` and `max` as its opposite corners.
+    ///
+    /// The corner radius is limited to half of the shorter side, at which point
+    /// the short sides become half-circles.
+    pub fn new(min: Point2, max: Point2, corner_radius: f32) -> RoundedRect {
+        let (min, max) = (min.min(max), min.max(max));
+        let size = max - min;
+        let max_radius = size.get_x().min(size.get_y()) / 2f32;
+
+        RoundedRect {
+            min,
+            max,
+            corner_radius: corner_radius.max(0f32).min(max_radius),
+        }
+    }
+
+    /// Gets the points on the boundary of this rectangle, offset outwards
+    /// by `offset`. The corners of the offset boundary stay rounded around
+    /// the same centers, unless the offset takes their radius below zero.
+    ///
+    /// Gives nothing if the offset boundary is empty.
+    fn boundary(&self, offset: f32) -> Vec<Point2> {
+        let (min_x, min_y) = (self.min.get_x() - offset, self.min.get_y() - offset);
+        let (max_x, max_y) = (self.max.get_x() + offset, self.max.get_y() + offset);
+        if min_x >= max_x || min_y >= max_y {
+            return Vec::new();
+        }
+
+        let radius = (self.corner_radius + offset).max(0f32);
+        let quarter = std::f32::consts::FRAC_PI_2;
+        // The corners in the order of growing angles, with their centers
+        // and the angle at which their quarter circle starts.
+        let corners = [
+            (max_x - radius, max_y - radius, 0f32),
+            (min_x + radius, max_y - radius, quarter),
+            (min_x + radius, min_y + radius, 2f32 * quarter),
+            (max_x - radius, min_y + radius, 3f32 * quarter),
+        ];
+
+        let mut points = Vec::new();
+        for &(center_x, center_y, start_angle) in corners.iter() {
+            if radius == 0f32 {
+                points.push(Point2::new(center_x, center_y));
+                continue;
+            }
+
+            let line_segments = approximate_segment_count(quarter * radius);
+            for i in 0..=line_segments {
+                let angle = start_angle + quarter * (i as f32) / (line_segments as f32);
+                let (sin, cos) = angle.sin_cos();
+                let spoke = Vector2::new(cos, sin) * radius;
+                points.push(Point2::new(center_x, center_y) + spoke);
+            }
+        }
+        points
+    }
+}
+
+impl Path for RoundedRect {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        let (inside, outside) = c.stroke_align.extents(width);
+
+        // Just like with ellipses, the stroke is the ring between the
+        // outer and the inner boundary, joined into one polygon.
+        let mut outer = self.boundary(outside);
+        if outer.is_empty() {
+            return;
+        }
+        let mut inner = self.boundary(-inside);
+        if !inner.is_empty() {
+            outer.push(outer[0]);
+            inner.push(inner[0]);
+            inner.reverse();
+        }
+
+        let points = outer.into_iter().chain(inner).collect::<Vec<_>>();
+        c.rasterize_convex_filled_polygon(&points[..]);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        (self.min, self.max)
+    }
+}
+
+impl Loop for RoundedRect {
+    fn fill(&self, c: &mut Canvas) {
+        let points = self.boundary(0f32);
+        if points.is_empty() {
+            return;
+        }
+
+        c.rasterize_convex_filled_polygon(&points[..]);
+    }
+
+    fn outline(&self, _: f32) -> Vec<Vec<Point2>> {
+        vec![self.boundary(0f32)]
+    }
+}
+
+/// A part of the circumference of a circle, going from the starting angle to the ending
+/// angle. The angles grow from the positive x-axis towards the positive y-axis.
+///
+/// If the ending angle is smaller than the starting angle, the arc still goes in the
+/// direction of growing angles, wrapping around past the full turn.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Arc {
+    center: Point2,
+    radius: f32,
+    start_angle: f32,
+    end_angle: f32,
+}
+
+impl Arc {
+    pub fn new(center: Point2, radius: f32, start_angle: Angle, end_angle: Angle) -> Arc {
+        Arc {
+            center,
+            radius,
+            start_angle: start_angle.as_radians(),
+            end_angle: end_angle.as_radians(),
+        }
+    }
+
+    fn point_at(&self, angle: f32) -> Point2 {
+        let (sin, cos) = angle.sin_cos();
+        self.center + Vector2::new(cos, sin) * self.radius
+    }
+
+    /// Approximates the arc with a polyline from its start to its end, straying
+    /// at most `tolerance` from the arc.
+    fn flatten(&self, tolerance: f32) -> Vec<Point2> {
+        let (start_angle, sweep) = normalize_arc(self.start_angle, self.end_angle);
+        let arc_length = sweep * self.radius;
+        let line_segments = flattened_segment_count(arc_length, self.radius, tolerance);
+
+        (0..=line_segments)
+            .map(|i| {
+                let angle = start_angle + sweep * (i as f32) / (line_segments as f32);
+                self.point_at(angle)
+            })
+            .collect()
+    }
+}
+
+impl Path for Arc {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        let inner_radius = (self.radius) - (width / 2f32);
+        let outer_radius = (self.radius) + (width / 2f32);
+
+        let arc = normalize_arc(self.start_angle, self.end_angle);
+        c.rasterize_stroked_arc(self.center, inner_radius, outer_radius, Some(arc));
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        let points = offset_polyline(&self.flatten(DEFAULT_TOLERANCE)[..], false, distance);
+        lines_between(&points[..], false)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.flatten(c.flattening_tolerance), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        let (start_angle, sweep) = normalize_arc(self.start_angle, self.end_angle);
+        let full_turn = 2f32 * std::f32::consts::PI;
+
+        // Besides its ends, an arc can only reach furthest along the axes
+        // where it crosses them.
+        let crossings = (0..4)
+            .map(|i| (i as f32) * std::f32::consts::FRAC_PI_2)
+            .filter(|angle| (angle - start_angle).rem_euclid(full_turn) <= sweep);
+        let angles = [start_angle, start_angle + sweep];
+        bounding_box_of(
+            angles
+                .iter()
+                .cloned()
+                .chain(crossings)
+                .map(|angle| self.point_at(angle)),
+        )
+    }
+}
+
+/// A slice of a disk, bounded by an arc and the two radii at its ends.
+///
+/// The angles work like they do with `Arc`.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pie {
+    arc: Arc,
+}
+
+impl Pie {
+    pub fn new(center: Point2, radius: f32, start_angle: Angle, end_angle: Angle) -> Pie {
+        Pie {
+            arc: Arc::new(center, radius, start_angle, end_angle),
+        }
+    }
+}
+
+impl Path for Pie {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        let (start_angle, sweep) = normalize_arc(self.arc.start_angle, self.arc.end_angle);
+
+        self.arc.stroke(c, width);
+        Line::new(self.arc.center, self.arc.point_at(start_angle)).stroke(c, width);
+        Line::new(self.arc.center, self.arc.point_at(start_angle + sweep)).stroke(c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        let center = (self.arc.center, self.arc.center);
+        union_bounding_boxes(self.arc.bounding_box(), center)
+    }
+}
+
+impl Loop for Pie {
+    fn fill(&self, c: &mut Canvas) {
+        let arc = normalize_arc(self.arc.start_angle, self.arc.end_angle);
+        c.rasterize_filled_sector(self.arc.center, self.arc.radius, Some(arc));
+    }
+
+    fn outline(&self, tolerance: f32) -> Vec<Vec<Point2>> {
+        let arc = self.arc.flatten(tolerance);
+        vec![std::iter::once(self.arc.center).chain(arc).collect()]
+    }
+}
+
+/// Turns the starting and ending angles of an arc into the starting
+/// angle within `[0, 2pi)` and the sweep of the arc within `[0, 2pi]`.
+fn normalize_arc(start_angle: f32, end_angle: f32) -> (f32, f32) {
+    let full_turn = 2f32 * std::f32::consts::PI;
+    let sweep = end_angle - start_angle;
+    let sweep = if sweep >= full_turn {
+        full_turn
+    } else {
+        sweep.rem_euclid(full_turn)
+    };
+    (start_angle.rem_euclid(full_turn), sweep)
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Ellipse {
+    center: Point2,
+    rx: f32,
+    ry: f32,
+    rotation: f32,
+}
+
+impl Ellipse {
+    pub fn new(center: Point2, rx: f32, ry: f32) -> Ellipse {
+        Ellipse {
+            center,
+            rx,
+            ry,
+            rotation: 0f32,
+        }
+    }
+
+    /// Rotates the axes of the ellipse by the given angle.
+    pub fn with_rotation(self, rotation: Angle) -> Ellipse {
+        Ellipse {
+            rotation: rotation.as_radians(),
+            ..self
+        }
+    }
+
+    /// Gets the points on the boundary of this ellipse, offset
+    /// outwards along the normal of the boundary by `offset`.
+    fn boundary(&self, offset: f32) -> Vec<Point2> {
+        let circumference = 2f32 * std::f32::consts::PI * self.rx.max(self.ry);
+        let line_segments = approximate_segment_count(circumference);
+
+        (0..line_segments)
+            .map(|i| {
+                let t = 2f32 * std::f32::consts::PI * (i as f32) / (line_segments as f32);
+                let (sin, cos) = t.sin_cos();
+                let mut local = Vector2::new(self.rx * cos, self.ry * sin);
+                if offset != 0f32 {
+                    let norm = Vector2::new(self.ry * cos, self.rx * sin).unit();
+                    local = local + norm * offset;
+                }
+                self.center + local.rotate(self.rotation)
+            })
+            .collect()
+    }
+}
+
+impl Path for Ellipse {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        if self.rx <= 0f32 || self.ry <= 0f32 {
+            // A flat ellipse is just a line going back and forth along the other axis.
+            if self.rx <= 0f32 && self.ry <= 0f32 {
+                return;
+            }
+            let (sin_rot, cos_rot) = self.rotation.sin_cos();
+            let axis = if self.rx > 0f32 {
+                Vector2::new(cos_rot, sin_rot) * self.rx
+            } else {
+                Vector2::new(-sin_rot, cos_rot) * self.ry
+            };
+            Line::new(self.center - axis, self.center + axis).stroke(c, width);
+            return;
+        }
+
+        let (inside, outside) = c.stroke_align.extents(width);
+        if c.dash.is_some() {
+            // Like with circles, the dashes start from the end of the x axis of the ellipse.
+            let mut points = self.boundary((outside - inside) / 2f32);
+            points.push(points[0]);
+            if stroke_dashed(&points[..], true, c, width) {
+                return;
+            }
+        }
+
+        // The stroke is the ring between the outer and the inner boundary. We join the
+        // two loops into one polygon, so the even-odd rule leaves the inside empty.
+        let mut outer = self.boundary(outside);
+        let mut inner = self.boundary(-inside);
+        outer.push(outer[0]);
+        inner.push(inner[0]);
+        inner.reverse();
+
+        let points = outer.into_iter().chain(inner).collect::<Vec<_>>();
+        c.rasterize_convex_filled_polygon(&points[..]);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        offset_contours(&self.outline(DEFAULT_TOLERANCE)[..], distance)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        loop_stroke_outline(self, c, width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        let (sin, cos) = self.rotation.sin_cos();
+        let extent = Vector2::new(
+            (square(self.rx * cos) + square(self.ry * sin)).sqrt(),
+            (square(self.rx * sin) + square(self.ry * cos)).sqrt(),
+        );
+        (self.center - extent, self.center + extent)
+    }
+}
+
+impl Loop for Ellipse {
+    fn fill(&self, c: &mut Canvas) {
+        if self.rx <= 0f32 || self.ry <= 0f32 {
+            return;
+        }
+
+        let points = self.boundary(0f32);
+        c.rasterize_convex_filled_polygon(&points[..]);
+    }
+
+    fn outline(&self, _: f32) -> Vec<Vec<Point2>> {
+        if self.rx <= 0f32 || self.ry <= 0f32 {
+            return Vec::new();
+        }
+
+        vec![self.boundary(0f32)]
+    }
+}
+
+/// This trait represents a curve defined based on a parametric function.
+///
+/// A curve in grafizo is defined as a parametric function which gets lineraly
+/// interpreted on between two or more points.
+pub trait Curve: Path {
+    /// This function tells us how long the curve
+    /// thinks it is at a maximum.
+    ///
+    /// This can be used for deciding how many steps are used
+    /// to approximate the function.
+    ///
+    /// Note: The actual length of the curve may not match this.
+    fn approximate_length(&self) -> f32;
+    fn get_point(&self, t: f32) -> Point2;
+    fn derivative(&self, t: f32) -> [f32; 2];
+
+    /// Approximates the curve with a polyline, whose points are returned.
+    ///
+    /// The curve gets split into smaller pieces for as long as the points on the
+    /// curve deviate from the straight line between the ends of the piece by more
+    /// than `tolerance`. A straight curve thus gives just its two endpoints.
+    fn flatten(&self, tolerance: f32) -> Vec<Point2> {
+        flatten_parameters(self, tolerance)
+            .into_iter()
+            .map(|t| self.get_point(t))
+            .collect()
+    }
+
+    /// Measures the length along the curve, from its start to its end.
+    fn arc_length(&self) -> f32 {
+        arc_length_table(self).last().map_or(0f32, |&(_, s)| s)
+    }
+
+    /// Gets the point which is the given distance along the curve from its start.
+    ///
+    /// Unlike with `get_point`, evenly spaced distances give evenly spaced points.
+    /// Distances past either end of the curve give that end.
+    fn point_at_arc_length(&self, s: f32) -> Point2 {
+        let table = arc_length_table(self);
+        let total = table.last().map_or(0f32, |&(_, s)| s);
+        if total <= 0f32 || !s.is_finite() {
+            return self.get_point(0f32);
+        }
+        let s = s.clamp(0f32, total);
+
+        // Find the piece of the curve the distance falls on, and go the rest
+        // of the way within the piece as if it was straight.
+        let after = table
+            .iter()
+            .position(|&(_, length)| length >= s)
+            .unwrap_or(table.len() - 1)
+            .max(1);
+        let (t0, s0) = table[after - 1];
+        let (t1, s1) = table[after];
+        let t = if s1 > s0 {
+            t0 + (t1 - t0) * (s - s0) / (s1 - s0)
+        } else {
+            t1
+        };
+        self.get_point(t)
+    }
+
+    /// Gets the path going alongside the curve at the given distance from it, made out of
+    /// lines. The curve gets flattened within `tolerance` first, like with `flatten`, and
+    /// then the points get moved along the normal of the curve. Positive distances go to
+    /// the left of the curve as seen on the canvas, and negative ones to the right.
+    ///
+    /// Where the curve bends tighter than the distance, the offset path would loop over
+    /// itself, and so the loops get cut out at where the path crosses itself. This only
+    /// keeps the outermost path for simple bends, and paths which cross themselves or
+    /// turn back on themselves can still give loops.
+    fn offset_within(&self, distance: f32, tolerance: f32) -> OpenMultiPath {
+        let points = self.flatten(tolerance);
+        lines_between(&offset_polyline(&points[..], false, distance)[..], false)
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Line {
+    p0: Point2,
+    p1: Point2,
+}
+
+impl Line {
+    pub fn new(p0: Point2, p1: Point2) -> Line {
+        Line { p0, p1 }
+    }
+
+    /// Finds where this line crosses `other`, ordered along this line.
+    ///
+    /// Lines which cross give one intersection, and parallel lines give none. Lines which
+    /// lie on top of each other give the ends of the part they share, or just one point
+    /// if they only touch at their ends. Lines without any length don't cross anything.
+    pub fn intersect_line(&self, other: &Line) -> Vec<Intersection> {
+        let (d0, d1) = (self.p1 - self.p0, other.p1 - other.p0);
+        let (len0, len1) = (d0.length(), d1.length());
+        if len0 <= f32::EPSILON || len1 <= f32::EPSILON {
+            return Vec::new();
+        }
+
+        let between = other.p0 - self.p0;
+        let denominator = d0.cross(&d1);
+        if denominator.abs() > INTERSECTION_EPSILON * len0 * len1 {
+            let t = between.cross(&d1) / denominator;
+            let other_t = between.cross(&d0) / denominator;
+            return match (helper_unit_parameter(t), helper_unit_parameter(other_t)) {
+                (Some(t), Some(other_t)) => vec![Intersection {
+                    point: self.get_point(t),
+                    t,
+                    other_t,
+                }],
+                _ => Vec::new(),
+            };
+        }
+
+        // The lines are parallel, so they only meet if they are on the same line.
+        if between.cross(&d0).abs() > INTERSECTION_EPSILON * len0 * between.length() {
+            return Vec::new();
+        }
+        let along = |p: Point2| (p - self.p0).dot(&d0) / square(len0);
+        let (a, b) = (along(other.p0), along(other.p1));
+        let (start, end) = (a.min(b).max(0f32), a.max(b).min(1f32));
+        if start > end + INTERSECTION_EPSILON {
+            return Vec::new();
+        }
+
+        let mut parameters = vec![start];
+        if end - start > INTERSECTION_EPSILON {
+            parameters.push(end);
+        }
+        parameters
+            .into_iter()
+            .map(|t| {
+                let point = self.get_point(t);
+                let other_t = ((point - other.p0).dot(&d1) / square(len1)).clamp(0f32, 1f32);
+                Intersection { point, t, other_t }
+            })
+            .collect()
+    }
+
+    /// Finds where this line crosses `curve`, ordered along this line. A curve can
+    /// cross a line at most twice.
+    ///
+    /// If the whole curve lies along the line, the ends of the curve which are on the
+    /// line are given. Lines without any length don't cross anything.
+    pub fn intersect_quad(&self, curve: &QuadBezierCurve) -> Vec<Intersection> {
+        let d = self.p1 - self.p0;
+        let length = d.length();
+        if length <= f32::EPSILON {
+            return Vec::new();
+        }
+
+        // Putting the curve into the equation of the line gives a quadratic equation for
+        // the parameters of the curve where it is on the line, as long as the line is
+        // taken as going on forever.
+        let normal = d.perpendicular();
+        let side = |p: [f32; 2]| normal.dot(&(Point2::new(p[0], p[1]) - self.p0));
+        let (s0, s1, s2) = (side(curve.p0), side(curve.p1), side(curve.p2));
+        let (a, b, c) = (s0 - 2f32 * s1 + s2, 2f32 * (s1 - s0), s0);
+
+        let scale = s0.abs().max(s1.abs()).max(s2.abs());
+        let curve_parameters = if scale <= INTERSECTION_EPSILON * length {
+            vec![0f32, 1f32]
+        } else {
+            quadratic_roots(a, b, c)
+                .into_iter()
+                .filter_map(helper_unit_parameter)
+                .collect()
+        };
+
+        let mut intersections: Vec<Intersection> = Vec::new();
+        for other_t in curve_parameters {
+            let point = curve.get_point(other_t);
+            let t = match helper_unit_parameter((point - self.p0).dot(&d) / square(length)) {
+                Some(t) => t,
+                None => continue,
+            };
+            // A curve just touching the line gives the same root twice.
+            let repeated = intersections
+                .iter()
+                .any(|i| (i.other_t - other_t).abs() <= INTERSECTION_EPSILON);
+            if !repeated {
+                intersections.push(Intersection { point, t, other_t });
+            }
+        }
+        intersections.sort_by(|i, j| i.t.partial_cmp(&j.t).unwrap());
+        intersections
+    }
+}
+
+/// A point where two paths cross, found with `Line::intersect_line` or `Line::intersect_quad`.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Intersection {
+    pub point: Point2,
+    /// The parameter of the point on the path whose method found the intersection.
+    pub t: f32,
+    /// The parameter of the point on the other path.
+    pub other_t: f32,
+}
+
+/// How close to each other values need to be to count as the same when finding
+/// intersections, relative to the sizes involved.
+const INTERSECTION_EPSILON: f32 = 1e-5;
+
+/// Gets the parameter if it's on the curve, bringing parameters just past either
+/// end of the curve back onto it so that curves meeting at their ends still cross.
+fn helper_unit_parameter(t: f32) -> Option<f32> {
+    if (-INTERSECTION_EPSILON..=1f32 + INTERSECTION_EPSILON).contains(&t) {
+        Some(t.clamp(0f32, 1f32))
+    } else {
+        None
+    }
+}
+
+impl Path for Line {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        if stroke_dashed(&[self.p0, self.p1], false, c, width) {
+            return;
+        }
+
+        let half_width = width / 2f32;
+
+        let vec = self.p1 - self.p0;
+        let norm = vec.perpendicular().unit() * half_width;
+
+        // With square caps, the line extends past both of its endpoints.
+        let (start, end) = match c.line_cap {
+            LineCap::Square => {
+                let extension = vec.unit() * half_width;
+                (self.p0 - extension, self.p1 + extension)
+            }
+            _ => (self.p0, self.p1),
+        };
+
+        // Lines along the axes fill their box directly, so that its edges stay as straight
+        // as they can. With an integral width they also go along the edges of the pixels.
+        let snap = width.fract() == 0f32 && c.get_transform().is_identity();
+        if let Some((min, max)) = helper_axis_aligned_box(start, end, half_width, snap) {
+            let p2 = Point2::new(max.get_x(), min.get_y());
+            let p4 = Point2::new(min.get_x(), max.get_y());
+            c.rasterize_filled_rectangle(min, p2, max, p4);
+        } else {
+            // Now to create the rectangle that is our actual "thick line".
+            let p1 = start - norm;
+            let p2 = end - norm;
+            let p3 = end + norm;
+            let p4 = start + norm;
+
+            c.rasterize_filled_rectangle(p1, p2, p3, p4);
+        }
+
+        if c.line_cap == LineCap::Round {
+            draw_round_caps(c, self.p0, self.p1, half_width);
+        }
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        self.offset_within(distance, DEFAULT_TOLERANCE)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.flatten(c.flattening_tolerance), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        bounding_box_of(vec![self.p0, self.p1])
+    }
+}
+
+impl Curve for Line {
+    fn approximate_length(&self) -> f32 {
+        (square(self.p1.get_x() - self.p0.get_x()) + square(self.p1.get_y() - self.p0.get_y()))
+            .sqrt()
+    }
+
+    fn get_point(&self, t: f32) -> Point2 {
+        self.p0.lerp(self.p1, t)
+    }
+
+    fn derivative(&self, _: f32) -> [f32; 2] {
+        let dx = -self.p0.get_x() + self.p1.get_x();
+        let dy = -self.p0.get_y() + self.p1.get_y();
+
+        [dx, dy]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct QuadBezierCurve {
+    p0: [f32; 2],
+    p1: [f32; 2],
+    p2: [f32; 2],
+}
+
+impl QuadBezierCurve {
+    pub fn new(begin: Point2, control: Point2, end: Point2) -> QuadBezierCurve {
+        QuadBezierCurve {
+            p0: [begin.get_x(), begin.get_y()],
+            p1: [control.get_x(), control.get_y()],
+            p2: [end.get_x(), end.get_y()],
+        }
+    }
+
+    /// Strokes the curve as the given amount of straight pieces, each over an equal
+    /// part of its parameter, instead of as many as the flattening tolerance of the
+    /// canvas would need. The same curve then always gets the same pieces, no matter
+    /// how it is drawn.
+    pub fn stroke_with_segments(&self, c: &mut Canvas, width: f32, segments: u64) {
+        stroke_curve_pieces(self, &uniform_parameters(segments)[..], c, width);
+    }
+
+    /// Splits the curve at the parameter `t` into the curve before it and the curve after it,
+    /// which together go along the same points as this curve. The parameters of the halves
+    /// go from 0 to 1 over their own part of this curve.
+    pub fn split(&self, t: f32) -> (QuadBezierCurve, QuadBezierCurve) {
+        let [p0, p1, p2] = [self.p0, self.p1, self.p2];
+        let (p01, p12) = (helper_lerp(p0, p1, t), helper_lerp(p1, p2, t));
+        let mid = helper_lerp(p01, p12, t);
+        (
+            QuadBezierCurve {
+                p0,
+                p1: p01,
+                p2: mid,
+            },
+            QuadBezierCurve {
+                p0: mid,
+                p1: p12,
+                p2,
+            },
+        )
+    }
+}
+
+impl Path for QuadBezierCurve {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        stroke_curve_outline(self, c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        self.offset_within(distance, DEFAULT_TOLERANCE)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.flatten(c.flattening_tolerance), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        // The curve is furthest along an axis either at its ends, or where
+        // its derivative along that axis is zero.
+        let extrema = (0..2).filter_map(|axis| {
+            let denominator = self.p0[axis] - 2f32 * self.p1[axis] + self.p2[axis];
+            if denominator == 0f32 {
+                return None;
+            }
+            let t = (self.p0[axis] - self.p1[axis]) / denominator;
+            if t > 0f32 && t < 1f32 {
+                Some(t)
+            } else {
+                None
+            }
+        });
+        bounding_box_of(
+            [0f32, 1f32]
+                .iter()
+                .cloned()
+                .chain(extrema)
+                .map(|t| self.get_point(t)),
+        )
+    }
+}
+
+impl Curve for QuadBezierCurve {
+    fn approximate_length(&self) -> f32 {
+        (square(self.p1[0] - self.p0[0]) + square(self.p1[1] - self.p0[1])).sqrt()
+            + (square(self.p2[0] - self.p1[0]) + square(self.p2[1] - self.p1[1])).sqrt()
+    }
+
+    fn get_point(&self, t: f32) -> Point2 {
+        let x = square(1f32 - t) * self.p0[0]
+            + 2f32 * t * (1f32 - t) * self.p1[0]
+            + square(t) * self.p2[0];
+        let y = square(1f32 - t) * self.p0[1]
+            + 2f32 * t * (1f32 - t) * self.p1[1]
+            + square(t) * self.p2[1];
+        Point2::new(x, y)
+    }
+
+    fn derivative(&self, t: f32) -> [f32; 2] {
+        let dx = (-2f32 + 2f32 * t) * self.p0[0]
+            + (2f32 - 4f32 * t) * self.p1[0]
+            + 2f32 * t * self.p2[0];
+        let dy = (-2f32 + 2f32 * t) * self.p0[1]
+            + (2f32 - 4f32 * t) * self.p1[1]
+            + 2f32 * t * self.p2[1];
+        [dx, dy]
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CubicBezierCurve {
+    p0: [f32; 2],
+    p1: [f32; 2],
+    p2: [f32; 2],
+    p3: [f32; 2],
+}
+
+impl CubicBezierCurve {
+    pub fn new(begin: Point2, c1: Point2, c2: Point2, end: Point2) -> CubicBezierCurve {
+        CubicBezierCurve {
+            p0: [begin.get_x(), begin.get_y()],
+            p1: [c1.get_x(), c1.get_y()],
+            p2: [c2.get_x(), c2.get_y()],
+            p3: [end.get_x(), end.get_y()],
+        }
+    }
+
+    /// Strokes the curve as the given amount of straight pieces.
+    /// See `QuadBezierCurve::stroke_with_segments`.
+    pub fn stroke_with_segments(&self, c: &mut Canvas, width: f32, segments: u64) {
+        stroke_curve_pieces(self, &uniform_parameters(segments)[..], c, width);
+    }
+
+    /// Splits the curve at the parameter `t` into two curves.
+    /// See `QuadBezierCurve::split`.
+    pub fn split(&self, t: f32) -> (CubicBezierCurve, CubicBezierCurve) {
+        let [p0, p1, p2, p3] = [self.p0, self.p1, self.p2, self.p3];
+        let (p01, p12, p23) = (
+            helper_lerp(p0, p1, t),
+            helper_lerp(p1, p2, t),
+            helper_lerp(p2, p3, t),
+        );
+        let (p012, p123) = (helper_lerp(p01, p12, t), helper_lerp(p12, p23, t));
+        let mid = helper_lerp(p012, p123, t);
+        (
+            CubicBezierCurve {
+                p0,
+                p1: p01,
+                p2: p012,
+                p3: mid,
+            },
+            CubicBezierCurve {
+                p0: mid,
+                p1: p123,
+                p2: p23,
+                p3,
+            },
+        )
+    }
+}
+
+impl Path for CubicBezierCurve {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        stroke_curve_outline(self, c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        self.offset_within(distance, DEFAULT_TOLERANCE)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.flatten(c.flattening_tolerance), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        // Just like with quadratic curves, although the derivative is now quadratic.
+        let mut parameters = vec![0f32, 1f32];
+        for axis in 0..2 {
+            let (p0, p1, p2, p3) = (self.p0[axis], self.p1[axis], self.p2[axis], self.p3[axis]);
+            let a = -p0 + 3f32 * p1 - 3f32 * p2 + p3;
+            let b = 2f32 * (p0 - 2f32 * p1 + p2);
+            let c = p1 - p0;
+            parameters.extend(
+                quadratic_roots(a, b, c)
+                    .into_iter()
+                    .filter(|&t| t > 0f32 && t < 1f32),
+            );
+        }
+        bounding_box_of(parameters.into_iter().map(|t| self.get_point(t)))
+    }
+}
+
+impl Curve for CubicBezierCurve {
+    fn approximate_length(&self) -> f32 {
+        (square(self.p1[0] - self.p0[0]) + square(self.p1[1] - self.p0[1])).sqrt()
+            + (square(self.p2[0] - self.p1[0]) + square(self.p2[1] - self.p1[1])).sqrt()
+            + (square(self.p3[0] - self.p2[0]) + square(self.p3[1] - self.p2[1])).sqrt()
+    }
+
+    fn get_point(&self, t: f32) -> Point2 {
+        let u = 1f32 - t;
+        let x = cube(u) * self.p0[0]
+            + 3f32 * square(u) * t * self.p1[0]
+            + 3f32 * u * square(t) * self.p2[0]
+            + cube(t) * self.p3[0];
+        let y = cube(u) * self.p0[1]
+            + 3f32 * square(u) * t * self.p1[1]
+            + 3f32 * u * square(t) * self.p2[1]
+            + cube(t) * self.p3[1];
+        Point2::new(x, y)
+    }
+
+    fn derivative(&self, t: f32) -> [f32; 2] {
+        let u = 1f32 - t;
+        let dx = 3f32 * square(u) * (self.p1[0] - self.p0[0])
+            + 6f32 * u * t * (self.p2[0] - self.p1[0])
+            + 3f32 * square(t) * (self.p3[0] - self.p2[0]);
+        let dy = 3f32 * square(u) * (self.p1[1] - self.p0[1])
+            + 6f32 * u * t * (self.p2[1] - self.p1[1])
+            + 3f32 * square(t) * (self.p3[1] - self.p2[1]);
+        [dx, dy]
+    }
+}
+
+/// A smooth curve going through all of the given points, made out of
+/// uniform Catmull-Rom segments between each pair of consecutive points.
+///
+/// The first and the last points are used as their own neighbours, so the
+/// curve starts at the first point and ends at the last one.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CatmullRomSpline {
+    points: Vec<Point2>,
+}
+
+impl CatmullRomSpline {
+    pub fn new(points: Vec<Point2>) -> CatmullRomSpline {
+        CatmullRomSpline { points }
+    }
+
+    fn segment_count(&self) -> usize {
+        self.points.len().saturating_sub(1)
+    }
+
+    /// Gets the control point at the given index, clamping the index to the ends.
+    fn control(&self, i: isize) -> Vector2 {
+        let last = self.points.len() as isize - 1;
+        self.points[i.max(0).min(last) as usize].to_vector()
+    }
+
+    /// Finds the segment at the given parameter and the parameter within that segment.
+    fn locate(&self, t: f32) -> (isize, f32) {
+        let segments = self.segment_count();
+        let scaled = t.clamp(0f32, 1f32) * (segments as f32);
+        let i = (scaled.floor() as usize).min(segments - 1);
+        (i as isize, scaled - (i as f32))
+    }
+}
+
+impl Path for CatmullRomSpline {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        if self.points.len() < 2 {
+            return;
+        }
+
+        stroke_curve_outline(self, c, width);
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        self.offset_within(distance, DEFAULT_TOLERANCE)
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        stroke_outline(c, &[(self.flatten(c.flattening_tolerance), false)], width)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        if self.points.len() < 2 {
+            return bounding_box_of(self.points.iter().cloned());
+        }
+
+        // Every segment is also a cubic Bezier curve, like when measuring the length.
+        (0..self.segment_count() as isize)
+            .map(|i| {
+                let (p0, p1) = (self.control(i - 1), self.control(i));
+                let (p2, p3) = (self.control(i + 1), self.control(i + 2));
+                let c1 = p1 + (p2 - p0) / 6f32;
+                let c2 = p2 - (p3 - p1) / 6f32;
+                let segment = CubicBezierCurve::new(
+                    p1.to_point(),
+                    c1.to_point(),
+                    c2.to_point(),
+                    p2.to_point(),
+                );
+                segment.bounding_box()
+            })
+            .fold(empty_bounding_box(), union_bounding_boxes)
+    }
+}
+
+impl Curve for CatmullRomSpline {
+    fn approximate_length(&self) -> f32 {
+        // Every segment is also a cubic Bezier curve, so we can
+        // use the lengths of the control polygons of those.
+        (0..self.segment_count() as isize)
+            .map(|i| {
+                let (p0, p1) = (self.control(i - 1), self.control(i));
+                let (p2, p3) = (self.control(i + 1), self.control(i + 2));
+                let c1 = p1 + (p2 - p0) / 6f32;
+                let c2 = p2 - (p3 - p1) / 6f32;
+                (c1 - p1).length() + (c2 - c1).length() + (p2 - c2).length()
+            })
+            .sum()
+    }
+
+    fn get_point(&self, t: f32) -> Point2 {
+        match self.points.len() {
+            0 => return Point2::new(0f32, 0f32),
+            1 => return self.points[0],
+            _ => {}
+        }
+
+        let (i, u) = self.locate(t);
+        let (p0, p1) = (self.control(i - 1), self.control(i));
+        let (p2, p3) = (self.control(i + 1), self.control(i + 2));
+
+        let point = (p1 * 2f32
+            + (p2 - p0) * u
+            + (p0 * 2f32 - p1 * 5f32 + p2 * 4f32 - p3) * square(u)
+            + (p1 * 3f32 - p0 - p2 * 3f32 + p3) * cube(u))
+            * 0.5f32;
+        point.to_point()
+    }
+
+    fn derivative(&self, t: f32) -> [f32; 2] {
+        if self.points.len() < 2 {
+            return [0f32, 0f32];
+        }
+
+        let (i, u) = self.locate(t);
+        let (p0, p1) = (self.control(i - 1), self.control(i));
+        let (p2, p3) = (self.control(i + 1), self.control(i + 2));
+
+        // The parameter of the segment moves faster than t by the amount of segments.
+        let d = ((p2 - p0)
+            + (p0 * 2f32 - p1 * 5f32 + p2 * 4f32 - p3) * (2f32 * u)
+            + (p1 * 3f32 - p0 - p2 * 3f32 + p3) * (3f32 * square(u)))
+            * (0.5f32 * self.segment_count() as f32);
+        [d.get_x(), d.get_y()]
+    }
+}
+
+/// Strokes each of the parts of a multi-part path, and fills
+/// the corners between them according to the line join style.
+///
+/// The parts meet each other at the joins, so only the two ends of an
+/// open path get caps, and a closed path doesn't get any.
+fn stroke_parts(parts: &[Box<dyn Curve>], closed: bool, c: &mut Canvas, width: f32) {
+    if c.dash.is_some() && !parts.is_empty() {
+        // The dashes carry on from one part to the next, so we dash
+        // the whole path at once as if it were a single polyline.
+        let mut points = flatten_parts(parts, c.flattening_tolerance);
+        if closed {
+            points.push(points[0]);
+        }
+
+        if stroke_dashed(&points[..], closed, c, width) {
+            return;
+        }
+    }
+
+    // A closed path whose last part doesn't get back to its start needs a line to close it.
+    let mut parts = parts.iter().map(|part| part.as_ref()).collect::<Vec<_>>();
+    let closing_line = match (parts.first(), parts.last()) {
+        (Some(first), Some(last)) if closed => {
+            Some(Line::new(last.get_point(1f32), first.get_point(0f32)))
+        }
+        _ => None,
+    };
+    if let Some(line) = closing_line.as_ref() {
+        if line.p0.distance(line.p1) > f32::EPSILON {
+            parts.push(line);
+        }
+    }
+
+    let cap = std::mem::replace(&mut c.line_cap, LineCap::Butt);
+    for part in parts.iter() {
+        part.stroke(c, width);
+    }
+    c.line_cap = cap;
+
+    if !closed {
+        if let (Some(first), Some(last)) = (parts.first(), parts.last()) {
+            let [dx0, dy0] = first.derivative(0f32);
+            let [dx1, dy1] = last.derivative(1f32);
+            let half_width = width / 2f32;
+            draw_cap(
+                c,
+                first.get_point(0f32),
+                Vector2::new(-dx0, -dy0),
+                half_width,
+            );
+            draw_cap(c, last.get_point(1f32), Vector2::new(dx1, dy1), half_width);
+        }
+    }
+
+    for (i, part) in parts.iter().enumerate() {
+        let next = if i + 1 < parts.len() {
+            parts[i + 1]
+        } else if closed && parts.len() > 1 {
+            parts[0]
+        } else {
+            break;
+        };
+
+        let [dx0, dy0] = part.derivative(1f32);
+        let [dx1, dy1] = next.derivative(0f32);
+        draw_join(
+            c,
+            part.get_point(1f32),
+            Vector2::new(dx0, dy0),
+            Vector2::new(dx1, dy1),
+            width / 2f32,
+        );
+    }
+}
+
+/// Flattens the parts of a path into a single polyline. The first point of a part is left
+/// out when it's where the previous part ended, so that no point appears twice in a row.
+fn flatten_parts(parts: &[Box<dyn Curve>], tolerance: f32) -> Vec<Point2> {
+    let mut points: Vec<Point2> = Vec::new();
+    for part in parts.iter() {
+        let samples = part.flatten(tolerance);
+        let skip = match (points.last(), samples.first()) {
+            (Some(end), Some(&start)) if end.distance(start) <= f32::EPSILON => 1,
+            _ => 0,
+        };
+        points.extend(samples.into_iter().skip(skip));
+    }
+    points
+}
+
+/// Makes a path out of the lines between consecutive points, and between the last and
+/// the first point if `closed` is set.
+fn lines_between(points: &[Point2], closed: bool) -> OpenMultiPath {
+    let mut parts: Vec<Box<dyn Curve>> = points
+        .windows(2)
+        .map(|pair| Box::new(Line::new(pair[0], pair[1])) as Box<dyn Curve>)
+        .collect();
+    if closed && points.len() > 2 {
+        parts.push(Box::new(Line::new(points[points.len() - 1], points[0])));
+    }
+    OpenMultiPath::new(parts)
+}
+
+/// Grows each of the closed polygons going through the points of `contours` by
+/// `distance`, or shrinks them if it's negative, whichever way around they go. The
+/// first one is the outline of a shape and the rest are holes in it, which shrink as
+/// the outline grows. The lines around the offset polygons follow one after the other.
+fn offset_contours(contours: &[Vec<Point2>], distance: f32) -> OpenMultiPath {
+    let mut parts = Vec::new();
+    for (i, contour) in contours.iter().enumerate() {
+        let outwards = if i == 0 { distance } else { -distance };
+        // Going left of the path goes outwards when it goes around clockwise.
+        let distance = if helper_signed_area(&contour[..]) < 0f32 {
+            -outwards
+        } else {
+            outwards
+        };
+        let offset = offset_polyline(&contour[..], true, distance);
+        parts.extend(lines_between(&offset[..], true).parts);
+    }
+    OpenMultiPath::new(parts)
+}
+
+/// Moves the polyline going through `points` the given distance to its left, as seen on
+/// the canvas. The corners get mitered, unless they are so sharp that the miter would
+/// be too long, in which case they are beveled. The loops this leaves at tight turns
+/// get cut out afterwards.
+fn offset_polyline(points: &[Point2], closed: bool, distance: f32) -> Vec<Point2> {
+    let points = helper_distinct_points(points, closed);
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len().saturating_sub(1)
+    };
+    if points.len() < 2 || segment_count == 0 {
+        return points;
+    }
+
+    let normal = |i: usize| {
+        let (from, to) = (points[i], points[(i + 1) % points.len()]);
+        (to - from).perpendicular().unit()
+    };
+    let mut offset = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let incoming = if i > 0 || closed {
+            Some(normal((i + segment_count - 1) % segment_count))
+        } else {
+            None
+        };
+        let outgoing = if i < segment_count {
+            Some(normal(i))
+        } else {
+            None
+        };
+
+        match (incoming, outgoing) {
+            (Some(n0), Some(n1)) => {
+                // A corner turning all the way back has no bisector, and gets beveled.
+                let bisector = (n0 + n1).unit();
+                let cos = bisector.dot(&n1);
+                if cos > 0.25f32 {
+                    offset.push(point + bisector * (distance / cos));
+                } else {
+                    offset.push(point + n0 * distance);
+                    offset.push(point + n1 * distance);
+                }
+            }
+            (Some(n), None) | (None, Some(n)) => offset.push(point + n * distance),
+            (None, None) => {}
+        }
+    }
+
+    helper_remove_loops(offset, closed)
+}
+
+/// Leaves out the points which are where the previous point is, and the last point
+/// if `closed` is set and it's where the first point is. Without any length, a piece
+/// of a polyline has no normal to go along.
+fn helper_distinct_points(points: &[Point2], closed: bool) -> Vec<Point2> {
+    let mut points = points.to_vec();
+    points.dedup_by(|p, q| p.distance(*q) <= f32::EPSILON);
+    if closed && points.len() > 1 && points[0].distance(points[points.len() - 1]) <= f32::EPSILON {
+        points.pop();
+    }
+    points
+}
+
+/// Gets the outline of what a stroke of the polylines going through the given points
+/// covers, for each of them telling whether it's closed. The outline goes around each
+/// open polyline, with the caps of the canvas at its ends, and along both sides of each
+/// closed one.
+fn stroke_outline(c: &Canvas, polylines: &[(Vec<Point2>, bool)], width: f32) -> Polygon {
+    let half_width = width / 2f32;
+    let mut contours = Vec::new();
+    for &(ref points, closed) in polylines.iter() {
+        let points = helper_distinct_points(&points[..], closed);
+        if points.len() < 2 || half_width <= 0f32 {
+            continue;
+        }
+
+        let left = outline_side(c, &points[..], closed, half_width);
+        let mut right = outline_side(c, &points[..], closed, -half_width);
+        right.reverse();
+        if closed {
+            contours.push(left);
+            contours.push(right);
+            continue;
+        }
+
+        let last = points.len() - 1;
+        let mut contour = left;
+        let end = points[last] - points[last - 1];
+        contour.extend(cap_outline(c, points[last], end, half_width));
+        contour.extend(right);
+        let start = points[0] - points[1];
+        contour.extend(cap_outline(c, points[0], start, half_width));
+        contours.push(contour);
+    }
+    Polygon::from_contours(contours)
+}
+
+/// Gets the outline of what a stroke of the shape covers, with each of the polygons
+/// of its outline as a closed polyline.
+fn loop_stroke_outline<L: Loop + ?Sized>(shape: &L, c: &Canvas, width: f32) -> Polygon {
+    let contours = shape.outline(c.flattening_tolerance).into_iter();
+    let polylines = contours.map(|contour| (contour, true)).collect::<Vec<_>>();
+    stroke_outline(c, &polylines[..], width)
+}
+
+/// Goes along the polyline going through `points` at the given distance to its left,
+/// like `offset_polyline` does, but with the outer corners joined the way the canvas
+/// joins strokes. The points must be distinct, like `helper_distinct_points` leaves them.
+fn outline_side(c: &Canvas, points: &[Point2], closed: bool, distance: f32) -> Vec<Point2> {
+    let segment_count = if closed {
+        points.len()
+    } else {
+        points.len() - 1
+    };
+    let direction = |i: usize| (points[(i + 1) % points.len()] - points[i]).unit();
+    let mut side = Vec::new();
+    for (i, &point) in points.iter().enumerate() {
+        let incoming = if i > 0 || closed {
+            Some(direction((i + segment_count - 1) % segment_count))
+        } else {
+            None
+        };
+        let outgoing = if i < segment_count {
+            Some(direction(i))
+        } else {
+            None
+        };
+        let (d0, d1) = match (incoming, outgoing) {
+            (Some(d0), Some(d1)) => (d0, d1),
+            (Some(d), None) | (None, Some(d)) => {
+                side.push(point + d.perpendicular() * distance);
+                continue;
+            }
+            (None, None) => continue,
+        };
+
+        let (n0, n1) = (d0.perpendicular(), d1.perpendicular());
+        let bisector = (n0 + n1).unit();
+        let cos = bisector.dot(&n1);
+        if d1.dot(&n0) * distance >= 0f32 {
+            // The inner side of a turn goes straight to where its edges cross, unless
+            // the turn is so sharp that they cross far away, like with `offset_polyline`.
+            if cos > 0.25f32 {
+                side.push(point + bisector * (distance / cos));
+            } else {
+                side.push(point + n0 * distance);
+                side.push(point + n1 * distance);
+            }
+            continue;
+        }
+
+        // The outer side has a gap to fill, which gets joined like strokes are.
+        side.push(point + n0 * distance);
+        match c.line_join {
+            LineJoin::Miter => {
+                if cos > 0f32 && 1f32 / cos <= c.miter_limit {
+                    side.push(point + bisector * (distance / cos));
+                }
+            }
+            LineJoin::Round => {
+                let sweep = n0.angle_between(&n1);
+                let radius = distance.abs();
+                let line_segments =
+                    flattened_segment_count(sweep.abs() * radius, radius, c.flattening_tolerance);
+                for k in 1..line_segments {
+                    let angle = sweep * (k as f32) / (line_segments as f32);
+                    side.push(point + (n0 * distance).rotate(angle));
+                }
+            }
+            LineJoin::Bevel => {}
+        }
+        side.push(point + n1 * distance);
+    }
+    side.dedup_by(|p, q| p.distance(*q) <= f32::EPSILON);
+
+    helper_remove_loops(side, closed)
+}
+
+/// Gets the points going around the cap of the canvas at `end`, where a stroke heading
+/// in the direction of `direction` stops, from its left side over to its right side.
+/// A butt cap goes straight across, so it doesn't have any points in between.
+fn cap_outline(c: &Canvas, end: Point2, direction: Vector2, half_width: f32) -> Vec<Point2> {
+    let along = direction.unit() * half_width;
+    let norm = along.perpendicular();
+    match c.line_cap {
+        LineCap::Butt => Vec::new(),
+        LineCap::Square => vec![end + norm + along, end - norm + along],
+        LineCap::Round => {
+            let half_turn = std::f32::consts::PI;
+            let line_segments =
+                flattened_segment_count(half_turn * half_width, half_width, c.flattening_tolerance);
+            (1..line_segments)
+                .map(|k| {
+                    let (sin, cos) = (half_turn * (k as f32) / (line_segments as f32)).sin_cos();
+                    end + norm * cos + along * sin
+                })
+                .collect()
+        }
+    }
+}
+
+/// Cuts out the loops of the polyline going through `points`, where a segment crosses
+/// a later one, by going straight from one crossing segment to the other at where they
+/// cross. If `closed` is set, the segment from the last point back to the first one
+/// counts as well.
+fn helper_remove_loops(mut points: Vec<Point2>, closed: bool) -> Vec<Point2> {
+    if closed {
+        if let Some(&first) = points.first() {
+            points.push(first);
+        }
+    }
+
+    let mut i = 0;
+    while i + 1 < points.len() {
+        let (a, b) = (points[i], points[i + 1]);
+        // Neighbouring segments share a point, so they can't cross anywhere else.
+        let mut j = points.len() - 1;
+        while j > i + 2 {
+            let (p, q) = (points[j - 1], points[j]);
+            if helper_segments_cross(a, b, p, q) {
+                let crossing = Line::new(a, b).intersect_line(&Line::new(p, q));
+                if let Some(crossing) = crossing.first() {
+                    points.splice((i + 1)..j, std::iter::once(crossing.point));
+                    break;
+                }
+            }
+            j -= 1;
+        }
+        i += 1;
+    }
+
+    if closed {
+        points.pop();
+    }
+    points
+}
+
+/// Strokes the polyline going through the given points with the dash pattern of the canvas.
+/// If `closed` is set, the points must end where they started, and a dash running into
+/// that point carries on into the first dash instead of stopping there.
+///
+/// If the canvas has no dash pattern, or the pattern can't be used for dashing, nothing
+/// gets drawn and this returns `false` to tell that the stroke should be solid instead.
+fn stroke_dashed(points: &[Point2], closed: bool, c: &mut Canvas, width: f32) -> bool {
+    let pattern = match c.dash.take() {
+        Some(pattern) => pattern,
+        None => return false,
+    };
+
+    // The dashes themselves are stroked as solid lines, which is why the
+    // pattern is taken out of the canvas until we're done with it.
+    let dashed = match Dasher::new(&pattern[..]) {
+        Some(mut dasher) => {
+            let mut runs = dasher.dash(points);
+            // The last run gets as far as the seam only if the pattern was still on there.
+            let wraps = closed && dasher.on && runs.len() > 1 && runs[0].first() == points.first();
+            if wraps {
+                let first = runs.remove(0);
+                let last = runs.last_mut().unwrap();
+                last.extend(first.into_iter().skip(1));
+            }
+
+            for run in runs {
+                stroke_lines(&run[..], false, c, width);
+            }
+            true
+        }
+        None => false,
+    };
+
+    c.dash = Some(pattern);
+    dashed
+}
+
+/// Keeps track of where we are in a dash pattern while walking along a path.
+struct Dasher<'a> {
+    pattern: &'a [f32],
+    index: usize,
+    remaining: f32,
+    on: bool,
+}
+
+impl<'a> Dasher<'a> {
+    /// Starts at the beginning of the first dash of the pattern. Patterns with negative
+    /// lengths or without any length at all can't be used for dashing, and give `None`.
+    fn new(pattern: &'a [f32]) -> Option<Dasher<'a>> {
+        let valid = pattern.iter().all(|&l| l >= 0f32 && l.is_finite());
+        if !valid || pattern.iter().sum::<f32>() <= 0f32 {
+            return None;
+        }
+
+        Some(Dasher {
+            pattern,
+            index: 0,
+            remaining: pattern[0],
+            on: true,
+        })
+    }
+
+    fn advance(&mut self) {
+        self.index = (self.index + 1) % self.pattern.len();
+        self.remaining = self.pattern[self.index];
+        self.on = !self.on;
+    }
+
+    /// Splits the polyline going through `points` into the runs
+    /// which are drawn, continuing from where the last call left off.
+    fn dash(&mut self, points: &[Point2]) -> Vec<Vec<Point2>> {
+        let mut runs = Vec::new();
+        let mut current = Vec::new();
+        if self.on && !points.is_empty() {
+            current.push(points[0]);
+        }
+
+        for pair in points.windows(2) {
+            let (mut start, end) = (pair[0], pair[1]);
+            let mut segment_length = start.distance(end);
+
+            while segment_length > self.remaining {
+                let split = start.lerp(end, self.remaining / segment_length);
+                segment_length -= self.remaining;
+                start = split;
+
+                // The split either ends the current run or starts a new one.
+                current.push(split);
+                if self.on {
+                    runs.push(std::mem::take(&mut current));
+                }
+                self.advance();
+            }
+
+            self.remaining -= segment_length;
+            if self.on {
+                current.push(end);
+            }
+        }
+
+        if self.on && current.len() >= 2 {
+            runs.push(current);
+        }
+        runs
+    }
+}
+
+/// Strokes the lines between consecutive points, and between the last
+/// and the first point if `closed` is set. Fewer than two points draw nothing.
+fn stroke_lines(points: &[Point2], closed: bool, c: &mut Canvas, width: f32) {
+    if points.len() < 2 {
+        return;
+    }
+
+    let mut lines: Vec<Box<dyn Curve>> = points
+        .windows(2)
+        .map(|pair| Box::new(Line::new(pair[0], pair[1])) as Box<dyn Curve>)
+        .collect();
+    if closed {
+        lines.push(Box::new(Line::new(points[points.len() - 1], points[0])));
+    }
+    stroke_parts(&lines[..], closed, c, width);
+}
+
+/// Fills the corner at `joint` where a stroke heading in the direction
+/// of `incoming` turns to the direction of `outgoing`.
+fn draw_join(c: &mut Canvas, joint: Point2, incoming: Vector2, outgoing: Vector2, half_width: f32) {
+    if incoming.length() <= f32::EPSILON || outgoing.length() <= f32::EPSILON {
+        return;
+    }
+
+    let norm_in = incoming.perpendicular().unit() * half_width;
+    let norm_out = outgoing.perpendicular().unit() * half_width;
+
+    // The gap we need to fill is on the outer side of the turn,
+    // which is the side the outgoing stroke is heading away from.
+    let turn = norm_in.dot(&outgoing);
+    if turn.abs() <= f32::EPSILON {
+        // The parts continue in the same direction, so there is no gap.
+        return;
+    }
+    let (norm_in, norm_out) = if turn < 0f32 {
+        (norm_in, norm_out)
+    } else {
+        (-norm_in, -norm_out)
+    };
+    let outer_in = joint + norm_in;
+    let outer_out = joint + norm_out;
+
+    match c.line_join {
+        LineJoin::Round => {
+            c.rasterize_filled_circle(joint, half_width);
+        }
+        LineJoin::Miter => {
+            // Find where the outer edges meet.
+            let det = incoming.cross(&outgoing);
+            let miter = if det.abs() > f32::EPSILON {
+                let t = (outer_out - outer_in).cross(&outgoing) / det;
+                Some(outer_in + incoming * t)
+            } else {
+                None
+            };
+
+            match miter {
+                Some(miter) if (miter - joint).length() / half_width <= c.miter_limit => {
+                    let points = [joint, outer_in, miter, outer_out];
+                    c.rasterize_convex_filled_polygon(&points[..]);
+                }
+                _ => {
+                    let points = [joint, outer_in, outer_out];
+                    c.rasterize_convex_filled_polygon(&points[..]);
+                }
+            }
+        }
+        LineJoin::Bevel => {
+            let points = [joint, outer_in, outer_out];
+            c.rasterize_convex_filled_polygon(&points[..]);
+        }
+    }
+}
+
+/// Draws the cap of the canvas at `end`, where a stroke heading
+/// in the direction of `direction` stops.
+fn draw_cap(c: &mut Canvas, end: Point2, direction: Vector2, half_width: f32) {
+    if direction.length() <= f32::EPSILON {
+        return;
+    }
+
+    match c.line_cap {
+        LineCap::Butt => {}
+        LineCap::Round => c.rasterize_filled_circle(end, half_width),
+        LineCap::Square => {
+            let along = direction.unit() * half_width;
+            let norm = direction.perpendicular().unit() * half_width;
+            let points = [
+                end - norm,
+                end + along - norm,
+                end + along + norm,
+                end + norm,
+            ];
+            c.rasterize_convex_filled_polygon(&points[..]);
+        }
+    }
+}
+
+/// Strokes a curve by building the outline of the stroke out of the
+/// points offset along the normal on both sides of the curve.
+fn stroke_curve_outline(curve: &dyn Curve, c: &mut Canvas, width: f32) {
+    // We want to use a line-based approximation of
+    // our curve.
+    let parameters = flatten_parameters(curve, c.flattening_tolerance);
+    stroke_curve_pieces(curve, &parameters[..], c, width);
+}
+
+/// Strokes a curve like `stroke_curve_outline` does, but with the straight pieces
+/// going between the points of the curve at `parameters` instead.
+fn stroke_curve_pieces(curve: &dyn Curve, parameters: &[f32], c: &mut Canvas, width: f32) {
+    if c.dash.is_some() {
+        let points = parameters
+            .iter()
+            .map(|&t| curve.get_point(t))
+            .collect::<Vec<_>>();
+        if stroke_dashed(&points[..], false, c, width) {
+            return;
+        }
+    }
+
+    let half_width = width / 2f32;
+    let edges = match offset_edges(curve, parameters, half_width) {
+        Some(edges) => edges,
+        None => return,
+    };
+    let OffsetEdges {
+        centers,
+        mut left_edge,
+        mut right_edge,
+        first_norm,
+        last_norm,
+    } = edges;
+
+    if c.line_cap == LineCap::Square {
+        // The tangents are just the normals rotated back, and they
+        // already have the right length for the extension.
+        let first_tangent = Vector2::new(-first_norm.get_y(), first_norm.get_x());
+        let last_tangent = Vector2::new(-last_norm.get_y(), last_norm.get_x());
+
+        let last = left_edge.len() - 1;
+        left_edge[0] = left_edge[0] - first_tangent;
+        left_edge[last] = left_edge[last] + last_tangent;
+        right_edge[last] = right_edge[last] - first_tangent;
+        right_edge[0] = right_edge[0] + last_tangent;
+    }
+
+    let point = left_edge.into_iter().chain(right_edge).collect::<Vec<_>>();
+    if helper_polygon_self_intersects(&point[..]) {
+        // Where the curve bends tighter than the stroke is wide, the outline folds over
+        // itself and the even-odd rule would leave holes in the stroke.
+        stroke_flattened_pieces(c, &centers[..], half_width);
+    } else {
+        c.rasterize_convex_filled_polygon(&point[..]);
+    }
+
+    if c.line_cap == LineCap::Round {
+        draw_round_caps(c, curve.get_point(0f32), curve.get_point(1f32), half_width);
+    }
+}
+
+/// The points on both sides of a flattened curve, half of the width of a stroke away from it.
+struct OffsetEdges {
+    centers: Vec<Point2>,
+    left_edge: Vec<Point2>,
+    /// Goes from the end of the curve back to its start, so that it
+    /// carries on from the end of the left edge around the stroke.
+    right_edge: VecDeque<Point2>,
+    first_norm: Vector2,
+    last_norm: Vector2,
+}
+
+/// Offsets the points of the curve at the given parameters along its normal to both sides.
+/// Curves which don't have at least two points with a direction give `None`.
+fn offset_edges<C: Curve + ?Sized>(
+    curve: &C,
+    parameters: &[f32],
+    half_width: f32,
+) -> Option<OffsetEdges> {
+    let mut centers: Vec<Point2> = Vec::new();
+    let mut left_edge: Vec<Point2> = Vec::new();
+    let mut right_edge: VecDeque<Point2> = VecDeque::new();
+
+    let mut first_norm: Option<Vector2> = None;
+    let mut last_norm: Option<Vector2> = None;
+    for (i, &t) in parameters.iter().enumerate() {
+        let curr_point = curve.get_point(t);
+        let [dx, dy] = curve.derivative(t);
+        let mut tangent = Vector2::new(dx, dy);
+
+        // At cusps the tangent vanishes, and so we'd get a NaN normal.
+        // In that case we go along the neighbouring piece of the flattened curve,
+        // or if that fails too, keep going in the direction we were heading.
+        if tangent.length() <= f32::EPSILON {
+            let (from, to) = if i + 1 < parameters.len() {
+                (t, parameters[i + 1])
+            } else {
+                (parameters[i.saturating_sub(1)], t)
+            };
+            tangent = curve.get_point(to) - curve.get_point(from);
+        }
+        let norm = if tangent.length() > f32::EPSILON {
+            tangent.perpendicular().unit() * half_width
+        } else {
+            match last_norm {
+                Some(norm) => norm,
+                None => continue,
+            }
+        };
+        if first_norm.is_none() {
+            first_norm = Some(norm);
+        }
+        last_norm = Some(norm);
+
+        let left = curr_point - norm;
+        let right = curr_point + norm;
+
+        centers.push(curr_point);
+        left_edge.push(left);
+        right_edge.push_front(right);
+    }
+
+    if left_edge.len() < 2 {
+        return None;
+    }
+
+    Some(OffsetEdges {
+        centers,
+        left_edge,
+        right_edge,
+        first_norm: first_norm?,
+        last_norm: last_norm?,
+    })
+}
+
+/// Strokes the polyline going through `centers` as the union of a rectangle around
+/// each of its segments and a disk at each point between the segments. Unlike an
+/// outline, this works no matter how sharply the polyline turns.
+///
+/// The ends get extended for square caps, but round caps are left to the caller.
+fn stroke_flattened_pieces(c: &mut Canvas, centers: &[Point2], half_width: f32) {
+    let last = centers.len().saturating_sub(2);
+    let mut pieces: Vec<Vec<Point2>> = Vec::new();
+
+    for (i, pair) in centers.windows(2).enumerate() {
+        let direction = (pair[1] - pair[0]).unit();
+        if direction.length() <= f32::EPSILON {
+            continue;
+        }
+        let norm = direction.perpendicular() * half_width;
+
+        let (mut start, mut end) = (pair[0], pair[1]);
+        if c.line_cap == LineCap::Square {
+            if i == 0 {
+                start = start - direction * half_width;
+            }
+            if i == last {
+                end = end + direction * half_width;
+            }
+        }
+        pieces.push(vec![start - norm, end - norm, end + norm, start + norm]);
+    }
+
+    let line_segments = approximate_segment_count(2f32 * std::f32::consts::PI * half_width);
+    for &joint in centers.iter().skip(1).take(last) {
+        pieces.push(
+            (0..line_segments)
+                .map(|i| {
+                    let angle = 2f32 * std::f32::consts::PI * (i as f32) / (line_segments as f32);
+                    let (sin, cos) = angle.sin_cos();
+                    joint + Vector2::new(cos, sin) * half_width
+                })
+                .collect(),
+        );
+    }
+
+    // With the nonzero rule the pieces fill their union, as long as they all go around
+    // in the same direction.
+    for piece in pieces.iter_mut() {
+        if helper_signed_area(&piece[..]) < 0f32 {
+            piece.reverse();
+        }
+    }
+    let contours = pieces.iter().map(|piece| &piece[..]).collect::<Vec<_>>();
+    c.rasterize_filled_contours(&contours[..], FillRule::NonZero);
+}
+
+/// Gets the corners of the box a horizontal or a vertical line from `start` to `end` covers,
+/// reaching `half_width` to both sides of it. Other lines give `None`.
+///
+/// If `snap` is set, the sides along the line get rounded to the nearest whole pixels,
+/// keeping the width of the box the same.
+fn helper_axis_aligned_box(
+    start: Point2,
+    end: Point2,
+    half_width: f32,
+    snap: bool,
+) -> Option<(Point2, Point2)> {
+    let sides = |center: f32| {
+        let low = center - half_width;
+        let low = if snap { low.round() } else { low };
+        (low, low + 2f32 * half_width)
+    };
+    let (min, max) = bounding_box_of(vec![start, end]);
+
+    if start.get_y() == end.get_y() {
+        let (y0, y1) = sides(start.get_y());
+        Some((Point2::new(min.get_x(), y0), Point2::new(max.get_x(), y1)))
+    } else if start.get_x() == end.get_x() {
+        let (x0, x1) = sides(start.get_x());
+        Some((Point2::new(x0, min.get_y()), Point2::new(x1, max.get_y())))
+    } else {
+        None
+    }
+}
+
+/// Tells whether any two edges of the polygon which aren't next to each other cross.
+fn helper_polygon_self_intersects(points: &[Point2]) -> bool {
+    let n = points.len();
+    if n < 4 {
+        return false;
+    }
+
+    let edge = |i: usize| (points[i], points[(i + 1) % n]);
+    for i in 0..n {
+        // The last edge is next to the first one, so it can't be checked against it.
+        let end = if i == 0 { n - 1 } else { n };
+        for j in (i + 2)..end {
+            let ((a, b), (p, q)) = (edge(i), edge(j));
+            if helper_segments_cross(a, b, p, q) {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Tells whether the segments from `a` to `b` and from `p` to `q` cross each other
+/// at a single point which is not at the end of either of them.
+fn helper_segments_cross(a: Point2, b: Point2, p: Point2, q: Point2) -> bool {
+    let ab = b - a;
+    let pq = q - p;
+    let side = |v: Vector2, from: Point2, point: Point2| v.cross(&(point - from));
+
+    let p_side = side(ab, a, p);
+    let q_side = side(ab, a, q);
+    let a_side = side(pq, p, a);
+    let b_side = side(pq, p, b);
+    p_side * q_side < 0f32 && a_side * b_side < 0f32
+}
+
+/// The area of the polygon, which is positive if the points go around it in the
+/// direction of growing angles and negative otherwise. On the canvas, growing
+/// angles go clockwise.
+fn helper_signed_area(points: &[Point2]) -> f32 {
+    let mut area = 0f32;
+    let mut j = points.len().saturating_sub(1);
+    for (i, p_i) in points.iter().enumerate() {
+        let p_j = points[j];
+        area += p_j.get_x() * p_i.get_y() - p_i.get_x() * p_j.get_y();
+        j = i;
+    }
+    area / 2f32
+}
+
+/// Draws the round caps for a stroke going from `start` to `end`.
+///
+/// The caps are drawn as full disks, since the half facing the
+/// stroke gets covered by the stroke itself.
+fn draw_round_caps(c: &mut Canvas, start: Point2, end: Point2, radius: f32) {
+    c.rasterize_filled_circle(start, radius);
+    c.rasterize_filled_circle(end, radius);
+}
+
+/// Goes the fraction `t` of the way from `a` to `b`, which is the step de Casteljau's
+/// algorithm takes between each pair of neighbouring control points.
+fn helper_lerp(a: [f32; 2], b: [f32; 2], t: f32) -> [f32; 2] {
+    [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t]
+}
+
+/// Splits the parameters of a curve from 0 to 1 into the given amount of equal parts,
+/// giving the parameters between them along with both ends. There is always at least one part.
+fn uniform_parameters(segments: u64) -> Vec<f32> {
+    let segments = segments.max(1);
+    (0..=segments)
+        .map(|i| (i as f32) / (segments as f32))
+        .collect()
+}
+
+/// Finds the parameters of the points which approximate the curve
+/// within the given tolerance. See `Curve::flatten`.
+fn flatten_parameters<C: Curve + ?Sized>(curve: &C, tolerance: f32) -> Vec<f32> {
+    // Without a limit, a bad tolerance or a bad curve could keep us splitting forever.
+    const MAX_DEPTH: u32 = 10;
+
+    fn subdivide<C: Curve + ?Sized>(
+        curve: &C,
+        tolerance: f32,
+        (t0, p0): (f32, Point2),
+        (t1, p1): (f32, Point2),
+        depth: u32,
+        parameters: &mut Vec<f32>,
+    ) {
+        // Checking just the middle would miss curves which cross the chord there,
+        // like an S-shaped curve does, so we check the quarters as well.
+        let max_deviation = [0.25f32, 0.5f32, 0.75f32]
+            .iter()
+            .map(|&f| {
+                let p = curve.get_point(t0 + (t1 - t0) * f);
+                helper_distance_to_segment(p, p0, p1)
+            })
+            .fold(0f32, f32::max);
+
+        if depth < MAX_DEPTH && max_deviation > tolerance {
+            let tm = (t0 + t1) / 2f32;
+            let pm = curve.get_point(tm);
+            subdivide(curve, tolerance, (t0, p0), (tm, pm), depth + 1, parameters);
+            subdivide(curve, tolerance, (tm, pm), (t1, p1), depth + 1, parameters);
+        } else {
+            parameters.push(t1);
+        }
+    }
+
+    let mut parameters = vec![0f32];
+    let start = (0f32, curve.get_point(0f32));
+    let end = (1f32, curve.get_point(1f32));
+    subdivide(curve, tolerance, start, end, 0, &mut parameters);
+    parameters
+}
+
+/// Gets a table of parameters along the curve, each with the length of the curve
+/// up to that parameter. The parameters are evenly spaced, since the points of a
+/// flattened curve can be far apart where it's straight, even where its speed changes.
+fn arc_length_table<C: Curve + ?Sized>(curve: &C) -> Vec<(f32, f32)> {
+    let samples = approximate_segment_count(curve.approximate_length()) * 8;
+
+    let mut table = Vec::with_capacity(samples as usize + 1);
+    let mut previous = curve.get_point(0f32);
+    let mut length = 0f32;
+    table.push((0f32, 0f32));
+    for i in 1..=samples {
+        let t = (i as f32) / (samples as f32);
+        let point = curve.get_point(t);
+        length += previous.distance(point);
+        table.push((t, length));
+        previous = point;
+    }
+    table
+}
+
+/// The distance from `p` to the closest point on the line segment from `a` to `b`.
+fn helper_distance_to_segment(p: Point2, a: Point2, b: Point2) -> f32 {
+    let segment = b - a;
+    let length_squared = segment.dot(&segment);
+    if length_squared <= f32::EPSILON {
+        return p.distance(a);
+    }
+
+    let t = ((p - a).dot(&segment) / length_squared).clamp(0f32, 1f32);
+    p.distance(a + segment * t)
+}
+
+/// Tells how many line segments should be used to approximate
+/// a curve of the given length.
+///
+/// We are going to use a hyperbola so we get a somewhat linear
+/// approximation for the amount of segments needed while having
+/// the count be high for low numbers. The particular hyperbola
+/// we'll be using is `sqrt(x * x + 100), for x >= 0`.
+fn approximate_segment_count(length: f32) -> u64 {
+    (((length * length) + 100f32).sqrt() + 1f32) as u64
+}
+
+/// Gets how many straight pieces an arc of the given length and radius needs for
+/// each piece to stray at most `tolerance` from the arc, but never less pieces
+/// than would be used for drawing the arc.
+fn flattened_segment_count(length: f32, radius: f32, tolerance: f32) -> u64 {
+    let drawn = approximate_segment_count(length);
+    if tolerance <= 0f32 || tolerance >= radius {
+        return drawn;
+    }
+
+    // A piece covering the angle `a` strays `radius * (1 - cos(a / 2))` from the arc.
+    let max_angle = 2f32 * (1f32 - tolerance / radius).acos();
+    let needed = (length / radius / max_angle).ceil() as u64;
+    drawn.max(needed)
+}
+
+/// Gets the smallest axis-aligned box containing all of the points.
+fn bounding_box_of<I: IntoIterator<Item = Point2>>(points: I) -> (Point2, Point2) {
+    points
+        .into_iter()
+        .map(|p| (p, p))
+        .fold(empty_bounding_box(), union_bounding_boxes)
+}
+
+/// A box which contains nothing, so that growing it to contain something
+/// gives just the box of that something.
+pub(crate) fn empty_bounding_box() -> (Point2, Point2) {
+    (
+        Point2::new(f32::INFINITY, f32::INFINITY),
+        Point2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+    )
+}
+
+pub(crate) fn union_bounding_boxes(a: (Point2, Point2), b: (Point2, Point2)) -> (Point2, Point2) {
+    let ((a_min, a_max), (b_min, b_max)) = (a, b);
+    (a_min.min(b_min), a_max.max(b_max))
+}
+
+/// Gets the real solutions of `a * t^2 + b * t + c = 0`.
+fn quadratic_roots(a: f32, b: f32, c: f32) -> Vec<f32> {
+    if a.abs() <= f32::EPSILON {
+        if b == 0f32 {
+            return Vec::new();
+        }
+        return vec![-c / b];
+    }
+
+    let discriminant = b * b - 4f32 * a * c;
+    if discriminant < 0f32 {
+        return Vec::new();
+    }
+    let root = discriminant.sqrt();
+    vec![(-b - root) / (2f32 * a), (-b + root) / (2f32 * a)]
+}
+
+fn square(x: f32) -> f32 {
+    x * x
+}
+
+fn cube(x: f32) -> f32 {
+    x * x * x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::{color_rgb8, color_rgba8};
+
+    /// A canvas with a black background which draws in white.
+    fn black_canvas(width: u64, height: u64) -> Canvas {
+        let mut canvas = Canvas::new(width, height, color_rgb8(0, 0, 0));
+        canvas.set_draw_color(color_rgb8(255, 255, 255));
+        canvas
+    }
+
+    fn red(canvas: &Canvas, x: u64, y: u64) -> f32 {
+        canvas.get_pixel(x, y).unwrap().r
+    }
+
+    fn p(x: f32, y: f32) -> Point2 {
+        Point2::new(x, y)
+    }
+
+    /// Draws the pixels of a row as `#` where they are mostly covered and `.` elsewhere.
+    fn row(canvas: &Canvas, y: u64) -> String {
+        (0..canvas.width())
+            .map(|x| if red(canvas, x, y) > 0.5 { '#' } else { '.' })
+            .collect()
+    }
+
+    #[test]
+    fn multi_paths() {
+        let mut canvas = black_canvas(40, 40);
+        let mut path = OpenMultiPath::new(vec![Box::new(Line::new(p(5f32, 5f32), p(20f32, 5f32)))]);
+        path.push(Box::new(QuadBezierCurve::new(
+            p(20f32, 5f32),
+            p(35f32, 5f32),
+            p(35f32, 20f32),
+        )));
+        path.stroke(&mut canvas, 2f32);
+        assert!(red(&canvas, 12, 5) > 0.9);
+        assert!(red(&canvas, 34, 19) > 0.4);
+        assert_eq!(red(&canvas, 20, 20), 0f32);
+
+        let mut canvas = black_canvas(40, 40);
+        let path = ClosedMultiPath::new(vec![
+            Box::new(Line::new(p(5f32, 5f32), p(35f32, 5f32))),
+            Box::new(Line::new(p(35f32, 5f32), p(35f32, 35f32))),
+            Box::new(QuadBezierCurve::new(
+                p(35f32, 35f32),
+                p(20f32, 40f32),
+                p(5f32, 35f32),
+            )),
+        ]);
+        path.fill(&mut canvas);
+        assert_eq!(red(&canvas, 20, 20), 1f32);
+        assert_eq!(red(&canvas, 20, 36), 1f32);
+        assert_eq!(red(&canvas, 2, 20), 0f32);
+    }
+
+    #[test]
+    fn cubic_points() {
+        let curve = CubicBezierCurve::new(
+            p(0f32, 0f32),
+            p(0f32, 12f32),
+            p(12f32, 12f32),
+            p(12f32, 0f32),
+        );
+        // (1 - t)^3 p0 + 3 (1 - t)^2 t c1 + 3 (1 - t) t^2 c2 + t^3 p1, worked out by hand.
+        let expected = [
+            (0f32, p(0f32, 0f32)),
+            (0.25, p(1.875, 6.75)),
+            (0.5, p(6f32, 9f32)),
+            (0.75, p(10.125, 6.75)),
+            (1f32, p(12f32, 0f32)),
+        ];
+        for &(t, point) in expected.iter() {
+            assert!(curve.get_point(t).distance(point) < 1e-5, "at {}", t);
+        }
+        assert_eq!(curve.derivative(0.5), [18f32, 0f32]);
+
+        // The tangent vanishes at the ends of this one, which mustn't give NaNs.
+        let mut canvas = black_canvas(20, 20);
+        let straight = CubicBezierCurve::new(
+            p(0f32, 0f32),
+            p(0f32, 0f32),
+            p(10f32, 10f32),
+            p(10f32, 10f32),
+        );
+        straight.stroke(&mut canvas, 2f32);
+        for y in 0..20 {
+            for x in 0..20 {
+                assert!(!red(&canvas, x, y).is_nan());
+            }
+        }
+        assert!(red(&canvas, 5, 5) > 0.5);
+    }
+
+    #[test]
+    fn line_caps() {
+        let caps = [
+            (LineCap::Butt, false),
+            (LineCap::Round, true),
+            (LineCap::Square, true),
+        ];
+        for &(cap, past_end) in caps.iter() {
+            let mut canvas = black_canvas(40, 40);
+            canvas.set_line_cap(cap);
+            Line::new(p(10f32, 20f32), p(30f32, 20f32)).stroke(&mut canvas, 8f32);
+            assert!(red(&canvas, 20, 20) > 0.9, "{:?}", cap);
+            assert_eq!(red(&canvas, 7, 20) > 0.5, past_end, "{:?}", cap);
+            assert_eq!(red(&canvas, 32, 20) > 0.5, past_end, "{:?}", cap);
+            // Only square caps reach the corners past the ends.
+            let corner = cap == LineCap::Square;
+            assert_eq!(red(&canvas, 6, 16) > 0.5, corner, "{:?}", cap);
+        }
+    }
+
+    #[test]
+    fn line_joins() {
+        let joins = [
+            (LineJoin::Miter, true),
+            (LineJoin::Round, false),
+            (LineJoin::Bevel, false),
+        ];
+        // The caps only go on the ends of the path, so they don't change the corner.
+        let caps = [LineCap::Butt, LineCap::Round, LineCap::Square];
+        for &(join, sharp) in joins.iter() {
+            for &cap in caps.iter() {
+                let mut canvas = black_canvas(40, 40);
+                canvas.set_line_join(join);
+                canvas.set_line_cap(cap);
+                let path = OpenMultiPath::new(vec![
+                    Box::new(Line::new(p(5f32, 20f32), p(20f32, 20f32))),
+                    Box::new(Line::new(p(20f32, 20f32), p(20f32, 35f32))),
+                ]);
+                path.stroke(&mut canvas, 8f32);
+                // Every join fills the corner right next to the joint, but only a
+                // miter reaches all the way out to where the outer edges meet.
+                assert!(red(&canvas, 21, 18) > 0.9, "{:?} {:?}", join, cap);
+                assert_eq!(red(&canvas, 23, 16) > 0.9, sharp, "{:?} {:?}", join, cap);
+            }
+        }
+
+        // Past the miter limit, the join gets beveled.
+        let mut canvas = black_canvas(40, 40);
+        canvas.set_miter_limit(1f32);
+        let path = OpenMultiPath::new(vec![
+            Box::new(Line::new(p(5f32, 20f32), p(20f32, 20f32))),
+            Box::new(Line::new(p(20f32, 20f32), p(20f32, 35f32))),
+        ]);
+        path.stroke(&mut canvas, 8f32);
+        assert!(red(&canvas, 21, 18) > 0.9);
+        assert!(red(&canvas, 23, 16) < 0.1);
+    }
+
+    #[test]
+    fn ellipses() {
+        let mut canvas = black_canvas(60, 40);
+        Ellipse::new(p(30f32, 20f32), 20f32, 5f32).fill(&mut canvas);
+        // The pixel just inside of the end of the major axis is covered, but not past it.
+        assert!(red(&canvas, 49, 20) > 0.4);
+        assert!(red(&canvas, 47, 20) > 0.9);
+        assert_eq!(red(&canvas, 51, 20), 0f32);
+        assert_eq!(red(&canvas, 30, 26), 0f32);
+
+        let mut canvas = black_canvas(60, 40);
+        Ellipse::new(p(30f32, 20f32), 20f32, 10f32).stroke(&mut canvas, 2f32);
+        assert_eq!(red(&canvas, 30, 20), 0f32);
+        assert!(red(&canvas, 50, 20) > 0.4);
+        assert!(red(&canvas, 30, 10) > 0.4);
+
+        // Without any height, an ellipse is a line along its width.
+        let mut canvas = black_canvas(60, 40);
+        let flat = Ellipse::new(p(30f32, 20f32), 10f32, 0f32);
+        flat.stroke(&mut canvas, 2f32);
+        assert!(red(&canvas, 30, 20) > 0.4);
+        flat.fill(&mut canvas);
+        assert_eq!(red(&canvas, 30, 22), 0f32);
+    }
+
+    #[test]
+    fn arcs_and_pies() {
+        let degrees = Angle::from_degrees;
+        // With the y-axis pointing down, the angles grow clockwise.
+        let mut canvas = black_canvas(40, 40);
+        Arc::new(p(20f32, 20f32), 10f32, degrees(0f32), degrees(90f32)).stroke(&mut canvas, 2f32);
+        assert!(red(&canvas, 20, 30) > 0.3);
+        assert!(red(&canvas, 27, 27) > 0.3);
+        assert_eq!(red(&canvas, 20, 9), 0f32);
+        assert_eq!(red(&canvas, 9, 20), 0f32);
+        assert_eq!(red(&canvas, 12, 12), 0f32);
+
+        // Ending before the start wraps around the long way.
+        let mut canvas = black_canvas(40, 40);
+        Arc::new(p(20f32, 20f32), 10f32, degrees(90f32), degrees(0f32)).stroke(&mut canvas, 2f32);
+        assert_eq!(red(&canvas, 27, 27), 0f32);
+        assert!(red(&canvas, 20, 10) > 0.3);
+        assert!(red(&canvas, 10, 20) > 0.3);
+
+        let mut canvas = black_canvas(40, 40);
+        Pie::new(p(20f32, 20f32), 10f32, degrees(270f32), degrees(90f32)).fill(&mut canvas);
+        assert!(red(&canvas, 25, 20) > 0.9);
+        assert!(red(&canvas, 20, 25) > 0.9);
+        assert_eq!(red(&canvas, 15, 20), 0f32);
+        assert_eq!(red(&canvas, 31, 20), 0f32);
+    }
+
+    #[test]
+    fn polygons_and_polylines() {
+        let mut canvas = black_canvas(40, 40);
+        // Too few points to draw anything.
+        Polyline::new(vec![]).stroke(&mut canvas, 2f32);
+        Polyline::new(vec![p(1f32, 1f32)]).stroke(&mut canvas, 2f32);
+        Polygon::new(vec![]).stroke(&mut canvas, 2f32);
+        Polygon::new(vec![]).fill(&mut canvas);
+        Polygon::new(vec![p(1f32, 1f32)]).fill(&mut canvas);
+        Polygon::new(vec![p(1f32, 1f32), p(9f32, 9f32)]).fill(&mut canvas);
+        assert!((0..40).all(|y| (0..40).all(|x| red(&canvas, x, y) == 0f32)));
+
+        let triangle = Polygon::new(vec![p(5f32, 5f32), p(30f32, 5f32), p(5f32, 30f32)]);
+        triangle.fill(&mut canvas);
+        assert_eq!(red(&canvas, 8, 8), 1f32);
+        assert_eq!(red(&canvas, 25, 25), 0f32);
+
+        // The outline of a polygon is closed, unlike a polyline through the same points.
+        let points = vec![p(5f32, 5f32), p(30f32, 5f32), p(5f32, 30f32)];
+        let mut canvas = black_canvas(40, 40);
+        Polyline::new(points.clone()).stroke(&mut canvas, 2f32);
+        assert!(red(&canvas, 17, 17) > 0.4);
+        assert_eq!(red(&canvas, 5, 17), 0f32);
+        Polygon::new(points).stroke(&mut canvas, 2f32);
+        assert!(red(&canvas, 5, 17) > 0.4);
+    }
+
+    #[test]
+    fn zero_length_line() {
+        let mut canvas = black_canvas(20, 20);
+        Line::new(p(10f32, 10f32), p(10f32, 10f32)).stroke(&mut canvas, 4f32);
+        canvas.set_line_cap(LineCap::Square);
+        Line::new(p(10f32, 10f32), p(10f32, 10f32)).stroke(&mut canvas, 4f32);
+        for y in 0..20 {
+            for x in 0..20 {
+                assert!(!red(&canvas, x, y).is_nan());
+            }
+        }
+    }
+
+    #[test]
+    fn stroke_with_style() {
+        let mut canvas = black_canvas(60, 20);
+        let style = StrokeStyle {
+            width: 4f32,
+            cap: LineCap::Round,
+            dash: Some(vec![10f32, 10f32]),
+            ..Default::default()
+        };
+        Line::new(p(10f32, 10f32), p(50f32, 10f32)).stroke_with(&mut canvas, &style);
+        // Every dash gets rounded off by two pixels at both of its ends.
+        let expected = "........##############......##############..................";
+        assert_eq!(row(&canvas, 10), expected);
+        assert_eq!(canvas.line_cap, LineCap::Butt);
+        assert_eq!(canvas.dash, None);
+
+        // The default style strokes just like `stroke` does.
+        let mut plain = black_canvas(60, 20);
+        let style = StrokeStyle {
+            width: 4f32,
+            ..Default::default()
+        };
+        Line::new(p(10f32, 10f32), p(50f32, 10f32)).stroke_with(&mut plain, &style);
+        let mut expected = black_canvas(60, 20);
+        Line::new(p(10f32, 10f32), p(50f32, 10f32)).stroke(&mut expected, 4f32);
+        assert_eq!(row(&plain, 10), row(&expected, 10));
+        assert_eq!(row(&plain, 8), row(&expected, 8));
+    }
+
+    #[test]
+    fn dashed_lines() {
+        let mut canvas = black_canvas(60, 20);
+        canvas.set_dash(Some(vec![10f32, 10f32]));
+        Line::new(p(0f32, 10f32), p(60f32, 10f32)).stroke(&mut canvas, 4f32);
+        let expected = "##########..........##########..........##########..........";
+        assert_eq!(row(&canvas, 10), expected);
+
+        // The dashes carry on from one part of a path to the next.
+        let mut canvas = black_canvas(60, 20);
+        canvas.set_dash(Some(vec![10f32, 10f32]));
+        let path = OpenMultiPath::new(vec![
+            Box::new(Line::new(p(0f32, 10f32), p(25f32, 10f32))),
+            Box::new(Line::new(p(25f32, 10f32), p(60f32, 10f32))),
+        ]);
+        path.stroke(&mut canvas, 4f32);
+        assert_eq!(row(&canvas, 10), expected);
+
+        // A pattern without any length draws a solid line.
+        let mut canvas = black_canvas(60, 20);
+        canvas.set_dash(Some(vec![0f32, 0f32]));
+        Line::new(p(0f32, 10f32), p(60f32, 10f32)).stroke(&mut canvas, 4f32);
+        assert!(row(&canvas, 10).chars().all(|pixel| pixel == '#'));
+    }
+
+    #[test]
+    fn catmull_rom_through_points() {
+        let points = vec![
+            p(0f32, 0f32),
+            p(10f32, 5f32),
+            p(20f32, -3f32),
+            p(30f32, 8f32),
+        ];
+        let spline = CatmullRomSpline::new(points.clone());
+        for (i, &point) in points.iter().enumerate() {
+            let t = i as f32 / 3f32;
+            assert!(spline.get_point(t).distance(point) < 1e-4, "at {}", t);
+        }
+        assert!(spline.approximate_length() > 30f32);
+
+        // Too few points to draw a curve through.
+        let mut canvas = black_canvas(40, 40);
+        CatmullRomSpline::new(vec![]).stroke(&mut canvas, 2f32);
+        CatmullRomSpline::new(vec![p(1f32, 1f32)]).stroke(&mut canvas, 2f32);
+        assert!((0..40).all(|y| (0..40).all(|x| red(&canvas, x, y) == 0f32)));
+    }
+
+    #[test]
+    fn flattening() {
+        let line = Line::new(p(0f32, 0f32), p(100f32, 50f32));
+        assert_eq!(line.flatten(0.0001), vec![p(0f32, 0f32), p(100f32, 50f32)]);
+        assert_eq!(line.flatten(10f32).len(), 2);
+
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(50f32, 100f32), p(100f32, 0f32));
+        assert!(quad.flatten(0.01).len() > quad.flatten(2f32).len());
+        let cubic = CubicBezierCurve::new(
+            p(0f32, 0f32),
+            p(0f32, 50f32),
+            p(100f32, -50f32),
+            p(100f32, 0f32),
+        );
+        let points = cubic.flatten(0.5);
+        assert!(points.len() > 3);
+        assert_eq!(points.first(), Some(&p(0f32, 0f32)));
+        assert_eq!(points.last(), Some(&p(100f32, 0f32)));
+    }
+
+    #[test]
+    fn adaptive_subdivision() {
+        // The old heuristic went by the length of the control polygon, giving
+        // the long curve more pieces even though it is nearly straight.
+        let sharp = QuadBezierCurve::new(p(0f32, 0f32), p(5f32, 40f32), p(10f32, 0f32));
+        let flat = QuadBezierCurve::new(p(0f32, 0f32), p(500f32, 1f32), p(1000f32, 0f32));
+        assert!(sharp.flatten(0.1).len() > flat.flatten(0.1).len());
+
+        let mut canvas = black_canvas(20, 20);
+        canvas.set_flattening_tolerance(-1f32);
+        canvas.set_flattening_tolerance(0f32);
+        assert_eq!(canvas.flattening_tolerance, 0.1);
+        canvas.set_flattening_tolerance(2f32);
+        assert_eq!(canvas.flattening_tolerance, 2f32);
+    }
+
+    #[test]
+    fn path_builder() {
+        let mut canvas = black_canvas(30, 30);
+        let triangle = PathBuilder::new()
+            .move_to(p(2f32, 2f32))
+            .line_to(p(28f32, 2f32))
+            .line_to(p(15f32, 28f32))
+            .close()
+            .build_closed();
+        triangle.fill(&mut canvas);
+        assert_eq!(red(&canvas, 15, 10), 1f32);
+        assert_eq!(red(&canvas, 1, 28), 0f32);
+        assert_eq!(red(&canvas, 28, 28), 0f32);
+
+        let open = PathBuilder::new()
+            .move_to(p(0f32, 0f32))
+            .quad_to(p(10f32, 10f32), p(20f32, 0f32))
+            .cubic_to(p(21f32, 1f32), p(22f32, 2f32), p(23f32, 3f32))
+            .close()
+            .build();
+        assert_eq!(open.parts.len(), 3);
+        assert_eq!(open.bounding_box(), (p(0f32, 0f32), p(23f32, 5f32)));
+    }
+
+    #[test]
+    fn rounded_rectangles() {
+        // The radius gets clamped to half of the side, which makes this a circle.
+        let mut canvas = black_canvas(30, 30);
+        RoundedRect::new(p(25f32, 25f32), p(5f32, 5f32), 100f32).fill(&mut canvas);
+        for &(x, y) in [(5, 5), (24, 5), (5, 24), (24, 24)].iter() {
+            assert_eq!(red(&canvas, x, y), 0f32, "at {}, {}", x, y);
+        }
+        for &(x, y) in [(15, 5), (5, 15), (24, 15), (15, 24), (15, 15)].iter() {
+            assert!(red(&canvas, x, y) > 0.4, "at {}, {}", x, y);
+        }
+
+        let mut canvas = black_canvas(30, 30);
+        RoundedRect::new(p(5f32, 5f32), p(25f32, 25f32), 4f32).stroke(&mut canvas, 2f32);
+        assert_eq!(red(&canvas, 15, 15), 0f32);
+        assert_eq!(red(&canvas, 15, 4), 1f32);
+        assert_eq!(red(&canvas, 4, 4), 0f32);
+    }
+
+    #[test]
+    fn star_fill_rules() {
+        use std::f32::consts::{FRAC_PI_2, PI};
+
+        let star: Vec<Point2> = (0..5)
+            .map(|i| {
+                let angle = i as f32 * 4f32 * PI / 5f32 - FRAC_PI_2;
+                p(20f32 + 18f32 * angle.cos(), 20f32 + 18f32 * angle.sin())
+            })
+            .collect();
+        let mut builder = PathBuilder::new().move_to(star[0]);
+        for &point in star[1..].iter() {
+            builder = builder.line_to(point);
+        }
+        let path = builder.close().build_closed();
+        let shapes: [&dyn Loop; 2] = [&Polygon::new(star.clone()), &path];
+
+        for shape in shapes.iter() {
+            let mut canvas = black_canvas(40, 40);
+            shape.fill(&mut canvas);
+            // The points are covered once and the pentagon in the middle twice.
+            assert_eq!(red(&canvas, 20, 5), 1f32);
+            assert_eq!(red(&canvas, 20, 20), 0f32);
+
+            canvas.set_fill_rule(FillRule::NonZero);
+            shape.fill(&mut canvas);
+            assert_eq!(red(&canvas, 20, 20), 1f32);
+        }
+    }
+
+    #[test]
+    fn sharp_quad_stroke_has_no_holes() {
+        let mut canvas = black_canvas(80, 80);
+        canvas.set_line_cap(LineCap::Round);
+        let curve = QuadBezierCurve::new(p(30f32, 70f32), p(40f32, -60f32), p(50f32, 70f32));
+        curve.stroke(&mut canvas, 16f32);
+
+        // Everything well within half of the width from the curve is covered,
+        // and nothing well past it is.
+        let points = curve.flatten(0.01);
+        for y in 0..80 {
+            for x in 0..80 {
+                let center = p(x as f32 + 0.5, y as f32 + 0.5);
+                let distance = points
+                    .iter()
+                    .map(|&point| center.distance(point))
+                    .fold(f32::INFINITY, f32::min);
+                if distance < 7f32 {
+                    assert!(red(&canvas, x, y) > 0.999, "hole at {}, {}", x, y);
+                } else if distance > 9.5 {
+                    assert!(red(&canvas, x, y) < 0.001, "spill at {}, {}", x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rectangles() {
+        let square = Rectangle::new(p(10f32, 10f32), p(30f32, 30f32));
+        let mut canvas = black_canvas(40, 40);
+        square.fill(&mut canvas);
+        assert_eq!(red(&canvas, 20, 20), 1f32);
+        assert_eq!(red(&canvas, 10, 10), 1f32);
+        assert_eq!(red(&canvas, 29, 29), 1f32);
+        assert_eq!(red(&canvas, 30, 20), 0f32);
+
+        // Turned by 45 degrees, the corners stick out of the original square.
+        let mut canvas = black_canvas(40, 40);
+        let diamond = Rectangle::new(p(10f32, 10f32), p(30f32, 30f32))
+            .rotated(p(20f32, 20f32), Angle::from_degrees(45f32));
+        diamond.fill(&mut canvas);
+        assert_eq!(red(&canvas, 20, 8), 1f32);
+        assert_eq!(red(&canvas, 31, 20), 1f32);
+        assert_eq!(red(&canvas, 11, 11), 0f32);
+        let (min, max) = diamond.bounding_box();
+        assert!(min.get_x() < 10f32 && max.get_y() > 30f32);
+
+        let mut canvas = black_canvas(40, 40);
+        square.stroke(&mut canvas, 2f32);
+        assert_eq!(red(&canvas, 9, 9), 1f32);
+        assert_eq!(red(&canvas, 20, 20), 0f32);
+    }
+
+    #[test]
+    fn winding_order() {
+        // Clockwise on the canvas, where the y-axis points down.
+        let clockwise = vec![p(0f32, 0f32), p(1f32, 0f32), p(1f32, 1f32), p(0f32, 1f32)];
+        let mut counter_clockwise = clockwise.clone();
+        counter_clockwise.reverse();
+        assert_eq!(helper_signed_area(&clockwise[..]), 1f32);
+        assert_eq!(helper_signed_area(&counter_clockwise[..]), -1f32);
+        assert_eq!(helper_signed_area(&[]), 0f32);
+
+        assert!(Polygon::new(clockwise.clone()).is_clockwise());
+        assert!(!Polygon::new(counter_clockwise).is_clockwise());
+        let mut polygon = Polygon::new(clockwise);
+        polygon.to_counter_clockwise();
+        assert!(!polygon.is_clockwise());
+    }
+
+    #[test]
+    fn polygon_with_hole() {
+        let outer = vec![
+            p(5f32, 5f32),
+            p(35f32, 5f32),
+            p(35f32, 35f32),
+            p(5f32, 35f32),
+        ];
+        let hole = vec![
+            p(15f32, 15f32),
+            p(25f32, 15f32),
+            p(25f32, 25f32),
+            p(15f32, 25f32),
+        ];
+        for &rule in [FillRule::EvenOdd, FillRule::NonZero].iter() {
+            let mut canvas = black_canvas(40, 40);
+            canvas.set_fill_rule(rule);
+            Polygon::with_holes(outer.clone(), vec![hole.clone()]).fill(&mut canvas);
+            assert_eq!(red(&canvas, 20, 20), 0f32, "{:?}", rule);
+            assert_eq!(red(&canvas, 10, 10), 1f32, "{:?}", rule);
+            assert_eq!(red(&canvas, 30, 20), 1f32, "{:?}", rule);
+            assert_eq!(red(&canvas, 2, 2), 0f32, "{:?}", rule);
+        }
+    }
+
+    #[test]
+    fn stroke_alignment() {
+        let round_shapes: [&dyn Path; 2] = [
+            &Circle::new(p(20f32, 20f32), 10f32),
+            &Ellipse::new(p(20f32, 20f32), 10f32, 10f32),
+        ];
+        for shape in round_shapes.iter() {
+            for &align in &[StrokeAlign::Inside, StrokeAlign::Outside] {
+                let mut canvas = black_canvas(40, 40);
+                canvas.set_stroke_align(align);
+                shape.stroke(&mut canvas, 4f32);
+                let mut stroked = 0;
+                for y in 0..40 {
+                    for x in 0..40 {
+                        if red(&canvas, x, y) == 0f32 {
+                            continue;
+                        }
+                        stroked += 1;
+                        let distance =
+                            (p(x as f32 + 0.5, y as f32 + 0.5) - p(20f32, 20f32)).length();
+                        match align {
+                            StrokeAlign::Inside => assert!(distance <= 10.75),
+                            _ => assert!(distance >= 9.25),
+                        }
+                    }
+                }
+                assert!(stroked > 0);
+            }
+        }
+
+        let square = Rectangle::new(p(10f32, 10f32), p(30f32, 30f32));
+        let mut canvas = black_canvas(40, 40);
+        canvas.set_stroke_align(StrokeAlign::Inside);
+        square.stroke(&mut canvas, 4f32);
+        assert_eq!(&row(&canvas, 20)[8..16], "..####..");
+        let mut canvas = black_canvas(40, 40);
+        canvas.set_stroke_align(StrokeAlign::Outside);
+        square.stroke(&mut canvas, 4f32);
+        assert_eq!(&row(&canvas, 20)[4..12], "..####..");
+        assert_eq!(red(&canvas, 6, 6), 1f32);
+    }
+
+    #[test]
+    fn loops_contain_points() {
+        let circle = Circle::new(p(10f32, 10f32), 5f32);
+        assert!(circle.contains(p(10f32, 10f32)));
+        assert!(!circle.contains(p(16f32, 10f32)));
+
+        let notch = Polygon::new(vec![
+            p(0f32, 0f32),
+            p(10f32, 0f32),
+            p(10f32, 10f32),
+            p(5f32, 3f32),
+            p(0f32, 10f32),
+        ]);
+        assert!(notch.contains(p(5f32, 1f32)));
+        assert!(!notch.contains(p(5f32, 8f32)));
+        assert!(notch.contains(p(9f32, 8f32)));
+
+        let holed = Polygon::with_holes(
+            vec![
+                p(0f32, 0f32),
+                p(10f32, 0f32),
+                p(10f32, 10f32),
+                p(0f32, 10f32),
+            ],
+            vec![vec![
+                p(3f32, 3f32),
+                p(7f32, 3f32),
+                p(7f32, 7f32),
+                p(3f32, 7f32),
+            ]],
+        );
+        assert!(!holed.contains(p(5f32, 5f32)));
+        assert!(holed.contains(p(1f32, 5f32)));
+
+        let quarter = Angle::from_radians(std::f32::consts::FRAC_PI_2);
+        let pie = Pie::new(p(10f32, 10f32), 5f32, Angle::from_radians(0f32), quarter);
+        assert!(pie.contains(p(12f32, 12f32)));
+        assert!(!pie.contains(p(8f32, 12f32)));
+
+        let ellipse = Ellipse::new(p(0f32, 0f32), 4f32, 2f32);
+        assert!(ellipse.contains(p(3.5f32, 0f32)));
+        assert!(!ellipse.contains(p(0f32, 2.5f32)));
+        let rounded = RoundedRect::new(p(0f32, 0f32), p(10f32, 10f32), 3f32);
+        assert!(rounded.contains(p(5f32, 5f32)));
+        assert!(!rounded.contains(p(0.2f32, 0.2f32)));
+
+        let path = PathBuilder::new()
+            .move_to(p(0f32, 0f32))
+            .line_to(p(10f32, 0f32))
+            .quad_to(p(10f32, 10f32), p(0f32, 10f32))
+            .close()
+            .build_closed();
+        assert!(path.contains(p(5f32, 5f32)));
+        assert!(!path.contains(p(11f32, 5f32)));
+    }
+
+    #[test]
+    fn arc_length_spacing() {
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(90f32, 0f32), p(100f32, 100f32));
+        let total = quad.arc_length();
+        assert!(total > 141f32 && total < 200f32);
+        let points: Vec<Point2> = (0..=10)
+            .map(|i| quad.point_at_arc_length(total * i as f32 / 10f32))
+            .collect();
+        let chords: Vec<f32> = points.windows(2).map(|w| w[0].distance(w[1])).collect();
+        let shortest = chords.iter().cloned().fold(f32::MAX, f32::min);
+        let longest = chords.iter().cloned().fold(0f32, f32::max);
+        assert!(longest - shortest < 0.2);
+
+        // Distances off either end stay at the end points.
+        assert_eq!(quad.point_at_arc_length(-5f32), p(0f32, 0f32));
+        assert!(quad.point_at_arc_length(1e6).distance(p(100f32, 100f32)) < 1e-3);
+
+        let straight = QuadBezierCurve::new(p(0f32, 0f32), p(9f32, 0f32), p(10f32, 0f32));
+        assert!(straight.point_at_arc_length(5f32).distance(p(5f32, 0f32)) < 0.05);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn line_json_round_trip() {
+        let line = Line::new(p(1f32, 2f32), p(3.5f32, 4f32));
+        let json = serde_json::to_string(&line).unwrap();
+        assert_eq!(json, r#"{"p0":[1.0,2.0],"p1":[3.5,4.0]}"#);
+        let parsed: Line = serde_json::from_str(&json).unwrap();
+        assert_eq!((parsed.p0, parsed.p1), (line.p0, line.p1));
+    }
+
+    #[test]
+    fn bounding_boxes() {
+        let near = |a: Point2, b: Point2| a.distance(b) < 1e-3;
+        let (min, max) = Circle::new(p(10f32, 20f32), 5f32).bounding_box();
+        assert!(near(min, p(5f32, 15f32)) && near(max, p(15f32, 25f32)));
+
+        // The control points of the curves lie outside of their boxes.
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(5f32, 10f32), p(10f32, 0f32));
+        let (min, max) = quad.bounding_box();
+        assert!(near(min, p(0f32, 0f32)) && near(max, p(10f32, 5f32)));
+        let cubic = CubicBezierCurve::new(
+            p(0f32, 0f32),
+            p(0f32, 10f32),
+            p(10f32, 10f32),
+            p(10f32, 0f32),
+        );
+        let (min, max) = cubic.bounding_box();
+        assert!(near(min, p(0f32, 0f32)) && near(max, p(10f32, 7.5f32)));
+
+        let half = Angle::from_radians(std::f32::consts::PI);
+        let (min, max) =
+            Arc::new(p(0f32, 0f32), 2f32, Angle::from_radians(0f32), half).bounding_box();
+        assert!(near(min, p(-2f32, 0f32)) && near(max, p(2f32, 2f32)));
+        let (min, max) = Line::new(p(3f32, 1f32), p(1f32, 4f32)).stroke_bounding_box(2f32);
+        assert!(near(min, p(0f32, 0f32)) && near(max, p(4f32, 5f32)));
+        let upright = Ellipse::new(p(0f32, 0f32), 4f32, 2f32)
+            .with_rotation(Angle::from_radians(std::f32::consts::FRAC_PI_2));
+        let (min, max) = upright.bounding_box();
+        assert!(near(min, p(-2f32, -4f32)) && near(max, p(2f32, 4f32)));
+
+        let path = PathBuilder::new()
+            .move_to(p(0f32, 0f32))
+            .line_to(p(4f32, 0f32))
+            .quad_to(p(8f32, 4f32), p(4f32, 4f32))
+            .build();
+        let (min, max) = path.bounding_box();
+        assert!(near(min, p(0f32, 0f32)) && near(max, p(6f32, 4f32)));
+    }
+
+    #[test]
+    fn dashed_circles_and_ellipses() {
+        // Counts the dashes met when walking around the outline.
+        let dashes = |canvas: &Canvas, radius_x: f32, radius_y: f32| {
+            let n = 720;
+            let on: Vec<bool> = (0..n)
+                .map(|i| {
+                    let t = 2f32 * std::f32::consts::PI * (i as f32 + 0.5) / n as f32;
+                    let (x, y) = (50f32 + radius_x * t.cos(), 50f32 + radius_y * t.sin());
+                    red(canvas, x as u64, y as u64) > 0.5
+                })
+                .collect();
+            (0..n).filter(|&i| on[i] && !on[(i + n - 1) % n]).count()
+        };
+        let perimeter = 2f32 * std::f32::consts::PI * 30f32;
+
+        let mut canvas = black_canvas(100, 100);
+        canvas.set_dash(Some(vec![perimeter / 16f32, perimeter / 16f32]));
+        Circle::new(p(50f32, 50f32), 30f32).stroke(&mut canvas, 3f32);
+        assert_eq!(dashes(&canvas, 30f32, 30f32), 8);
+        // The first dash starts at the angle 0.
+        assert!(red(&canvas, 80, 52) > 0.5);
+        assert!(red(&canvas, 80, 47) < 0.5);
+
+        // The last dash runs over the seam into the first one.
+        let mut canvas = black_canvas(100, 100);
+        canvas.set_dash(Some(vec![0.28 * perimeter, 0.12 * perimeter]));
+        Circle::new(p(50f32, 50f32), 30f32).stroke(&mut canvas, 3f32);
+        assert_eq!(dashes(&canvas, 30f32, 30f32), 2);
+        assert!(red(&canvas, 80, 50) > 0.5);
+
+        let mut canvas = black_canvas(100, 100);
+        canvas.set_dash(Some(vec![5f32, 5f32]));
+        Ellipse::new(p(50f32, 50f32), 40f32, 20f32).stroke(&mut canvas, 3f32);
+        assert!((19..=20).contains(&dashes(&canvas, 40f32, 20f32)));
+    }
+
+    #[test]
+    fn open_and_closed_multi_paths() {
+        let parts = || -> Vec<Box<dyn Curve>> {
+            vec![
+                Box::new(CubicBezierCurve::new(
+                    p(10f32, 10f32),
+                    p(20f32, 5f32),
+                    p(30f32, 5f32),
+                    p(40f32, 10f32),
+                )),
+                Box::new(CubicBezierCurve::new(
+                    p(40f32, 10f32),
+                    p(45f32, 20f32),
+                    p(45f32, 30f32),
+                    p(40f32, 40f32),
+                )),
+                // This one ends a bit short of where the first one starts.
+                Box::new(CubicBezierCurve::new(
+                    p(40f32, 40f32),
+                    p(30f32, 45f32),
+                    p(15f32, 40f32),
+                    p(10f32, 39.5f32),
+                )),
+            ]
+        };
+
+        let mut filled = black_canvas(50, 50);
+        ClosedMultiPath::new(parts()).fill(&mut filled);
+        for &(x, y) in [(25, 25), (12, 25), (38, 20), (25, 12), (25, 38)].iter() {
+            assert!(red(&filled, x, y) > 0.99);
+        }
+        assert!(red(&filled, 5, 25) < 0.01);
+
+        // Only the closed path strokes the edge back to the start.
+        let mut open = black_canvas(50, 50);
+        OpenMultiPath::new(parts()).stroke(&mut open, 2f32);
+        assert!(red(&open, 10, 25) < 0.01);
+        assert!(red(&open, 43, 25) > 0.5);
+        let mut closed = black_canvas(50, 50);
+        ClosedMultiPath::new(parts()).stroke(&mut closed, 2f32);
+        assert!(red(&closed, 10, 25) > 0.5);
+    }
+
+    #[test]
+    fn fill_outlines() {
+        let mut canvas = black_canvas(40, 40);
+        let line = Line::new(p(0f32, 10f32), p(20f32, 10f32));
+        let outline = line.to_fill_outline(&canvas, 10f32).outline(0.1);
+        assert_eq!(outline.len(), 1);
+        let mut corners: Vec<(f32, f32)> =
+            outline[0].iter().map(|q| (q.get_x(), q.get_y())).collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert_eq!(
+            corners,
+            vec![(0f32, 5f32), (0f32, 15f32), (20f32, 5f32), (20f32, 15f32)]
+        );
+
+        // The caps of the canvas go on the ends, with round ones flattened like curves.
+        for &cap in [LineCap::Square, LineCap::Round].iter() {
+            canvas.set_line_cap(cap);
+            let (min, max) = line.to_fill_outline(&canvas, 10f32).bounding_box();
+            assert!(min.distance(p(-5f32, 5f32)) < 0.1, "{:?}", cap);
+            assert!(max.distance(p(25f32, 15f32)) < 0.1, "{:?}", cap);
+        }
+        canvas.set_line_cap(LineCap::Butt);
+
+        // And so do its joins, whether the corner is within a single path or between parts.
+        let corner = [p(0f32, 0f32), p(20f32, 0f32), p(20f32, 20f32)];
+        let polyline = Polyline::new(corner.to_vec());
+        let multi_path = PathBuilder::new()
+            .move_to(corner[0])
+            .line_to(corner[1])
+            .line_to(corner[2])
+            .build();
+        let joins = [
+            (LineJoin::Miter, true, true),
+            (LineJoin::Round, false, true),
+            (LineJoin::Bevel, false, false),
+        ];
+        for &(join, tip, rounding) in joins.iter() {
+            canvas.set_line_join(join);
+            for path in [&polyline as &dyn Path, &multi_path].iter() {
+                let outline = path.to_fill_outline(&canvas, 4f32);
+                assert_eq!(outline.contains(p(21.5f32, -1.5f32)), tip, "{:?}", join);
+                assert_eq!(
+                    outline.contains(p(21.2f32, -1.2f32)),
+                    rounding,
+                    "{:?}",
+                    join
+                );
+                assert!(outline.contains(p(10f32, 1f32)) && !outline.contains(p(10f32, 3f32)));
+                // The inner corner gets cut off where the sides cross.
+                assert!(outline.contains(p(18.5f32, 1.5f32)));
+            }
+        }
+
+        // A closed shape gives a ring, which fills like the stroke.
+        let square = Rectangle::new(p(10f32, 10f32), p(30f32, 30f32));
+        let outline = square.to_fill_outline(&canvas, 4f32);
+        assert_eq!(outline.outline(0.1).len(), 2);
+        outline.fill(&mut canvas);
+        assert!(red(&canvas, 10, 20) > 0.99 && red(&canvas, 20, 10) > 0.99);
+        assert!(red(&canvas, 20, 20) < 0.01 && red(&canvas, 5, 5) < 0.01);
+
+        // Curves are outlined by their offsets on both sides, also through `dyn Curve`.
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(10f32, 20f32), p(20f32, 0f32));
+        let outline = quad.to_fill_outline(&canvas, 4f32).outline(0.1);
+        assert!(outline[0].len() > 4);
+        let curve: &dyn Curve = &quad;
+        assert_eq!(
+            curve.to_fill_outline(&canvas, 4f32).outline(0.1)[0].len(),
+            outline[0].len()
+        );
+    }
+
+    #[test]
+    fn stroke_with_segments() {
+        let quad = QuadBezierCurve::new(p(5f32, 55f32), p(30f32, -40f32), p(55f32, 55f32));
+        let draw = |segments: u64| {
+            let mut canvas = black_canvas(60, 60);
+            quad.stroke_with_segments(&mut canvas, 3f32, segments);
+            canvas
+        };
+        let (coarse, fine) = (draw(4), draw(64));
+        let differing = (0..3600)
+            .filter(|&i| (red(&coarse, i % 60, i / 60) - red(&fine, i % 60, i / 60)).abs() > 0.5)
+            .count();
+        assert!(differing > 30);
+        // The same count always gives the same facets.
+        assert!(coarse.pixels_equal(&draw(4), 0f32));
+
+        // Without any segments there's nothing to draw.
+        let mut canvas = black_canvas(60, 60);
+        let cubic = CubicBezierCurve::new(
+            p(5f32, 55f32),
+            p(5f32, 0f32),
+            p(55f32, 0f32),
+            p(55f32, 55f32),
+        );
+        cubic.stroke_with_segments(&mut canvas, 2f32, 0);
+        assert!(canvas.pixels_equal(&black_canvas(60, 60), 0f32));
+    }
+
+    #[test]
+    fn axis_aligned_lines_are_crisp() {
+        // Counts the fully and the partially covered pixels, and the rows of the full ones.
+        let coverage = |from: Point2, to: Point2, width: f32| {
+            let mut canvas = black_canvas(64, 64);
+            Line::new(from, to).stroke(&mut canvas, width);
+            let (mut full, mut partial, mut rows) = (0, 0, Vec::new());
+            for (_, y, pixel) in canvas.pixels() {
+                if pixel.r > 0.999 {
+                    full += 1;
+                    if !rows.contains(&y) {
+                        rows.push(y);
+                    }
+                } else if pixel.r > 0.001 {
+                    partial += 1;
+                }
+            }
+            (full, partial, rows)
+        };
+        let horizontal = coverage(p(10f32, 10f32), p(50f32, 10f32), 2f32);
+        assert_eq!(horizontal, (80, 0, vec![9, 10]));
+        // With an integral width, the line snaps to the closest rows.
+        let snapped = coverage(p(10f32, 10.3f32), p(50f32, 10.3f32), 2f32);
+        assert_eq!(snapped, (80, 0, vec![9, 10]));
+        assert_eq!(coverage(p(10f32, 10f32), p(50f32, 10f32), 3f32).1, 0);
+        assert_eq!(coverage(p(10f32, 10f32), p(10f32, 50f32), 2f32).1, 0);
+        assert!(coverage(p(10f32, 10.3f32), p(50f32, 10.3f32), 2.5).1 > 0);
+    }
+
+    #[test]
+    fn split_curves() {
+        let near = |a: Point2, b: Point2| a.distance(b) < 1e-4;
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(10f32, 20f32), p(30f32, 5f32));
+        let cubic = CubicBezierCurve::new(
+            p(0f32, 0f32),
+            p(5f32, 30f32),
+            p(25f32, -10f32),
+            p(30f32, 5f32),
+        );
+        let (quad_start, quad_end) = quad.split(0.5);
+        let (cubic_start, cubic_end) = cubic.split(0.5);
+        for i in 0..=10 {
+            let t = i as f32 / 10f32;
+            assert!(near(quad_start.get_point(t), quad.get_point(t * 0.5)));
+            assert!(near(quad_end.get_point(t), quad.get_point(0.5 + t * 0.5)));
+            assert!(near(cubic_start.get_point(t), cubic.get_point(t * 0.5)));
+            assert!(near(cubic_end.get_point(t), cubic.get_point(0.5 + t * 0.5)));
+        }
+
+        let (start, end) = cubic.split(0.3);
+        assert!(near(start.get_point(1f32), cubic.get_point(0.3)));
+        assert!(near(end.get_point(0.5), cubic.get_point(0.65)));
+    }
+
+    #[test]
+    fn intersections() {
+        let diagonal = Line::new(p(0f32, 0f32), p(10f32, 10f32));
+        let crossing = diagonal.intersect_line(&Line::new(p(0f32, 10f32), p(10f32, 0f32)));
+        assert_eq!(crossing.len(), 1);
+        assert!(crossing[0].point.distance(p(5f32, 5f32)) < 1e-4);
+        assert!((crossing[0].t - 0.5).abs() < 1e-5 && (crossing[0].other_t - 0.5).abs() < 1e-5);
+        assert!(diagonal
+            .intersect_line(&Line::new(p(0f32, 1f32), p(10f32, 11f32)))
+            .is_empty());
+        assert!(diagonal
+            .intersect_line(&Line::new(p(6f32, 0f32), p(10f32, 0f32)))
+            .is_empty());
+        assert_eq!(
+            diagonal
+                .intersect_line(&Line::new(p(10f32, 10f32), p(20f32, 0f32)))
+                .len(),
+            1
+        );
+
+        // Lines on top of each other meet at the ends of the part they share.
+        let shared = diagonal.intersect_line(&Line::new(p(15f32, 15f32), p(5f32, 5f32)));
+        assert_eq!(shared.len(), 2);
+        assert!((shared[0].t - 0.5).abs() < 1e-5 && (shared[1].t - 1f32).abs() < 1e-5);
+        assert!((shared[1].other_t - 0.5).abs() < 1e-5);
+        assert_eq!(
+            diagonal
+                .intersect_line(&Line::new(p(10f32, 10f32), p(20f32, 20f32)))
+                .len(),
+            1
+        );
+        assert!(diagonal
+            .intersect_line(&Line::new(p(11f32, 11f32), p(20f32, 20f32)))
+            .is_empty());
+
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(10f32, 20f32), p(20f32, 0f32));
+        let line = Line::new(p(-5f32, 5f32), p(25f32, 5f32));
+        let cuts = line.intersect_quad(&quad);
+        assert_eq!(cuts.len(), 2);
+        assert!(cuts[0].t < cuts[1].t);
+        for cut in cuts.iter() {
+            assert!((cut.point.get_y() - 5f32).abs() < 1e-3);
+            assert!(quad.get_point(cut.other_t).distance(cut.point) < 1e-4);
+            assert!(line.get_point(cut.t).distance(cut.point) < 1e-3);
+        }
+        assert!(Line::new(p(-5f32, 15f32), p(25f32, 15f32))
+            .intersect_quad(&quad)
+            .is_empty());
+        assert_eq!(
+            Line::new(p(-5f32, 10f32), p(25f32, 10f32))
+                .intersect_quad(&quad)
+                .len(),
+            1
+        );
+        assert_eq!(
+            Line::new(p(-5f32, 5f32), p(10f32, 5f32))
+                .intersect_quad(&quad)
+                .len(),
+            1
+        );
+        let flat = QuadBezierCurve::new(p(1f32, 5f32), p(3f32, 5f32), p(30f32, 5f32));
+        assert_eq!(line.intersect_quad(&flat).len(), 1);
+    }
+
+    #[test]
+    fn offset_paths() {
+        let near = |a: Point2, b: Point2| a.distance(b) < 1e-4;
+        let line = Line::new(p(0f32, 10f32), p(20f32, 10f32));
+        let (min, max) = line.offset(3f32).bounding_box();
+        assert!(near(min, p(0f32, 7f32)) && near(max, p(20f32, 7f32)));
+        let (min, max) = line.offset(-3f32).bounding_box();
+        assert!(near(min, p(0f32, 13f32)) && near(max, p(20f32, 13f32)));
+
+        // Closed paths go inwards with negative distances, whichever way they wind.
+        let clockwise = [
+            p(0f32, 0f32),
+            p(20f32, 0f32),
+            p(20f32, 20f32),
+            p(0f32, 20f32),
+        ];
+        let mut counterclockwise = clockwise;
+        counterclockwise.reverse();
+        for corners in [clockwise, counterclockwise].iter() {
+            let square = PathBuilder::new()
+                .move_to(corners[0])
+                .line_to(corners[1])
+                .line_to(corners[2])
+                .line_to(corners[3])
+                .close()
+                .build_closed();
+            let (min, max) = square.offset_within(-5f32, 0.1).bounding_box();
+            assert!(near(min, p(5f32, 5f32)) && near(max, p(15f32, 15f32)));
+            let (min, max) = square.offset_within(2f32, 0.1).bounding_box();
+            assert!(near(min, p(-2f32, -2f32)) && near(max, p(22f32, 22f32)));
+        }
+
+        let polyline = Polyline::new(vec![p(0f32, 0f32), p(10f32, 0f32), p(10f32, 10f32)]);
+        let (min, max) = polyline.offset(-1f32).bounding_box();
+        assert!(near(min, p(0f32, 1f32)) && near(max, p(9f32, 10f32)));
+        let (min, max) = ClosedMultiPath::new(Vec::new()).offset(1f32).bounding_box();
+        assert!(min.get_x() > max.get_x());
+
+        // The shapes grow with positive distances, and their holes shrink.
+        let close = |a: Point2, b: Point2| a.distance(b) < 0.2;
+        let rectangle = Rectangle::new(p(0f32, 0f32), p(20f32, 20f32));
+        let (min, max) = rectangle.offset(2f32).bounding_box();
+        assert!(near(min, p(-2f32, -2f32)) && near(max, p(22f32, 22f32)));
+        let rounded = RoundedRect::new(p(0f32, 0f32), p(20f32, 20f32), 5f32);
+        let (min, max) = rounded.offset(-2f32).bounding_box();
+        assert!(close(min, p(2f32, 2f32)) && close(max, p(18f32, 18f32)));
+        let (min, max) = Circle::new(p(10f32, 10f32), 10f32)
+            .offset(-3f32)
+            .bounding_box();
+        assert!(close(min, p(3f32, 3f32)) && close(max, p(17f32, 17f32)));
+        let (min, max) = Ellipse::new(p(20f32, 10f32), 20f32, 10f32)
+            .offset(2f32)
+            .bounding_box();
+        assert!(close(min, p(-2f32, -2f32)) && close(max, p(42f32, 22f32)));
+
+        let polygon = Polygon::with_holes(
+            vec![
+                p(0f32, 0f32),
+                p(40f32, 0f32),
+                p(40f32, 40f32),
+                p(0f32, 40f32),
+            ],
+            vec![vec![
+                p(10f32, 10f32),
+                p(30f32, 10f32),
+                p(30f32, 30f32),
+                p(10f32, 30f32),
+            ]],
+        );
+        let grown = polygon.offset(2f32);
+        assert_eq!(grown.parts.len(), 8);
+        let (min, max) = grown.bounding_box();
+        assert!(near(min, p(-2f32, -2f32)) && near(max, p(42f32, 42f32)));
+        let (min, max) = grown.parts[4..]
+            .iter()
+            .map(|part| part.bounding_box())
+            .fold(empty_bounding_box(), union_bounding_boxes);
+        assert!(near(min, p(12f32, 12f32)) && near(max, p(28f32, 28f32)));
+
+        // An arc going around clockwise has the outside of its circle on its left.
+        let arc = Arc::new(
+            p(0f32, 0f32),
+            10f32,
+            Angle::from_radians(0f32),
+            Angle::from_radians(std::f32::consts::FRAC_PI_2),
+        );
+        let (min, max) = arc.offset(2f32).bounding_box();
+        assert!(close(min, p(0f32, 0f32)) && close(max, p(12f32, 12f32)));
+    }
+
+    #[test]
+    fn offset_loops_get_cut_out() {
+        let triangle = PathBuilder::new()
+            .move_to(p(0f32, 0f32))
+            .line_to(p(40f32, 0f32))
+            .line_to(p(20f32, 4f32))
+            .close()
+            .build_closed();
+        let inset = triangle.offset_within(-1f32, 0.1).outline(0.1).remove(0);
+        let n = inset.len();
+        assert!(n >= 3);
+        let side = |a: Point2, b: Point2, c: Point2| (b - a).cross(&(c - a));
+        let crosses = |a: Point2, b: Point2, c: Point2, d: Point2| {
+            side(a, b, c) * side(a, b, d) < 0f32 && side(c, d, a) * side(c, d, b) < 0f32
+        };
+        for i in 0..n {
+            for j in i + 2..n {
+                if i == 0 && j == n - 1 {
+                    continue;
+                }
+                assert!(!crosses(
+                    inset[i],
+                    inset[(i + 1) % n],
+                    inset[j],
+                    inset[(j + 1) % n]
+                ));
+            }
+        }
+
+        // Offsetting a curve inwards by more than it bends still gives a finite path.
+        let quad = QuadBezierCurve::new(p(0f32, 0f32), p(10f32, 20f32), p(20f32, 0f32));
+        assert!(quad
+            .offset_within(-8f32, 0.05)
+            .bounding_box()
+            .0
+            .get_y()
+            .is_finite());
+    }
+
+    #[test]
+    fn caps_only_at_the_ends() {
+        // The parts of a path meet at a join, so the caps don't reach around the corner.
+        for &cap in [LineCap::Square, LineCap::Round].iter() {
+            let mut canvas = black_canvas(40, 40);
+            canvas.set_line_cap(cap);
+            canvas.set_line_join(LineJoin::Bevel);
+            let path = OpenMultiPath::new(vec![
+                Box::new(Line::new(p(5f32, 20f32), p(20f32, 20f32))),
+                Box::new(Line::new(p(20f32, 20f32), p(20f32, 35f32))),
+            ]);
+            path.stroke(&mut canvas, 8f32);
+            assert_eq!(red(&canvas, 23, 16), 0f32, "{:?}", cap);
+            assert!(red(&canvas, 2, 20) > 0.5, "{:?}", cap);
+            assert!(red(&canvas, 20, 37) > 0.5, "{:?}", cap);
+        }
+
+        // A translucent stroke going straight on doesn't get any darker at the joint.
+        for &cap in [LineCap::Butt, LineCap::Square, LineCap::Round].iter() {
+            let mut canvas = black_canvas(40, 20);
+            canvas.set_line_cap(cap);
+            canvas.set_draw_color(color_rgba8(255, 255, 255, 128));
+            Polyline::new(vec![p(5f32, 10f32), p(20f32, 10f32), p(35f32, 10f32)])
+                .stroke(&mut canvas, 4f32);
+            let along = red(&canvas, 12, 10);
+            for x in 8..32 {
+                assert!(
+                    (red(&canvas, x, 10) - along).abs() < 1e-4,
+                    "{:?} at {}",
+                    cap,
+                    x
+                );
+            }
+        }
+
+        // Closed paths don't have any ends to put caps on.
+        let square = Rectangle::new(p(10f32, 10f32), p(30f32, 30f32));
+        let mut butt = black_canvas(40, 40);
+        butt.set_line_join(LineJoin::Bevel);
+        square.stroke(&mut butt, 4f32);
+        for &cap in [LineCap::Square, LineCap::Round].iter() {
+            let mut canvas = black_canvas(40, 40);
+            canvas.set_line_join(LineJoin::Bevel);
+            canvas.set_line_cap(cap);
+            square.stroke(&mut canvas, 4f32);
+            assert!(canvas.pixels_equal(&butt, 0f32), "{:?}", cap);
+        }
+    }
 }
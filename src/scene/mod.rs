@@ -0,0 +1,109 @@
+use crate::paint::Paint;
+use crate::path::{Loop, Path};
+use crate::transform::Transform2;
+use crate::Canvas;
+
+/// A list of drawing commands, which can be recorded once and then replayed
+/// onto any amount of canvases.
+#[derive(Default)]
+pub struct Scene {
+    commands: Vec<Command>,
+}
+
+enum Command {
+    SetPaint(Paint),
+    SetTransform(Transform2),
+    Stroke(Box<dyn Path>, f32),
+    Fill(Box<dyn Loop>),
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene {
+            commands: Vec::new(),
+        }
+    }
+
+    pub fn set_draw_color(&mut self, color: colorbuf::Color) -> &mut Scene {
+        self.set_paint(Paint::Solid(color))
+    }
+
+    pub fn set_paint(&mut self, paint: Paint) -> &mut Scene {
+        self.commands.push(Command::SetPaint(paint));
+        self
+    }
+
+    pub fn set_transform(&mut self, transform: Transform2) -> &mut Scene {
+        self.commands.push(Command::SetTransform(transform));
+        self
+    }
+
+    pub fn stroke(&mut self, path: Box<dyn Path>, width: f32) -> &mut Scene {
+        self.commands.push(Command::Stroke(path, width));
+        self
+    }
+
+    pub fn fill(&mut self, shape: Box<dyn Loop>) -> &mut Scene {
+        self.commands.push(Command::Fill(shape));
+        self
+    }
+
+    /// Draws everything in this scene onto the canvas, in the order it was recorded.
+    ///
+    /// The paints and the transforms set by the scene stay on the canvas afterwards, just like
+    /// they would have if they were set on the canvas directly.
+    pub fn render(&self, c: &mut Canvas) {
+        for command in self.commands.iter() {
+            match command {
+                Command::SetPaint(paint) => c.set_paint(paint.clone()),
+                Command::SetTransform(transform) => c.set_transform(*transform),
+                Command::Stroke(path, width) => path.stroke(c, *width),
+                Command::Fill(shape) => shape.fill(c),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color_rgb8;
+    use crate::path::{Circle, Line};
+    use crate::vector::Point2;
+
+    #[test]
+    fn replaying_onto_two_canvases() {
+        let mut scene = Scene::new();
+        scene
+            .set_draw_color(color_rgb8(255, 0, 0))
+            .fill(Box::new(Circle::new(Point2::new(10f32, 10f32), 5f32)))
+            .set_draw_color(color_rgb8(0, 0, 255))
+            .stroke(
+                Box::new(Line::new(
+                    Point2::new(0f32, 0f32),
+                    Point2::new(20f32, 20f32),
+                )),
+                2f32,
+            );
+        let mut first = Canvas::new(20, 20, color_rgb8(0, 0, 0));
+        let mut second = Canvas::new(20, 20, color_rgb8(0, 0, 0));
+        scene.render(&mut first);
+        scene.render(&mut second);
+        assert!(first.pixels_equal(&second, 0f32));
+        assert_eq!(first.get_pixel(13, 10).unwrap().r, 1f32);
+        assert_eq!(first.get_pixel(10, 10).unwrap().b, 1f32);
+    }
+
+    #[test]
+    fn recorded_transform() {
+        let mut scene = Scene::new();
+        scene
+            .set_draw_color(color_rgb8(255, 0, 0))
+            .set_transform(Transform2::translation(10f32, 0f32))
+            .fill(Box::new(Circle::new(Point2::new(5f32, 5f32), 3f32)));
+        let mut canvas = Canvas::new(20, 20, color_rgb8(0, 0, 0));
+        scene.render(&mut canvas);
+        assert_eq!(canvas.get_pixel(15, 5).unwrap().r, 1f32);
+        assert_eq!(canvas.get_pixel(5, 5).unwrap().r, 0f32);
+    }
+}
@@ -0,0 +1,405 @@
+//! Reading paths from the path data of SVG files, as found in the `d`
+//! attribute of their `path` elements.
+
+use crate::path::{
+    empty_bounding_box, union_bounding_boxes, ClosedMultiPath, CubicBezierCurve, Curve, Line, Loop,
+    OpenMultiPath, Path, Polygon, QuadBezierCurve,
+};
+use crate::vector::{Point2, Vector2};
+use crate::Canvas;
+
+/// The reasons why path data couldn't be parsed. The positions are byte offsets
+/// into the path data, pointing to where the problem was found.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ParseError {
+    /// The path data doesn't start with a moveto command.
+    MissingMoveTo,
+    /// The command at the position isn't one of the supported commands.
+    UnsupportedCommand(usize),
+    /// A command is missing some of its numbers, which should be at the position.
+    MissingNumber(usize),
+    /// The number at the position isn't a valid number.
+    InvalidNumber(usize),
+    /// There is something else than a command or a number at the position.
+    UnexpectedCharacter(usize),
+}
+
+/// One of the pieces of a path read from path data, from a moveto up to the next
+/// moveto or Z.
+pub enum Subpath {
+    Open(OpenMultiPath),
+    /// A subpath ended by a Z, whose end gets joined back to its start when stroked.
+    Closed(ClosedMultiPath),
+}
+
+impl Path for Subpath {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        match self {
+            Subpath::Open(path) => path.stroke(c, width),
+            Subpath::Closed(path) => path.stroke(c, width),
+        }
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        match self {
+            Subpath::Open(path) => path.offset(distance),
+            Subpath::Closed(path) => path.offset(distance),
+        }
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        match self {
+            Subpath::Open(path) => path.to_fill_outline(c, width),
+            Subpath::Closed(path) => path.to_fill_outline(c, width),
+        }
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        match self {
+            Subpath::Open(path) => path.bounding_box(),
+            Subpath::Closed(path) => path.bounding_box(),
+        }
+    }
+}
+
+/// A path read from path data, made out of subpaths which are stroked separately,
+/// so that there are no joins between them.
+pub struct SvgPath {
+    subpaths: Vec<Subpath>,
+}
+
+impl SvgPath {
+    pub fn subpaths(&self) -> &[Subpath] {
+        &self.subpaths[..]
+    }
+}
+
+impl Path for SvgPath {
+    fn stroke(&self, c: &mut Canvas, width: f32) {
+        for subpath in self.subpaths.iter() {
+            subpath.stroke(c, width);
+        }
+    }
+
+    fn offset(&self, distance: f32) -> OpenMultiPath {
+        let mut offset = OpenMultiPath::new(Vec::new());
+        for subpath in self.subpaths.iter() {
+            offset.append(subpath.offset(distance));
+        }
+        offset
+    }
+
+    fn to_fill_outline(&self, c: &Canvas, width: f32) -> Polygon {
+        // The outlines of polygons don't depend on the tolerance.
+        let contours = self
+            .subpaths
+            .iter()
+            .flat_map(|subpath| subpath.to_fill_outline(c, width).outline(0f32))
+            .collect();
+        Polygon::from_contours(contours)
+    }
+
+    fn bounding_box(&self) -> (Point2, Point2) {
+        self.subpaths
+            .iter()
+            .map(|subpath| subpath.bounding_box())
+            .fold(empty_bounding_box(), union_bounding_boxes)
+    }
+}
+
+/// Parses SVG path data into a path made out of lines and Bézier curves.
+///
+/// The supported commands are M, L, H, V, C, Q and Z, both in their absolute and in their
+/// relative forms. Like in SVG, the numbers of a command can be repeated to repeat the
+/// command, and the extra points of a moveto are lines. Z takes no numbers, so numbers
+/// right after it are an `UnexpectedCharacter`.
+///
+/// Every moveto starts a new subpath. A Z closes the subpath, so that it gets a join
+/// instead of caps where it gets back to its start, and drawing on after a Z starts
+/// another subpath from there.
+pub fn parse_svg_path(d: &str) -> Result<SvgPath, ParseError> {
+    let mut parser = Parser {
+        bytes: d.as_bytes(),
+        pos: 0,
+    };
+    let mut subpaths = Vec::new();
+    let mut parts: Vec<Box<dyn Curve>> = Vec::new();
+    let origin = Point2::new(0f32, 0f32);
+    let mut current = origin;
+    let mut subpath_start = origin;
+    let mut command = None;
+
+    loop {
+        match parser.next_command() {
+            Some(letter) => {
+                if command.is_none() && letter != b'M' && letter != b'm' {
+                    return Err(ParseError::MissingMoveTo);
+                }
+                command = Some((letter, parser.pos));
+                parser.pos += 1;
+            }
+            None if parser.at_end() => break,
+            None => match command {
+                // Without a new command, the numbers go to another round of the last one.
+                Some((letter, _)) if letter != b'Z' && letter != b'z' && parser.at_number() => {}
+                Some(_) => return Err(ParseError::UnexpectedCharacter(parser.pos)),
+                None => return Err(ParseError::MissingMoveTo),
+            },
+        }
+
+        let (letter, position) = command.unwrap();
+        let offset = if letter.is_ascii_lowercase() {
+            current - origin
+        } else {
+            Vector2::new(0f32, 0f32)
+        };
+
+        match letter.to_ascii_uppercase() {
+            b'M' => {
+                helper_finish_subpath(&mut subpaths, &mut parts, false);
+                current = parser.point()? + offset;
+                subpath_start = current;
+                // The points after the first one draw lines to them.
+                let line = if letter == b'M' { b'L' } else { b'l' };
+                command = Some((line, position));
+            }
+            b'L' => {
+                let end = parser.point()? + offset;
+                parts.push(Box::new(Line::new(current, end)));
+                current = end;
+            }
+            b'H' => {
+                let x = parser.number()? + offset.get_x();
+                let end = Point2::new(x, current.get_y());
+                parts.push(Box::new(Line::new(current, end)));
+                current = end;
+            }
+            b'V' => {
+                let y = parser.number()? + offset.get_y();
+                let end = Point2::new(current.get_x(), y);
+                parts.push(Box::new(Line::new(current, end)));
+                current = end;
+            }
+            b'C' => {
+                let c1 = parser.point()? + offset;
+                let c2 = parser.point()? + offset;
+                let end = parser.point()? + offset;
+                parts.push(Box::new(CubicBezierCurve::new(current, c1, c2, end)));
+                current = end;
+            }
+            b'Q' => {
+                let control = parser.point()? + offset;
+                let end = parser.point()? + offset;
+                parts.push(Box::new(QuadBezierCurve::new(current, control, end)));
+                current = end;
+            }
+            b'Z' => {
+                // The closed path adds the line back to the start by itself.
+                helper_finish_subpath(&mut subpaths, &mut parts, true);
+                current = subpath_start;
+            }
+            _ => return Err(ParseError::UnsupportedCommand(position)),
+        }
+    }
+
+    helper_finish_subpath(&mut subpaths, &mut parts, false);
+    Ok(SvgPath { subpaths })
+}
+
+/// Moves the parts drawn so far into a subpath of their own, unless there are none.
+fn helper_finish_subpath(
+    subpaths: &mut Vec<Subpath>,
+    parts: &mut Vec<Box<dyn Curve>>,
+    closed: bool,
+) {
+    if parts.is_empty() {
+        return;
+    }
+    let parts = std::mem::take(parts);
+    subpaths.push(if closed {
+        Subpath::Closed(ClosedMultiPath::new(parts))
+    } else {
+        Subpath::Open(OpenMultiPath::new(parts))
+    });
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn at_end(&self) -> bool {
+        self.pos >= self.bytes.len()
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
+    /// Skips the whitespace and the commas between commands and numbers.
+    fn skip_separators(&mut self) {
+        while let Some(b) = self.peek() {
+            if !b.is_ascii_whitespace() && b != b',' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// Gets the letter of the next command without moving past it, if there is one next.
+    fn next_command(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.peek().filter(|b| b.is_ascii_alphabetic())
+    }
+
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        match self.peek() {
+            Some(b) => b.is_ascii_digit() || b == b'-' || b == b'+' || b == b'.',
+            None => false,
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while matches!(self.peek(), Some(b) if b.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn number(&mut self) -> Result<f32, ParseError> {
+        if !self.at_number() {
+            return Err(ParseError::MissingNumber(self.pos));
+        }
+
+        // Numbers end wherever something which can't continue them comes up, so that
+        // compact data like `10-5` or `.5.5` gives two numbers.
+        let start = self.pos;
+        if let Some(b'-') | Some(b'+') = self.peek() {
+            self.pos += 1;
+        }
+        let mut digits = self.skip_digits();
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            digits += self.skip_digits();
+        }
+        if digits == 0 {
+            return Err(ParseError::InvalidNumber(start));
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.pos;
+            self.pos += 1;
+            if let Some(b'-') | Some(b'+') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                self.pos = mantissa_end;
+            }
+        }
+
+        // Everything which got read is ASCII, so it's also valid UTF-8.
+        std::str::from_utf8(&self.bytes[start..self.pos])
+            .ok()
+            .and_then(|number| number.parse::<f32>().ok())
+            .ok_or(ParseError::InvalidNumber(start))
+    }
+
+    fn point(&mut self) -> Result<Point2, ParseError> {
+        let x = self.number()?;
+        let y = self.number()?;
+        Ok(Point2::new(x, y))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::color_rgb8;
+
+    #[test]
+    fn closed_triangle_renders() {
+        let path = parse_svg_path("M0,0 L10,0 L10,10 Z").unwrap();
+        assert_eq!(path.subpaths().len(), 1);
+        assert!(matches!(path.subpaths()[0], Subpath::Closed(_)));
+        let bounds = path.bounding_box();
+        assert_eq!(bounds, (Point2::new(0f32, 0f32), Point2::new(10f32, 10f32)));
+
+        let mut canvas = Canvas::new(20, 20, color_rgb8(0, 0, 0));
+        canvas.set_draw_color(color_rgb8(255, 255, 255));
+        let path = parse_svg_path("M4,4 L14,4 L14,14 Z").unwrap();
+        path.stroke(&mut canvas, 1f32);
+        assert!(canvas.get_pixel(9, 4).unwrap().r > 0.4);
+        assert!(canvas.get_pixel(14, 9).unwrap().r > 0.4);
+        assert!(canvas.get_pixel(9, 9).unwrap().r > 0.4);
+        assert!(canvas.get_pixel(6, 12).unwrap().r < 0.01);
+    }
+
+    #[test]
+    fn closing_gets_a_join() {
+        let mut canvas = Canvas::new(20, 20, color_rgb8(0, 0, 0));
+        canvas.enable_antialias(false);
+        canvas.set_draw_color(color_rgb8(255, 255, 255));
+        // With butt caps, only a miter join covers the outer corner at the start.
+        let path = parse_svg_path("M4,4 H16 V16 H4 Z").unwrap();
+        path.stroke(&mut canvas, 4f32);
+        assert_eq!(canvas.get_pixel(2, 2).unwrap().r, 1f32);
+        assert_eq!(canvas.get_pixel(17, 17).unwrap().r, 1f32);
+    }
+
+    #[test]
+    fn subpaths() {
+        let path = parse_svg_path("M0,0 L1,1 Z L2,0 M5,5 L6,6").unwrap();
+        assert_eq!(path.subpaths().len(), 3);
+        assert!(matches!(path.subpaths()[0], Subpath::Closed(_)));
+        // Drawing on after a Z starts from the start of the closed subpath.
+        match &path.subpaths()[1] {
+            Subpath::Open(open) => {
+                let bounds = open.bounding_box();
+                assert_eq!(bounds, (Point2::new(0f32, 0f32), Point2::new(2f32, 0f32)));
+            }
+            Subpath::Closed(_) => panic!("the subpath after Z isn't closed"),
+        }
+        assert!(matches!(path.subpaths()[2], Subpath::Open(_)));
+        assert!(parse_svg_path("").unwrap().subpaths().is_empty());
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse_svg_path("L1 1").err(),
+            Some(ParseError::MissingMoveTo)
+        );
+        assert_eq!(
+            parse_svg_path("M1 1 L2").err(),
+            Some(ParseError::MissingNumber(7))
+        );
+        let arc = parse_svg_path("M1 1 A2 2 0 0 0 3 3");
+        assert_eq!(arc.err(), Some(ParseError::UnsupportedCommand(5)));
+        let after_close = parse_svg_path("M1 1 Z 3");
+        assert_eq!(after_close.err(), Some(ParseError::UnexpectedCharacter(7)));
+        assert_eq!(
+            parse_svg_path("M1 1 L- 3").err(),
+            Some(ParseError::InvalidNumber(6))
+        );
+    }
+
+    #[test]
+    fn relative_commands_and_compact_numbers() {
+        let path = parse_svg_path("m1 1h4v4H1V1zm10,0l1-1 1 1q1 1 2 0c1,1 2,1 3,0M0 0 1e1 .5.5 2")
+            .unwrap();
+        assert_eq!(path.subpaths().len(), 3);
+        let (min, max) = path.bounding_box();
+        assert!(min.distance(Point2::new(0f32, 0f32)) < 1e-4);
+        assert!(max.distance(Point2::new(18f32, 5f32)) < 1e-4);
+
+        // Numbers can run into each other when there's no other way to read them.
+        let path = parse_svg_path("M0 0 1e1 .5.5 2").unwrap();
+        let (min, max) = path.bounding_box();
+        assert_eq!(
+            (min, max),
+            (Point2::new(0f32, 0f32), Point2::new(10f32, 2f32))
+        );
+    }
+}
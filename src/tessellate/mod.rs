@@ -0,0 +1,153 @@
+//! Turning filled shapes into lists of triangles, for drawing them with
+//! something else than a canvas, such as a GPU.
+//!
+//! The shapes must be simple, meaning that their outlines don't cross themselves.
+//! Self-intersecting input isn't supported, and gives an incomplete triangulation.
+
+use crate::path::Curve;
+use crate::vector::Point2;
+
+/// Splits the polygon with the given points into triangles which together
+/// cover the same area. The polygon can be either convex or concave, and its
+/// points can go around in either direction.
+pub fn tessellate_polygon(points: &[Point2]) -> Vec<[Point2; 3]> {
+    let mut ring = Vec::with_capacity(points.len());
+    for &point in points.iter() {
+        if ring.last() != Some(&point) {
+            ring.push(point);
+        }
+    }
+    while ring.len() > 1 && ring.first() == ring.last() {
+        ring.pop();
+    }
+    if helper_signed_area(&ring[..]) < 0f32 {
+        ring.reverse();
+    }
+
+    let mut triangles = Vec::new();
+    // Ear clipping: a corner whose triangle has no other point in it can be cut off
+    // the polygon, and a simple polygon always has such a corner left to cut.
+    while ring.len() > 3 {
+        let len = ring.len();
+        let mut clipped = false;
+
+        for i in 0..len {
+            let (prev, cur, next) = (ring[(i + len - 1) % len], ring[i], ring[(i + 1) % len]);
+            let turn = (cur - prev).cross(&(next - cur));
+            if turn.abs() <= f32::EPSILON {
+                // The point is on the line between its neighbours, so it adds no area.
+                ring.remove(i);
+                clipped = true;
+                break;
+            }
+            if turn < 0f32 {
+                continue;
+            }
+
+            let blocked = ring.iter().enumerate().any(|(j, &p)| {
+                j != i
+                    && j != (i + len - 1) % len
+                    && j != (i + 1) % len
+                    && helper_triangle_contains(prev, cur, next, p)
+            });
+            if !blocked {
+                triangles.push([prev, cur, next]);
+                ring.remove(i);
+                clipped = true;
+                break;
+            }
+        }
+
+        if !clipped {
+            // Only outlines crossing themselves run out of ears.
+            return triangles;
+        }
+    }
+
+    if ring.len() == 3 && helper_signed_area(&ring[..]).abs() > f32::EPSILON {
+        triangles.push([ring[0], ring[1], ring[2]]);
+    }
+    triangles
+}
+
+/// Splits the area enclosed by the given parts of a closed path into triangles,
+/// flattening the curves with the given tolerance, like when they're filled.
+pub fn tessellate_curves(parts: &[Box<dyn Curve>], tolerance: f32) -> Vec<[Point2; 3]> {
+    let points = parts
+        .iter()
+        .flat_map(|part| part.flatten(tolerance))
+        .collect::<Vec<_>>();
+    tessellate_polygon(&points[..])
+}
+
+fn helper_signed_area(points: &[Point2]) -> f32 {
+    let mut area = 0f32;
+    let mut j = points.len().saturating_sub(1);
+    for (i, p_i) in points.iter().enumerate() {
+        let p_j = points[j];
+        area += p_j.get_x() * p_i.get_y() - p_i.get_x() * p_j.get_y();
+        j = i;
+    }
+    area / 2f32
+}
+
+/// Tells whether `p` is inside of or on the edges of the triangle `a`, `b`, `c`,
+/// whose points go around in the direction of positive area.
+fn helper_triangle_contains(a: Point2, b: Point2, c: Point2, p: Point2) -> bool {
+    (b - a).cross(&(p - a)) >= 0f32
+        && (c - b).cross(&(p - b)) >= 0f32
+        && (a - c).cross(&(p - c)) >= 0f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::path::Line;
+
+    fn area(triangles: &[[Point2; 3]]) -> f32 {
+        triangles
+            .iter()
+            .map(|t| ((t[1] - t[0]).cross(&(t[2] - t[0])) / 2f32).abs())
+            .sum()
+    }
+
+    fn p(x: f32, y: f32) -> Point2 {
+        Point2::new(x, y)
+    }
+
+    #[test]
+    fn square_and_l_shape() {
+        let square = [p(0f32, 0f32), p(4f32, 0f32), p(4f32, 4f32), p(0f32, 4f32)];
+        let triangles = tessellate_polygon(&square);
+        assert_eq!(triangles.len(), 2);
+        assert!((area(&triangles) - 16f32).abs() < 1e-4);
+
+        // The winding of the outline doesn't matter.
+        let mut reversed = square.to_vec();
+        reversed.reverse();
+        assert!((area(&tessellate_polygon(&reversed)) - 16f32).abs() < 1e-4);
+
+        let l_shape = [
+            p(0f32, 0f32),
+            p(4f32, 0f32),
+            p(4f32, 1f32),
+            p(1f32, 1f32),
+            p(1f32, 4f32),
+            p(0f32, 4f32),
+        ];
+        let triangles = tessellate_polygon(&l_shape);
+        assert_eq!(triangles.len(), 4);
+        assert!((area(&triangles) - 7f32).abs() < 1e-4);
+    }
+
+    #[test]
+    fn curves() {
+        let corners = [p(0f32, 0f32), p(4f32, 0f32), p(4f32, 4f32), p(0f32, 4f32)];
+        let parts: Vec<Box<dyn Curve>> = (0..4)
+            .map(|i| Box::new(Line::new(corners[i], corners[(i + 1) % 4])) as Box<dyn Curve>)
+            .collect();
+        let triangles = tessellate_curves(&parts, 0.1);
+        assert_eq!(triangles.len(), 2);
+        assert!((area(&triangles) - 16f32).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,127 @@
+use std::ops;
+
+use crate::angle::Angle;
+use crate::vector::{Point2, Vector2};
+
+/// A 2D affine transformation, represented as a 3x3 matrix
+/// operating on homogeneous coordinates.
+#[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Transform2([[f32; 3]; 3]);
+
+impl Transform2 {
+    pub fn identity() -> Transform2 {
+        Transform2 {
+            0: [[1f32, 0f32, 0f32], [0f32, 1f32, 0f32], [0f32, 0f32, 1f32]],
+        }
+    }
+
+    pub fn translation(dx: f32, dy: f32) -> Transform2 {
+        Transform2 {
+            0: [[1f32, 0f32, dx], [0f32, 1f32, dy], [0f32, 0f32, 1f32]],
+        }
+    }
+
+    /// Rotates counter-clockwise by the given angle, assuming that the y-axis points up.
+    /// With the y-axis pointing down, like on a canvas, this rotates clockwise.
+    pub fn rotation(angle: Angle) -> Transform2 {
+        let (sin, cos) = angle.as_radians().sin_cos();
+        Transform2 {
+            0: [[cos, -sin, 0f32], [sin, cos, 0f32], [0f32, 0f32, 1f32]],
+        }
+    }
+
+    pub fn scale(sx: f32, sy: f32) -> Transform2 {
+        Transform2 {
+            0: [[sx, 0f32, 0f32], [0f32, sy, 0f32], [0f32, 0f32, 1f32]],
+        }
+    }
+
+    /// Composes this transform with `other`, so that the resulting transform
+    /// first applies `self` and then `other`.
+    ///
+    /// In terms of matrices, `a.then(&b)` is the product `B * A`.
+    pub fn then(&self, other: &Transform2) -> Transform2 {
+        *other * *self
+    }
+
+    pub fn apply(&self, p: Point2) -> Point2 {
+        let m = &self.0;
+        let x = m[0][0] * p.get_x() + m[0][1] * p.get_y() + m[0][2];
+        let y = m[1][0] * p.get_x() + m[1][1] * p.get_y() + m[1][2];
+        Point2::new(x, y)
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Transform2::identity()
+    }
+
+    /// Gets the scale and the rotation of this transform if it keeps circles circular,
+    /// which it does if it only translates, rotates and scales evenly along both axes.
+    pub(crate) fn similarity(&self) -> Option<(f32, f32)> {
+        let m = &self.0;
+        let (a, b, c, d) = (m[0][0], m[0][1], m[1][0], m[1][1]);
+        let scale = (a * a + c * c).sqrt();
+        let tolerance = 1e-5f32 * scale.max(1f32);
+        if (a - d).abs() > tolerance || (b + c).abs() > tolerance || scale <= 0f32 {
+            return None;
+        }
+        Some((scale, c.atan2(a)))
+    }
+
+    /// Gets an upper bound for how much this transform can stretch a vector.
+    pub(crate) fn max_stretch(&self) -> f32 {
+        let m = &self.0;
+        (m[0][0] * m[0][0] + m[0][1] * m[0][1] + m[1][0] * m[1][0] + m[1][1] * m[1][1]).sqrt()
+    }
+
+    /// Applies this transform to a vector. Unlike with points,
+    /// the translation part of the transform is ignored.
+    pub fn apply_vector(&self, v: Vector2) -> Vector2 {
+        let m = &self.0;
+        let x = m[0][0] * v.get_x() + m[0][1] * v.get_y();
+        let y = m[1][0] * v.get_x() + m[1][1] * v.get_y();
+        Vector2::new(x, y)
+    }
+}
+
+impl ops::Mul<Transform2> for Transform2 {
+    type Output = Transform2;
+
+    fn mul(self, rhs: Transform2) -> Transform2 {
+        let mut ret = [[0f32; 3]; 3];
+        for (row, ret_row) in ret.iter_mut().enumerate() {
+            for (col, entry) in ret_row.iter_mut().enumerate() {
+                *entry = (0..3).map(|k| self.0[row][k] * rhs.0[k][col]).sum();
+            }
+        }
+        Transform2 { 0: ret }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_and_composition() {
+        let quarter = Transform2::rotation(Angle::from_degrees(90f32));
+        let p = quarter.apply(Point2::new(1f32, 0f32));
+        assert!(p.distance(Point2::new(0f32, 1f32)) < 1e-6);
+
+        // Translating first and then scaling scales the translation too.
+        let translate = Transform2::translation(1f32, 0f32);
+        let scale = Transform2::scale(2f32, 2f32);
+        let both = translate.then(&scale);
+        assert_eq!(both, scale * translate);
+        assert_eq!(both.apply(Point2::new(1f32, 1f32)), Point2::new(4f32, 2f32));
+        let other_way = scale.then(&translate);
+        assert_eq!(
+            other_way.apply(Point2::new(1f32, 1f32)),
+            Point2::new(3f32, 2f32)
+        );
+
+        let v = both.apply_vector(Vector2::new(1f32, 1f32));
+        assert_eq!(v, Vector2::new(2f32, 2f32));
+    }
+}
@@ -1,9 +1,12 @@
 use std::ops;
 
+// Being newtypes, the points and the vectors get serialized as just their `[x, y]` arrays.
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Point2([f32; 2]);
 
 #[derive(Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector2([f32; 2]);
 
 impl Point2 {
@@ -18,6 +21,43 @@ impl Point2 {
     pub fn get_y(&self) -> f32 {
         self.0[1]
     }
+
+    /// Linearly interpolates between this point and `other`, so that
+    /// `t = 0` gives this point and `t = 1` gives `other`.
+    pub fn lerp(&self, other: Point2, t: f32) -> Point2 {
+        let x = (1f32 - t) * self.0[0] + t * other.0[0];
+        let y = (1f32 - t) * self.0[1] + t * other.0[1];
+        Point2 { 0: [x, y] }
+    }
+
+    pub fn to_vector(&self) -> Vector2 {
+        Vector2 { 0: self.0 }
+    }
+
+    pub fn distance(&self, other: Point2) -> f32 {
+        (other - *self).length()
+    }
+
+    /// Gets the point with the smaller of the coordinates of the points along each axis.
+    pub fn min(&self, other: Point2) -> Point2 {
+        Point2 {
+            0: [self.0[0].min(other.0[0]), self.0[1].min(other.0[1])],
+        }
+    }
+
+    /// Gets the point with the larger of the coordinates of the points along each axis.
+    pub fn max(&self, other: Point2) -> Point2 {
+        Point2 {
+            0: [self.0[0].max(other.0[0]), self.0[1].max(other.0[1])],
+        }
+    }
+
+    /// Moves this point into the axis-aligned box with `min` and `max` as its corners,
+    /// clamping each coordinate on its own. If `min` is past `max` along an axis,
+    /// the coordinate along that axis is taken from `max`.
+    pub fn clamp(&self, min: Point2, max: Point2) -> Point2 {
+        self.max(min).min(max)
+    }
 }
 
 impl Vector2 {
@@ -25,6 +65,20 @@ impl Vector2 {
         Vector2 { 0: [x, y] }
     }
 
+    /// Gets the vector with the smaller of the components of the vectors along each axis.
+    pub fn min(&self, other: Vector2) -> Vector2 {
+        Vector2 {
+            0: [self.0[0].min(other.0[0]), self.0[1].min(other.0[1])],
+        }
+    }
+
+    /// Gets the vector with the larger of the components of the vectors along each axis.
+    pub fn max(&self, other: Vector2) -> Vector2 {
+        Vector2 {
+            0: [self.0[0].max(other.0[0]), self.0[1].max(other.0[1])],
+        }
+    }
+
     pub fn dot(&self, other: &Vector2) -> f32 {
         self.0[0] * other.0[0] + self.0[1] * other.0[1]
     }
@@ -37,18 +91,54 @@ impl Vector2 {
         ((self.0[0] * self.0[0]) + (self.0[1] * self.0[1])).sqrt()
     }
 
+    /// The angle of this vector from the positive x-axis in radians, within `[-pi, pi]`.
+    pub fn angle(&self) -> f32 {
+        self.0[1].atan2(self.0[0])
+    }
+
+    /// The signed angle in radians that this vector needs to be turned by
+    /// to point in the direction of `other`, within `[-pi, pi]`.
+    pub fn angle_between(&self, other: &Vector2) -> f32 {
+        self.cross(other).atan2(self.dot(other))
+    }
+
+    /// Gets the vector perpendicular to this one, `(y, -x)`.
+    pub fn perpendicular(&self) -> Vector2 {
+        Vector2 {
+            0: [self.0[1], -self.0[0]],
+        }
+    }
+
+    /// Rotates this vector by the given angle in radians, from the
+    /// positive x-axis towards the positive y-axis.
+    pub fn rotate(&self, radians: f32) -> Vector2 {
+        let (sin, cos) = radians.sin_cos();
+        Vector2 {
+            0: [
+                self.0[0] * cos - self.0[1] * sin,
+                self.0[0] * sin + self.0[1] * cos,
+            ],
+        }
+    }
+
+    /// Gets the vector with the same direction as this one, but with a length of 1.
+    ///
+    /// A zero vector has no direction, so for vectors too short to have a meaningful
+    /// direction this returns the zero vector instead of a vector full of NaNs.
     pub fn unit(&self) -> Vector2 {
-        let new_x = self.0[0] / self.length();
-        let new_y = self.0[1] / self.length();
+        let length = self.length();
+        if length < f32::EPSILON {
+            return Vector2 { 0: [0f32, 0f32] };
+        }
+
+        let new_x = self.0[0] / length;
+        let new_y = self.0[1] / length;
         Vector2 { 0: [new_x, new_y] }
     }
 
+    /// Makes this vector a unit vector. See `unit` for how zero vectors are handled.
     pub fn to_unit(&mut self) {
-        let new_x = self.0[0] / self.length();
-        let new_y = self.0[1] / self.length();
-
-        self.0[0] = new_x;
-        self.0[1] = new_y;
+        *self = self.unit();
     }
 
     pub fn get_x(&self) -> f32 {
@@ -58,6 +148,10 @@ impl Vector2 {
     pub fn get_y(&self) -> f32 {
         self.0[1]
     }
+
+    pub fn to_point(&self) -> Point2 {
+        Point2 { 0: self.0 }
+    }
 }
 
 impl ops::Add<Vector2> for Vector2 {
@@ -135,3 +229,135 @@ impl ops::Sub<Vector2> for Point2 {
         self + (-rhs)
     }
 }
+
+impl From<(f32, f32)> for Point2 {
+    fn from((x, y): (f32, f32)) -> Point2 {
+        Point2 { 0: [x, y] }
+    }
+}
+
+impl From<[f32; 2]> for Point2 {
+    fn from(coords: [f32; 2]) -> Point2 {
+        Point2 { 0: coords }
+    }
+}
+
+impl From<Point2> for [f32; 2] {
+    fn from(p: Point2) -> [f32; 2] {
+        p.0
+    }
+}
+
+impl From<(f32, f32)> for Vector2 {
+    fn from((x, y): (f32, f32)) -> Vector2 {
+        Vector2 { 0: [x, y] }
+    }
+}
+
+impl From<[f32; 2]> for Vector2 {
+    fn from(coords: [f32; 2]) -> Vector2 {
+        Vector2 { 0: coords }
+    }
+}
+
+impl From<Vector2> for [f32; 2] {
+    fn from(v: Vector2) -> [f32; 2] {
+        v.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lerp_and_conversions() {
+        let (a, b) = (Point2::new(1f32, 2f32), Point2::new(5f32, -2f32));
+        assert_eq!(a.lerp(b, 0f32), a);
+        assert_eq!(a.lerp(b, 1f32), b);
+        assert_eq!(a.lerp(b, 0.5), Point2::new(3f32, 0f32));
+
+        assert_eq!(a.to_vector().to_point(), a);
+        let v = Vector2::new(-3f32, 4f32);
+        assert_eq!(v.to_point().to_vector(), v);
+    }
+
+    #[test]
+    fn distances_and_angles() {
+        use std::f32::consts::FRAC_PI_2;
+
+        let origin = Point2::new(0f32, 0f32);
+        assert_eq!(origin.distance(Point2::new(3f32, 4f32)), 5f32);
+        assert_eq!(Vector2::new(0f32, 1f32).angle(), FRAC_PI_2);
+
+        let (x, y) = (Vector2::new(1f32, 0f32), Vector2::new(0f32, 1f32));
+        assert_eq!(x.angle_between(&y), FRAC_PI_2);
+        assert_eq!(y.angle_between(&x), -FRAC_PI_2);
+    }
+
+    #[test]
+    fn perpendicular_and_rotate() {
+        let x = Vector2::new(1f32, 0f32);
+        assert_eq!(x.perpendicular(), Vector2::new(0f32, -1f32));
+        let rotated = x.rotate(std::f32::consts::FRAC_PI_2);
+        assert!((rotated - Vector2::new(0f32, 1f32)).length() < 1e-6);
+    }
+
+    #[test]
+    fn unit_of_zero_vector() {
+        let unit = Vector2::new(0f32, 0f32).unit();
+        assert_eq!(unit, Vector2::new(0f32, 0f32));
+        let mut v = Vector2::new(0f32, 0f32);
+        v.to_unit();
+        assert!(v.get_x().is_finite() && v.get_y().is_finite());
+        assert_eq!(Vector2::new(3f32, 4f32).unit(), Vector2::new(0.6, 0.8));
+    }
+
+    #[test]
+    fn tuple_and_array_conversions() {
+        use crate::path::{Curve, Line};
+
+        let from_tuple: Point2 = (100f32, 200f32).into();
+        let from_array: Point2 = [100f32, 200f32].into();
+        assert_eq!(from_tuple, from_array);
+        assert_eq!((from_tuple.get_x(), from_tuple.get_y()), (100f32, 200f32));
+        let array: [f32; 2] = from_tuple.into();
+        assert_eq!(array, [100f32, 200f32]);
+
+        let from_tuple: Vector2 = (1f32, -2f32).into();
+        assert_eq!(from_tuple, Vector2::from([1f32, -2f32]));
+        let array: [f32; 2] = from_tuple.into();
+        assert_eq!(array, [1f32, -2f32]);
+
+        let line = Line::new((0f32, 0f32).into(), [5f32, 5f32].into());
+        assert_eq!(line.get_point(0.5), Point2::new(2.5, 2.5));
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn point_json_round_trip() {
+        let point = Point2::new(1.5f32, -2f32);
+        let json = serde_json::to_string(&point).unwrap();
+        assert_eq!(json, "[1.5,-2.0]");
+        assert_eq!(serde_json::from_str::<Point2>(&json).unwrap(), point);
+    }
+
+    #[test]
+    fn min_max_and_clamp() {
+        let (a, b) = (Vector2::new(1f32, 4f32), Vector2::new(3f32, 2f32));
+        assert_eq!(a.min(b), Vector2::new(1f32, 2f32));
+        assert_eq!(a.max(b), Vector2::new(3f32, 4f32));
+        let min = Point2::new(1f32, 4f32).min(Point2::new(3f32, 2f32));
+        assert_eq!(min, Point2::new(1f32, 2f32));
+
+        let (low, high) = (Point2::new(0f32, 0f32), Point2::new(5f32, 10f32));
+        let at_bound = Point2::new(5f32, 5f32);
+        assert_eq!(at_bound.clamp(low, high), at_bound);
+        let beyond = Point2::new(-3f32, 12f32);
+        assert_eq!(beyond.clamp(low, high), Point2::new(0f32, 10f32));
+        // When the bounds are the wrong way around, the upper one wins.
+        let swapped =
+            Point2::new(3f32, 3f32).clamp(Point2::new(4f32, 0f32), Point2::new(2f32, 10f32));
+        assert_eq!(swapped, Point2::new(2f32, 3f32));
+    }
+}